@@ -1,44 +1,246 @@
 pub mod secret;
 
 use serde::{Deserialize, Serialize};
-use crate::error::Result;
+use std::collections::HashMap;
+use crate::error::{AppError, Result};
+use secret::SecretSource;
+use crate::debug::DebugConfig;
+use crate::metrics::MetricsConfig;
+use crate::outbound::OutboundLimit;
+use crate::pipeline::dead_letter::DeadLetterConfig;
+use crate::pipeline::retry_queue::RetryQueueConfig;
+use crate::pipeline::status::HealthConfig;
 use crate::sources::jira::JiraSourceConfig;
-use crate::pipeline::processors::ProcessorConfig;
-use crate::pipeline::sinks::SinkConfig;
+use crate::pipeline::processors::{PipelineProcessor, ProcessorConfig};
+use crate::pipeline::sinks::{BuiltinSinkConfig, ConnectRetry, PipelineSink, SinkConfig};
+use crate::server::tls::TlsConfig;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AppConfig {
     pub integrations: Vec<Integration>,
+    /// Prefix for every source webhook path, e.g. `/webhooks`; falls back to `BASE_PATH`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_path: Option<String>,
+    /// Enables the `/-/debug/failures` buffer
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debug: Option<DebugConfig>,
+    /// Backoff for sinks that can't be connected at startup
+    #[serde(default)]
+    pub sink_connect_retry: ConnectRetry,
+    /// Serve HTTPS instead of plain HTTP, optionally requiring client certificates
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+    /// Keep events that failed in a pipeline, within these bounds
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dead_letter: Option<DeadLetterConfig>,
+    /// Persist failed sink writes in MongoDB and retry them in the background
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_queue: Option<RetryQueueConfig>,
+    /// When `/-/healthz` starts failing
+    #[serde(default)]
+    pub health: HealthConfig,
+    /// Events accepted but not yet processed by every pipeline; sources answer `429` beyond it
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_in_flight: Option<usize>,
+    /// Push metrics to StatsD besides serving them on `/-/metrics`
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    /// Outbound HTTP requests in flight, e.g. from `jiraCustomFields`; unbounded if unset
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outbound_limit: Option<OutboundLimit>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Integration {
     pub source: SourceConfig,
+    /// Processors run ahead of every pipeline's own, e.g. a shared redaction step
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub default_processors: Vec<PipelineProcessor>,
     #[serde(default)]
     pub pipelines: Vec<Pipeline>,
+    /// Event type to the names of the pipelines it is dispatched to; without routes every
+    /// pipeline receives every event
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub routes: HashMap<String, Vec<String>>,
+    /// Pipelines receiving event types missing from `routes`; such events are not processed if unset
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_route: Option<Vec<String>>,
+}
+
+impl Integration {
+    /// A pipeline as it runs: the integration's `default_processors` ahead of its own, unless it
+    /// opts out
+    pub fn with_default_processors(&self, pipeline: &Pipeline) -> Pipeline {
+        let mut pipeline = pipeline.clone();
+        if !pipeline.skip_default_processors && !self.default_processors.is_empty() {
+            pipeline.processors = self.default_processors.iter().cloned().chain(pipeline.processors).collect();
+        }
+        pipeline
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Pipeline {
+    /// Name used to refer to the pipeline in the integration's `routes`
     #[serde(default)]
-    pub processors: Vec<ProcessorConfig>,
-    pub sinks: Vec<SinkConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub processors: Vec<PipelineProcessor>,
+    pub sinks: Vec<PipelineSink>,
+    /// Event run through the processors at startup to catch template/logic errors early
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_event: Option<SampleEvent>,
+    /// Upper bound for running one event through the processors and sinks; unbounded if unset
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_processing_ms: Option<u64>,
+    /// Events of the pipeline processed at the same time, each by its own worker
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+    /// Events with the same key go to the same worker, so they are processed in the order received
+    #[serde(default)]
+    pub ordering_key: OrderingKey,
+    /// How long an event waits for room in a full queue of the pipeline before it is refused and
+    /// its source answers `503`
+    #[serde(default = "default_queue_timeout_ms")]
+    pub queue_timeout_ms: u64,
+    /// Version of the document shape, stamped into every body the pipeline writes so consumers
+    /// and migrations can tell shapes apart
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
+    /// Top-level body key `schema_version` is stamped under
+    #[serde(default = "default_schema_version_key")]
+    pub schema_version_key: String,
+    /// Run only the pipeline's own processors, not the integration's `default_processors`
+    #[serde(default)]
+    pub skip_default_processors: bool,
+}
+
+fn default_schema_version_key() -> String {
+    "schemaVersion".to_string()
+}
+
+fn default_concurrency() -> usize {
+    1
+}
+
+fn default_queue_timeout_ms() -> u64 {
+    1000
+}
+
+/// What decides which events of a concurrent pipeline must stay in order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderingKey {
+    /// The event id, derived from the primary keys, so each document's events stay in order
+    #[default]
+    EventId,
+    /// The value at a body path (dotted or JSON Pointer); events without it use their id
+    Path(String),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SampleEvent {
+    pub event_type: String,
+    pub body: serde_json::Value,
 }
 
+/// Source of an integration, selected by its `type` tag
+///
+/// Types other than the built-in ones keep their settings as JSON, to be handled by a source
+/// registered in the [`SourceRegistry`](crate::sources::SourceRegistry).
 #[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(tag = "type")]
+#[serde(try_from = "serde_json::Value", into = "serde_json::Value")]
 pub enum SourceConfig {
-    #[serde(rename = "jira")]
-    Jira(JiraSourceConfig),
+    Jira(Box<JiraSourceConfig>),
+    Custom {
+        source_type: String,
+        /// The source object without its `type`
+        settings: serde_json::Map<String, serde_json::Value>,
+    },
+}
+
+impl SourceConfig {
+    /// The `type` tag, used to look the source up in the registry
+    pub fn source_type(&self) -> &str {
+        match self {
+            SourceConfig::Jira(_) => "jira",
+            SourceConfig::Custom { source_type, .. } => source_type,
+        }
+    }
+}
+
+impl TryFrom<serde_json::Value> for SourceConfig {
+    type Error = AppError;
+    
+    fn try_from(value: serde_json::Value) -> Result<Self> {
+        let serde_json::Value::Object(mut settings) = value else {
+            return Err(AppError::Config("source must be an object".to_string()));
+        };
+        
+        let source_type = match settings.remove("type") {
+            Some(serde_json::Value::String(source_type)) => source_type,
+            _ => return Err(AppError::Config("source is missing its type".to_string())),
+        };
+        
+        match source_type.as_str() {
+            "jira" => Ok(SourceConfig::Jira(serde_json::from_value(settings.into())?)),
+            _ => Ok(SourceConfig::Custom { source_type, settings }),
+        }
+    }
+}
+
+impl From<SourceConfig> for serde_json::Value {
+    fn from(config: SourceConfig) -> Self {
+        let source_type = config.source_type().to_string();
+        let mut settings = match config {
+            // Settings are a plain struct with string keys, which always serializes to an object
+            SourceConfig::Jira(jira) => match serde_json::to_value(jira).expect("Jira source settings serialize to JSON") {
+                serde_json::Value::Object(settings) => settings,
+                other => unreachable!("Jira source settings serialized to {}", other),
+            },
+            SourceConfig::Custom { settings, .. } => settings,
+        };
+        
+        settings.insert("type".to_string(), serde_json::Value::String(source_type));
+        serde_json::Value::Object(settings)
+    }
 }
 
 impl AppConfig {
+    /// Load a JSON configuration, or TOML when the file has a `.toml` extension
     pub fn from_file(path: &str) -> Result<Self> {
         let content = std::fs::read_to_string(path)?;
+        
+        let is_toml = std::path::Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+        if is_toml {
+            return Self::from_toml(&content);
+        }
+        
         let config: AppConfig = serde_json::from_str(&content)?;
         Ok(config)
     }
     
+    pub fn from_toml(content: &str) -> Result<Self> {
+        toml::from_str(content)
+            .map_err(|e| AppError::Config(format!("Invalid TOML configuration: {}", e)))
+    }
+    
     pub fn from_env() -> Result<Self> {
         let config_path = std::env::var("CONFIGURATION_PATH")
             .unwrap_or_else(|_| "config/config.json".to_string());
@@ -52,8 +254,183 @@ impl AppConfig {
             .unwrap_or(3000)
     }
     
+    pub fn get_base_path(&self) -> Option<String> {
+        self.base_path
+            .clone()
+            .or_else(|| std::env::var("BASE_PATH").ok())
+    }
+    
+    /// Default upper bound for a single sink write, overridable per sink
+    pub fn sink_timeout() -> std::time::Duration {
+        let millis = std::env::var("SINK_TIMEOUT_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(30_000);
+        std::time::Duration::from_millis(millis)
+    }
+    
+    /// The configuration as the service runs it, for `--print-effective-config`: defaults filled
+    /// in, the base path and webhook paths resolved, and inline secrets redacted
+    ///
+    /// Settings of custom sources and sinks are kept as they are, since their secrets aren't known.
+    pub fn effective(&self) -> AppConfig {
+        let mut config = self.clone();
+        config.base_path = self.get_base_path();
+        
+        if let Some(debug) = &mut config.debug {
+            debug.token = debug.token.redacted();
+        }
+        if let Some(retry_queue) = &mut config.retry_queue {
+            retry_queue.url = retry_queue.url.redacted();
+        }
+        
+        for integration in &mut config.integrations {
+            if let SourceConfig::Jira(jira) = &mut integration.source {
+                jira.webhook_path = Some(jira.get_webhook_path());
+                jira.authentication.secret = jira.authentication.secret.redacted();
+                jira.authentication.auth_bypass_secret = jira.authentication.auth_bypass_secret.as_ref().map(SecretSource::redacted);
+            }
+            
+            integration.default_processors.iter_mut().for_each(redact_processor);
+            for pipeline in &mut integration.pipelines {
+                pipeline.processors.iter_mut().for_each(redact_processor);
+                
+                for sink in &mut pipeline.sinks {
+                    let SinkConfig::Builtin(builtin) = &mut sink.config else {
+                        continue;
+                    };
+                    match builtin.as_mut() {
+                        BuiltinSinkConfig::Mongo { url, username, password, .. } => {
+                            for secret in [url, username, password] {
+                                *secret = secret.as_ref().map(SecretSource::redacted);
+                            }
+                        }
+                        BuiltinSinkConfig::Nats { credentials, .. } => {
+                            *credentials = credentials.as_ref().map(SecretSource::redacted);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        
+        config
+    }
+    
     pub fn mongodb_url() -> Result<String> {
         std::env::var("MONGO_URL")
-            .map_err(|_| crate::error::AppError::Config("MONGO_URL environment variable is required".to_string()))
+            .map_err(|_| AppError::Config("MONGO_URL environment variable is required".to_string()))
+    }
+}
+
+fn redact_processor(processor: &mut PipelineProcessor) {
+    match &mut processor.config {
+        ProcessorConfig::JiraCustomFields { field_api: Some(field_api), .. } => {
+            field_api.email = field_api.email.as_ref().map(SecretSource::redacted);
+            field_api.api_token = field_api.api_token.redacted();
+        }
+        ProcessorConfig::Anonymize { salt, .. } => *salt = salt.redacted(),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_toml_config_matches_json() {
+        let json = r#"{
+            "integrations": [{
+                "source": {
+                    "type": "jira",
+                    "webhook_path": "/jira/webhook",
+                    "authentication": { "secret": { "fromEnv": "JIRA_WEBHOOK_SECRET" } },
+                    "primary_key_paths": { "jira:issue_created": ["issue.id", "/issue/key"] }
+                },
+                "pipelines": [{
+                    "processors": [
+                        { "type": "filter", "celExpression": "eventType == 'jira:issue_created'" },
+                        { "type": "mapper", "outputEvent": { "key": "{{ issue.key }}", "id": { "value": "{{ issue.id }}", "castTo": "integer" } } }
+                    ],
+                    "sinks": [
+                        { "type": "mongo", "url": { "fromEnv": "MONGO_URL" }, "collection": "issues", "string_id": true, "timeout_ms": 5000, "required": true },
+                        { "type": "database", "provider": "MONGO", "tap": true }
+                    ]
+                }]
+            }]
+        }"#;
+        
+        let toml = r#"
+            [[integrations]]
+            [integrations.source]
+            type = "jira"
+            webhook_path = "/jira/webhook"
+            authentication = { secret = { fromEnv = "JIRA_WEBHOOK_SECRET" } }
+            primary_key_paths = { "jira:issue_created" = ["issue.id", "/issue/key"] }
+            
+            [[integrations.pipelines]]
+            
+            [[integrations.pipelines.processors]]
+            type = "filter"
+            celExpression = "eventType == 'jira:issue_created'"
+            
+            [[integrations.pipelines.processors]]
+            type = "mapper"
+            outputEvent = { key = "{{ issue.key }}", id = { value = "{{ issue.id }}", castTo = "integer" } }
+            
+            [[integrations.pipelines.sinks]]
+            type = "mongo"
+            url = { fromEnv = "MONGO_URL" }
+            collection = "issues"
+            string_id = true
+            timeout_ms = 5000
+            required = true
+            
+            [[integrations.pipelines.sinks]]
+            type = "database"
+            provider = "MONGO"
+            tap = true
+        "#;
+        
+        let from_json: AppConfig = serde_json::from_str(json).unwrap();
+        let from_toml = AppConfig::from_toml(toml).unwrap();
+        
+        assert_eq!(
+            serde_json::to_value(&from_toml).unwrap(),
+            serde_json::to_value(&from_json).unwrap()
+        );
+    }
+    
+    #[test]
+    fn test_effective_config_has_defaults_and_redacted_secrets() {
+        let config: AppConfig = serde_json::from_str(r#"{
+            "base_path": "/webhooks",
+            "integrations": [{
+                "source": { "type": "jira", "authentication": { "secret": "inline-secret" } },
+                "pipelines": [{
+                    "processors": [{ "type": "anonymize", "paths": ["issue.id"], "salt": "inline-salt" }],
+                    "sinks": [{ "type": "mongo", "url": { "fromEnv": "MONGO_URL" }, "password": "inline-password", "collection": "issues" }]
+                }]
+            }]
+        }"#).unwrap();
+        
+        let printed = serde_json::to_value(config.effective()).unwrap();
+        let source = &printed["integrations"][0]["source"];
+        let pipeline = &printed["integrations"][0]["pipelines"][0];
+        
+        assert_eq!(printed["base_path"], "/webhooks");
+        assert_eq!(source["webhook_path"], "/jira/webhook");
+        assert_eq!(source["authentication"]["header_name"], "X-Hub-Signature");
+        assert_eq!(source["event_type_paths"], serde_json::json!(["webhookEvent"]));
+        assert_eq!(pipeline["concurrency"], 1);
+        assert_eq!(pipeline["processors"][0]["onProcessorError"], "fail");
+        
+        assert_eq!(source["authentication"]["secret"], secret::REDACTED);
+        assert_eq!(pipeline["processors"][0]["salt"], secret::REDACTED);
+        assert_eq!(pipeline["sinks"][0]["password"], secret::REDACTED);
+        // References only tell where the secret lives
+        assert_eq!(pipeline["sinks"][0]["url"], serde_json::json!({ "fromEnv": "MONGO_URL" }));
+        assert!(!printed.to_string().contains("inline-"));
     }
 }
@@ -10,12 +10,34 @@ pub enum SecretSource {
         #[serde(rename = "fromEnv")]
         from_env: String 
     },
+    /// One key of a file holding several secrets; listed before `FromFile`, which would otherwise match first
+    FromFileKey {
+        #[serde(rename = "fromFile")]
+        from_file: String,
+        key: String,
+        /// Defaults to `json` for `.json` files and `dotenv` otherwise
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        format: Option<SecretFileFormat>,
+    },
     FromFile { 
         #[serde(rename = "fromFile")]
         from_file: String 
     },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretFileFormat {
+    /// `KEY=value` lines
+    Dotenv,
+    /// A JSON object of keys to values
+    Json,
+}
+
+/// Shown in place of inline secrets in printed configurations
+pub const REDACTED: &str = "<redacted>";
+
 impl SecretSource {
     pub fn resolve(&self) -> Result<String> {
         match self {
@@ -24,6 +46,25 @@ impl SecretSource {
                 std::env::var(from_env)
                     .map_err(|_| AppError::SecretNotFound(from_env.clone()))
             }
+            SecretSource::FromFileKey { from_file, key, format } => {
+                let content = fs::read_to_string(from_file)
+                    .map_err(|_| AppError::SecretNotFound(from_file.clone()))?;
+                
+                let format = format.unwrap_or_else(|| {
+                    if from_file.ends_with(".json") {
+                        SecretFileFormat::Json
+                    } else {
+                        SecretFileFormat::Dotenv
+                    }
+                });
+                
+                let value = match format {
+                    SecretFileFormat::Dotenv => Self::dotenv_value(&content, key),
+                    SecretFileFormat::Json => Self::json_value(&content, key),
+                };
+                
+                value.ok_or_else(|| AppError::SecretNotFound(format!("{} in {}", key, from_file)))
+            }
             SecretSource::FromFile { from_file } => {
                 fs::read_to_string(from_file)
                     .map(|s| s.trim().to_string())
@@ -31,4 +72,91 @@ impl SecretSource {
             }
         }
     }
+    
+    fn dotenv_value(content: &str, key: &str) -> Option<String> {
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.strip_prefix("export ").unwrap_or(line).split_once('='))
+            .find(|(name, _)| name.trim() == key)
+            .map(|(_, value)| {
+                let value = value.trim();
+                let unquoted = value
+                    .strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+                    .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                    .unwrap_or(value);
+                unquoted.to_string()
+            })
+    }
+    
+    /// The source with an inline value replaced by `REDACTED`; env and file references are kept,
+    /// as they only tell where the secret lives
+    pub fn redacted(&self) -> Self {
+        match self {
+            SecretSource::Plain(_) => SecretSource::Plain(REDACTED.to_string()),
+            other => other.clone(),
+        }
+    }
+    
+    fn json_value(content: &str, key: &str) -> Option<String> {
+        let document: serde_json::Value = serde_json::from_str(content).ok()?;
+        
+        match document.get(key)? {
+            serde_json::Value::String(value) => Some(value.clone()),
+            serde_json::Value::Number(value) => Some(value.to_string()),
+            serde_json::Value::Bool(value) => Some(value.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn write_temp(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("connectcare-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+    
+    #[test]
+    fn test_file_key_from_dotenv() {
+        let path = write_temp("secrets.env", "# mounted secrets\nexport JIRA_SECRET=\"abc=123\"\nMONGO_PASSWORD=s3cret\n");
+        
+        let source: SecretSource = serde_json::from_value(serde_json::json!({
+            "fromFile": path, "key": "JIRA_SECRET"
+        })).unwrap();
+        assert_eq!(source.resolve().unwrap(), "abc=123");
+        
+        let source: SecretSource = serde_json::from_value(serde_json::json!({
+            "fromFile": path, "key": "MISSING", "format": "dotenv"
+        })).unwrap();
+        assert!(matches!(source.resolve(), Err(AppError::SecretNotFound(_))));
+    }
+    
+    #[test]
+    fn test_file_key_from_json() {
+        let path = write_temp("secrets.json", r#"{"JIRA_SECRET": "abc", "PORT": 27017}"#);
+        
+        let source = SecretSource::FromFileKey { from_file: path.clone(), key: "JIRA_SECRET".to_string(), format: None };
+        assert_eq!(source.resolve().unwrap(), "abc");
+        
+        let source = SecretSource::FromFileKey { from_file: path.clone(), key: "PORT".to_string(), format: Some(SecretFileFormat::Json) };
+        assert_eq!(source.resolve().unwrap(), "27017");
+        
+        let source = SecretSource::FromFileKey { from_file: path, key: "MISSING".to_string(), format: None };
+        assert!(matches!(source.resolve(), Err(AppError::SecretNotFound(_))));
+    }
+    
+    #[test]
+    fn test_plain_file_still_reads_whole_file() {
+        let path = write_temp("secret.txt", "whole-secret\n");
+        
+        let source: SecretSource = serde_json::from_value(serde_json::json!({ "fromFile": path })).unwrap();
+        
+        assert!(matches!(source, SecretSource::FromFile { .. }));
+        assert_eq!(source.resolve().unwrap(), "whole-secret");
+    }
 }
@@ -0,0 +1,235 @@
+use crate::config::secret::SecretSource;
+use crate::pipeline::event::{Operation, PipelineEvent};
+use crate::pipeline::processors::path;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::ops::Deref;
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Events buffered per `/-/events/stream` client; a client falling further behind skips events
+const LIVE_EVENTS_CAPACITY: usize = 256;
+
+/// Opt-in capture of recent failing payloads, served at `/-/debug/failures`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DebugConfig {
+    /// Number of failures kept; the oldest is evicted first
+    #[serde(default = "default_failure_buffer_size")]
+    pub failure_buffer_size: usize,
+    /// Expected in the `X-Debug-Token` header of requests to the debug endpoint
+    pub token: SecretSource,
+    /// Body paths (dotted or JSON Pointer) replaced before a payload is stored
+    #[serde(default)]
+    pub redact_paths: Vec<String>,
+}
+
+fn default_failure_buffer_size() -> usize {
+    50
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailureRecord {
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    /// Where the failure happened, e.g. `jira` or `pipeline 0`
+    pub stage: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_type: Option<String>,
+    pub error: String,
+    pub body: Value,
+}
+
+#[derive(Default)]
+struct BufferState {
+    capacity: usize,
+    redact_paths: Vec<String>,
+    records: VecDeque<FailureRecord>,
+}
+
+/// Bounded in-memory buffer of the last failing payloads, disabled until configured
+#[derive(Default)]
+pub struct FailureBuffer {
+    state: Mutex<BufferState>,
+}
+
+static FAILURES: OnceLock<FailureBuffer> = OnceLock::new();
+
+thread_local! {
+    /// Buffer standing in for the process-wide one on this thread, see `FailureBuffer::scoped`
+    static SCOPED_FAILURES: Cell<Option<&'static FailureBuffer>> = const { Cell::new(None) };
+}
+
+/// Process-wide buffer, or the scoped one of the current thread
+pub fn failures() -> &'static FailureBuffer {
+    SCOPED_FAILURES.with(Cell::get).unwrap_or_else(|| FAILURES.get_or_init(FailureBuffer::default))
+}
+
+/// A fresh buffer `failures` returns on the current thread until dropped
+pub struct ScopedFailures {
+    buffer: &'static FailureBuffer,
+    previous: Option<&'static FailureBuffer>,
+}
+
+impl Deref for ScopedFailures {
+    type Target = FailureBuffer;
+
+    fn deref(&self) -> &FailureBuffer {
+        self.buffer
+    }
+}
+
+impl Drop for ScopedFailures {
+    fn drop(&mut self) {
+        SCOPED_FAILURES.with(|scoped| scoped.set(self.previous));
+    }
+}
+
+impl FailureBuffer {
+    /// Install a fresh buffer for the current thread, so a test sees only its own failures; it
+    /// is leaked, so meant for tests only
+    pub fn scoped() -> ScopedFailures {
+        let buffer: &'static FailureBuffer = Box::leak(Box::default());
+        let previous = SCOPED_FAILURES.with(|scoped| scoped.replace(Some(buffer)));
+        ScopedFailures { buffer, previous }
+    }
+
+    pub fn configure(&self, capacity: usize, redact_paths: Vec<String>) {
+        let mut state = self.state.lock().unwrap();
+        state.capacity = capacity;
+        state.redact_paths = redact_paths;
+
+        while state.records.len() > capacity {
+            state.records.pop_front();
+        }
+    }
+
+    /// Lets callers skip cloning payloads when nothing would be stored
+    pub fn is_enabled(&self) -> bool {
+        self.state.lock().unwrap().capacity > 0
+    }
+
+    pub fn record(&self, stage: &str, event_type: Option<&str>, body: &Value, error: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.capacity == 0 {
+            return;
+        }
+
+        let body = redact(body, &state.redact_paths);
+
+        if state.records.len() == state.capacity {
+            state.records.pop_front();
+        }
+        state.records.push_back(FailureRecord {
+            timestamp: now_millis(),
+            stage: stage.to_string(),
+            event_type: event_type.map(str::to_string),
+            error: error.to_string(),
+            body,
+        });
+    }
+
+    /// Stored failures, oldest first
+    pub fn records(&self) -> Vec<FailureRecord> {
+        self.state.lock().unwrap().records.iter().cloned().collect()
+    }
+}
+
+/// A processed event as streamed by `/-/events/stream`, after the processors and before the sinks
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveEvent {
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    pub pipeline: usize,
+    pub event_id: String,
+    pub event_type: String,
+    pub operation: Operation,
+    pub body: Value,
+}
+
+/// Broadcast of processed events to the clients of `/-/events/stream`
+pub struct LiveEvents {
+    sender: broadcast::Sender<LiveEvent>,
+    redact_paths: Mutex<Vec<String>>,
+}
+
+static LIVE_EVENTS: OnceLock<LiveEvents> = OnceLock::new();
+
+/// Process-wide broadcast
+pub fn live_events() -> &'static LiveEvents {
+    LIVE_EVENTS.get_or_init(|| LiveEvents {
+        sender: broadcast::channel(LIVE_EVENTS_CAPACITY).0,
+        redact_paths: Mutex::default(),
+    })
+}
+
+impl LiveEvents {
+    pub fn configure(&self, redact_paths: Vec<String>) {
+        *self.redact_paths.lock().unwrap() = redact_paths;
+    }
+
+    /// Send an event to the connected clients; payloads are only copied while one is connected
+    pub fn publish(&self, pipeline: usize, event: &PipelineEvent) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+
+        let body = redact(&event.body, &self.redact_paths.lock().unwrap());
+        let _ = self.sender.send(LiveEvent {
+            timestamp: now_millis(),
+            pipeline,
+            event_id: event.id.clone(),
+            event_type: event.event_type.clone(),
+            operation: event.operation.clone(),
+            body,
+        });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+}
+
+fn redact(body: &Value, redact_paths: &[String]) -> Value {
+    let mut body = body.clone();
+    for redact_path in redact_paths {
+        if let Some(value) = path::get_mut(&mut body, redact_path) {
+            *value = Value::String(REDACTED.to_string());
+        }
+    }
+    body
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_buffer_is_bounded_and_redacts() {
+        let buffer = FailureBuffer::default();
+        buffer.record("jira", None, &json!({}), "ignored while disabled");
+        assert!(buffer.records().is_empty());
+
+        buffer.configure(2, vec!["user.email".to_string()]);
+        for n in 0..3 {
+            buffer.record("jira", Some("jira:issue_created"), &json!({"n": n, "user": {"email": "a@b.c"}}), "boom");
+        }
+
+        let records = buffer.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].body, json!({"n": 1, "user": {"email": REDACTED}}));
+        assert_eq!(records[1].body["n"], 2);
+    }
+}
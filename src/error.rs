@@ -24,15 +24,36 @@ pub enum AppError {
     #[error("Primary key path not found: {0}")]
     PrimaryKeyPathNotFound(String),
     
+    #[error("Payload nested deeper than {0} levels")]
+    PayloadTooDeep(usize),
+    
+    #[error("Form field not found: {0}")]
+    MissingFormField(String),
+    
+    #[error("Required header missing or mismatched: {0}")]
+    RequiredHeader(String),
+    
     #[error("Failed to send event to pipeline")]
     PipelineSend,
     
+    #[error("Pipeline did not accept the event in time")]
+    PipelineBusy,
+    
+    #[error("Too many events in flight")]
+    Overloaded,
+    
     #[error("Processing error: {0}")]
     Processing(String),
     
     #[error("Database error: {0}")]
     Database(String),
     
+    #[error("Sink error: {0}")]
+    Sink(String),
+    
+    #[error("Duplicate key: {0}")]
+    DuplicateKey(String),
+    
     #[error("JSON parse error: {0}")]
     JsonParse(#[from] serde_json::Error),
     
@@ -43,22 +64,58 @@ pub enum AppError {
     SecretNotFound(String),
 }
 
+impl AppError {
+    /// Status a request failing with this error is answered with
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::HmacValidation => StatusCode::UNAUTHORIZED,
+            AppError::MissingSignature
+            | AppError::InvalidSignatureFormat
+            | AppError::EventTypeNotFound
+            | AppError::UnsupportedEvent(_)
+            | AppError::PrimaryKeyPathNotFound(_)
+            | AppError::PayloadTooDeep(_)
+            | AppError::MissingFormField(_)
+            | AppError::RequiredHeader(_)
+            | AppError::JsonParse(_) => StatusCode::BAD_REQUEST,
+            AppError::PipelineBusy => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Overloaded => StatusCode::TOO_MANY_REQUESTS,
+            AppError::DuplicateKey(_) => StatusCode::CONFLICT,
+            AppError::Config(_)
+            | AppError::PipelineSend
+            | AppError::Processing(_)
+            | AppError::Database(_)
+            | AppError::Sink(_)
+            | AppError::Io(_)
+            | AppError::SecretNotFound(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            AppError::Config(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-            AppError::HmacValidation => (StatusCode::UNAUTHORIZED, "Invalid signature".to_string()),
-            AppError::MissingSignature => (StatusCode::BAD_REQUEST, "Missing signature header".to_string()),
-            AppError::InvalidSignatureFormat => (StatusCode::BAD_REQUEST, "Invalid signature format".to_string()),
-            AppError::EventTypeNotFound => (StatusCode::BAD_REQUEST, "Event type not found".to_string()),
-            AppError::UnsupportedEvent(event) => (StatusCode::BAD_REQUEST, format!("Unsupported event: {}", event)),
-            AppError::PrimaryKeyPathNotFound(path) => (StatusCode::BAD_REQUEST, format!("Path not found: {}", path)),
-            AppError::PipelineSend => (StatusCode::INTERNAL_SERVER_ERROR, "Pipeline error".to_string()),
-            AppError::Processing(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Processing error: {}", e)),
-            AppError::Database(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Database error: {}", e)),
-            AppError::JsonParse(e) => (StatusCode::BAD_REQUEST, format!("Invalid JSON: {}", e)),
-            AppError::Io(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("IO error: {}", e)),
-            AppError::SecretNotFound(name) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Secret not found: {}", name)),
+        let status = self.status_code();
+        let message = match self {
+            AppError::Config(msg) => msg,
+            AppError::HmacValidation => "Invalid signature".to_string(),
+            AppError::MissingSignature => "Missing signature header".to_string(),
+            AppError::InvalidSignatureFormat => "Invalid signature format".to_string(),
+            AppError::EventTypeNotFound => "Event type not found".to_string(),
+            AppError::UnsupportedEvent(event) => format!("Unsupported event: {}", event),
+            AppError::PrimaryKeyPathNotFound(path) => format!("Path not found: {}", path),
+            AppError::PayloadTooDeep(max) => format!("Payload nested deeper than {} levels", max),
+            AppError::MissingFormField(field) => format!("Form field not found: {}", field),
+            AppError::RequiredHeader(name) => format!("Required header missing or mismatched: {}", name),
+            AppError::PipelineSend => "Pipeline error".to_string(),
+            AppError::PipelineBusy => "Pipeline busy, retry later".to_string(),
+            AppError::Overloaded => "Too many events in flight, retry later".to_string(),
+            AppError::Processing(e) => format!("Processing error: {}", e),
+            AppError::Database(e) => format!("Database error: {}", e),
+            AppError::Sink(e) => format!("Sink error: {}", e),
+            AppError::DuplicateKey(key) => format!("Duplicate key: {}", key),
+            AppError::JsonParse(e) => format!("Invalid JSON: {}", e),
+            AppError::Io(e) => format!("IO error: {}", e),
+            AppError::SecretNotFound(name) => format!("Secret not found: {}", name),
         };
         
         (status, message).into_response()
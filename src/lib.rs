@@ -1,5 +1,9 @@
 pub mod config;
+pub mod debug;
 pub mod error;
+pub mod logging;
+pub mod metrics;
+pub mod outbound;
 pub mod pipeline;
 pub mod server;
 pub mod sources;
@@ -0,0 +1,68 @@
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Output format of the log lines, selected with `LOG_FORMAT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-oriented lines, colored when attached to a terminal
+    Text,
+    /// One JSON object per line, including the fields of the enclosing spans
+    Json,
+}
+
+impl LogFormat {
+    pub fn from_env() -> Self {
+        Self::parse(std::env::var("LOG_FORMAT").ok().as_deref())
+    }
+
+    fn parse(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            Some("json") => LogFormat::Json,
+            _ => LogFormat::Text,
+        }
+    }
+}
+
+pub fn init() {
+    let log_level = std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| format!("connectcare={},tower_http=debug", log_level).into());
+
+    let (text_layer, json_layer) = match LogFormat::from_env() {
+        LogFormat::Text => {
+            let use_ansi = atty::is(atty::Stream::Stdout);
+            (
+                Some(tracing_subscriber::fmt::layer().with_ansi(use_ansi)), // Disable ANSI colors in non-terminal environments
+                None,
+            )
+        }
+        LogFormat::Json => (
+            None,
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true),
+            ),
+        ),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(text_layer)
+        .with(json_layer)
+        .init();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_format_selection() {
+        assert_eq!(LogFormat::parse(Some("json")), LogFormat::Json);
+        assert_eq!(LogFormat::parse(Some(" JSON ")), LogFormat::Json);
+        assert_eq!(LogFormat::parse(Some("text")), LogFormat::Text);
+        assert_eq!(LogFormat::parse(Some("yaml")), LogFormat::Text);
+        assert_eq!(LogFormat::parse(None), LogFormat::Text);
+    }
+}
@@ -1,37 +1,50 @@
 use connectcare::{
     config::AppConfig,
-    pipeline::{create_pipeline_channel, executor::PipelineExecutor},
+    logging,
+    pipeline::{create_pipeline_channel, executor::PipelineExecutor, sinks::SinkRegistry},
     server::run_server,
 };
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let log_level = std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
+    logging::init();
     
-    let use_ansi = atty::is(atty::Stream::Stdout);
+    let config = AppConfig::from_env()?;
     
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| format!("connectcare={},tower_http=debug", log_level).into()),
-        )
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_ansi(use_ansi) // Disable ANSI colors in non-terminal environments
-        )
-        .init();
+    // Inspect the configuration as it would run, defaults included, without starting anything
+    if std::env::args().any(|arg| arg == "--print-effective-config") {
+        println!("{}", serde_json::to_string_pretty(&config.effective())?);
+        return Ok(());
+    }
     
-    let config = AppConfig::from_env()?;
+    config.metrics.install()?;
+    if let Some(limit) = &config.outbound_limit {
+        limit.install();
+    }
+    
+    // Opt-in, since it connects to every sink: check the deployment's dependencies and exit
+    if std::env::args().any(|arg| arg == "--check-connectivity") {
+        let failures = PipelineExecutor::check_connectivity(&config, &SinkRegistry::default(), AppConfig::sink_timeout()).await;
+        for failure in &failures {
+            eprintln!("{}: {}", failure.target, failure.error);
+        }
+        
+        if !failures.is_empty() {
+            anyhow::bail!("{} dependencies unreachable", failures.len());
+        }
+        println!("All secrets resolved and sinks reachable");
+        return Ok(());
+    }
     
     let (pipeline_tx, pipeline_rx) = create_pipeline_channel(100);
     
     let executor = PipelineExecutor::new(&config).await?;
+    let status = executor.status();
     tokio::spawn(async move {
         executor.run(pipeline_rx).await;
     });
     
-    run_server(config, pipeline_tx).await?;
+    run_server(config, pipeline_tx, status).await?;
     
     Ok(())
 }
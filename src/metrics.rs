@@ -0,0 +1,364 @@
+use crate::error::{AppError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::cell::Cell;
+use std::ops::Deref;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Where metrics are exported; the `/-/metrics` endpoint is served either way
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum MetricsConfig {
+    /// Scraped from `/-/metrics` only
+    #[default]
+    Prometheus,
+    /// Also pushed to a StatsD agent over UDP as they are recorded, labels as DogStatsD tags
+    Statsd {
+        /// `host:port` of the agent
+        address: String,
+        /// Prepended to every metric name, e.g. `myteam.`
+        #[serde(default)]
+        #[serde(skip_serializing_if = "String::is_empty")]
+        prefix: String,
+    },
+}
+
+impl MetricsConfig {
+    /// Start exporting to the configured backend
+    pub fn install(&self) -> Result<()> {
+        if let MetricsConfig::Statsd { address, prefix } = self {
+            global().export_to(StatsdExporter::connect(address, prefix)?);
+            tracing::info!("Pushing metrics to StatsD at {}", address);
+        }
+        Ok(())
+    }
+}
+
+/// Sends every counter increment, gauge update and timing to a StatsD agent, fire and forget
+pub struct StatsdExporter {
+    socket: UdpSocket,
+    prefix: String,
+}
+
+impl StatsdExporter {
+    pub fn connect(address: &str, prefix: &str) -> Result<Self> {
+        let target = address
+            .to_socket_addrs()
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .ok_or_else(|| AppError::Config(format!("Invalid StatsD address: {}", address)))?;
+
+        let local = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(target)?;
+        // A slow or missing agent must never hold up the code recording a metric
+        socket.set_nonblocking(true)?;
+
+        Ok(Self { socket, prefix: prefix.to_string() })
+    }
+
+    fn send(&self, name: &str, labels: &[(&str, &str)], value: &str, kind: &str) {
+        let mut line = format!("{}{}:{}|{}", self.prefix, name, value, kind);
+        if !labels.is_empty() {
+            let tags = labels.iter().map(|(k, v)| format!("{}:{}", k, v)).collect::<Vec<_>>().join(",");
+            let _ = write!(line, "|#{}", tags);
+        }
+
+        // Lost packets are lost metrics, as usual with StatsD
+        let _ = self.socket.send(line.as_bytes());
+    }
+}
+
+/// Upper bounds, in seconds, of the buckets of every histogram
+const HISTOGRAM_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Observations of one histogram series
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    /// Observations per bucket, not cumulative; values above the last bound are only in `count`
+    buckets: [u64; HISTOGRAM_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if let Some(bucket) = HISTOGRAM_BUCKETS.iter().position(|bound| value <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// In-process metrics registry rendered in the Prometheus text format
+#[derive(Default)]
+pub struct Metrics {
+    counters: Mutex<BTreeMap<String, u64>>,
+    gauges: Mutex<BTreeMap<String, i64>>,
+    histograms: Mutex<BTreeMap<String, Histogram>>,
+    statsd: OnceLock<StatsdExporter>,
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+thread_local! {
+    /// Registry standing in for the process-wide one on this thread, see `Metrics::scoped`
+    static SCOPED: Cell<Option<&'static Metrics>> = const { Cell::new(None) };
+}
+
+/// Process-wide registry, or the scoped one of the current thread
+pub fn global() -> &'static Metrics {
+    SCOPED.with(Cell::get).unwrap_or_else(|| METRICS.get_or_init(Metrics::default))
+}
+
+/// A fresh registry `global` returns on the current thread until dropped
+///
+/// Lets a test read only the updates it caused; tasks it spawns on a current-thread runtime
+/// record into it too.
+pub struct ScopedMetrics {
+    metrics: &'static Metrics,
+    previous: Option<&'static Metrics>,
+}
+
+impl Deref for ScopedMetrics {
+    type Target = Metrics;
+
+    fn deref(&self) -> &Metrics {
+        self.metrics
+    }
+}
+
+impl Drop for ScopedMetrics {
+    fn drop(&mut self) {
+        SCOPED.with(|scoped| scoped.set(self.previous));
+    }
+}
+
+fn series_key(name: &str, labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return name.to_string();
+    }
+
+    let labels = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!("{}{{{}}}", name, labels)
+}
+
+impl Metrics {
+    /// Install a fresh registry for the current thread; it is leaked, so meant for tests only
+    pub fn scoped() -> ScopedMetrics {
+        let metrics: &'static Metrics = Box::leak(Box::default());
+        let previous = SCOPED.with(|scoped| scoped.replace(Some(metrics)));
+        ScopedMetrics { metrics, previous }
+    }
+
+    /// Also send every update to `exporter`; later calls are ignored
+    pub fn export_to(&self, exporter: StatsdExporter) {
+        let _ = self.statsd.set(exporter);
+    }
+
+    /// Whether updates are pushed to StatsD, so gauges read on scrape must be refreshed instead
+    pub fn pushes(&self) -> bool {
+        self.statsd.get().is_some()
+    }
+
+    pub fn increment(&self, name: &str, labels: &[(&str, &str)]) {
+        self.add(name, labels, 1);
+    }
+
+    pub fn add(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        *self.counters.lock().unwrap().entry(series_key(name, labels)).or_insert(0) += value;
+
+        // Sent once the registry is unlocked, so other threads recording metrics don't wait on it
+        if let Some(statsd) = self.statsd.get() {
+            statsd.send(name, labels, &value.to_string(), "c");
+        }
+    }
+
+    /// Current value of a counter series, 0 if never incremented
+    pub fn counter(&self, name: &str, labels: &[(&str, &str)]) -> u64 {
+        let counters = self.counters.lock().unwrap();
+        counters.get(&series_key(name, labels)).copied().unwrap_or(0)
+    }
+
+    pub fn set_gauge(&self, name: &str, labels: &[(&str, &str)], value: i64) {
+        self.gauges.lock().unwrap().insert(series_key(name, labels), value);
+
+        if let Some(statsd) = self.statsd.get() {
+            statsd.send(name, labels, &value.to_string(), "g");
+        }
+    }
+
+    /// Current value of a gauge series, 0 if never set
+    pub fn gauge(&self, name: &str, labels: &[(&str, &str)]) -> i64 {
+        let gauges = self.gauges.lock().unwrap();
+        gauges.get(&series_key(name, labels)).copied().unwrap_or(0)
+    }
+
+    /// Record a duration in a histogram, in seconds; sent to StatsD as a timing in milliseconds
+    pub fn observe_duration(&self, name: &str, labels: &[(&str, &str)], duration: Duration) {
+        self.histograms.lock().unwrap().entry(series_key(name, labels)).or_default().observe(duration.as_secs_f64());
+
+        if let Some(statsd) = self.statsd.get() {
+            statsd.send(name, labels, &format!("{:.3}", duration.as_secs_f64() * 1000.0), "ms");
+        }
+    }
+
+    /// Observations of a histogram series, 0 if never observed
+    pub fn histogram_count(&self, name: &str, labels: &[(&str, &str)]) -> u64 {
+        let histograms = self.histograms.lock().unwrap();
+        histograms.get(&series_key(name, labels)).map_or(0, |histogram| histogram.count)
+    }
+
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+
+        render_series(&mut output, "counter", self.counters.lock().unwrap().iter());
+        render_series(&mut output, "gauge", self.gauges.lock().unwrap().iter());
+        render_histograms(&mut output, self.histograms.lock().unwrap().iter());
+
+        output
+    }
+}
+
+fn render_histograms<'a>(output: &mut String, series: impl Iterator<Item = (&'a String, &'a Histogram)>) {
+    let mut last_name = "";
+    for (key, histogram) in series {
+        let (name, labels) = match key.split_once('{') {
+            Some((name, labels)) => (name, labels.trim_end_matches('}')),
+            None => (key.as_str(), ""),
+        };
+        if name != last_name {
+            let _ = writeln!(output, "# TYPE {} histogram", name);
+            last_name = name;
+        }
+
+        let separator = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (bound, observed) in HISTOGRAM_BUCKETS.iter().zip(histogram.buckets) {
+            cumulative += observed;
+            let _ = writeln!(output, "{}_bucket{{{}{}le=\"{}\"}} {}", name, labels, separator, bound, cumulative);
+        }
+        let _ = writeln!(output, "{}_bucket{{{}{}le=\"+Inf\"}} {}", name, labels, separator, histogram.count);
+
+        let labels = if labels.is_empty() { String::new() } else { format!("{{{}}}", labels) };
+        let _ = writeln!(output, "{}_sum{} {}", name, labels, histogram.sum);
+        let _ = writeln!(output, "{}_count{} {}", name, labels, histogram.count);
+    }
+}
+
+fn render_series<'a, V: std::fmt::Display + 'a>(
+    output: &mut String,
+    kind: &str,
+    series: impl Iterator<Item = (&'a String, &'a V)>,
+) {
+    let mut last_name = "";
+    for (key, value) in series {
+        let name = key.split('{').next().unwrap_or(key);
+        if name != last_name {
+            let _ = writeln!(output, "# TYPE {} {}", name, kind);
+            last_name = name;
+        }
+        let _ = writeln!(output, "{} {}", key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_render() {
+        let metrics = Metrics::default();
+        metrics.increment("connectcare_test_total", &[("source", "jira")]);
+        metrics.add("connectcare_test_total", &[("source", "jira")], 2);
+        metrics.increment("connectcare_test_total", &[("source", "git\"hub")]);
+
+        assert_eq!(metrics.counter("connectcare_test_total", &[("source", "jira")]), 3);
+        assert_eq!(metrics.counter("connectcare_test_total", &[("source", "other")]), 0);
+
+        let rendered = metrics.render();
+        assert_eq!(rendered.matches("# TYPE connectcare_test_total counter").count(), 1);
+        assert!(rendered.contains("connectcare_test_total{source=\"jira\"} 3"));
+        assert!(rendered.contains("connectcare_test_total{source=\"git\\\"hub\"} 1"));
+    }
+
+    #[test]
+    fn test_gauge_render() {
+        let metrics = Metrics::default();
+        metrics.set_gauge("connectcare_test_depth", &[], 5);
+        metrics.set_gauge("connectcare_test_depth", &[], 2);
+
+        assert_eq!(metrics.gauge("connectcare_test_depth", &[]), 2);
+
+        let rendered = metrics.render();
+        assert!(rendered.contains("# TYPE connectcare_test_depth gauge\nconnectcare_test_depth 2\n"));
+    }
+
+    #[test]
+    fn test_histogram_render() {
+        let metrics = Metrics::default();
+        metrics.observe_duration("connectcare_test_seconds", &[("pipeline", "0")], Duration::from_millis(3));
+        metrics.observe_duration("connectcare_test_seconds", &[("pipeline", "0")], Duration::from_millis(40));
+        metrics.observe_duration("connectcare_test_seconds", &[("pipeline", "0")], Duration::from_secs(60));
+
+        assert_eq!(metrics.histogram_count("connectcare_test_seconds", &[("pipeline", "0")]), 3);
+
+        let rendered = metrics.render();
+        assert_eq!(rendered.matches("# TYPE connectcare_test_seconds histogram").count(), 1);
+        assert!(rendered.contains("connectcare_test_seconds_bucket{pipeline=\"0\",le=\"0.005\"} 1\n"));
+        assert!(rendered.contains("connectcare_test_seconds_bucket{pipeline=\"0\",le=\"0.05\"} 2\n"));
+        assert!(rendered.contains("connectcare_test_seconds_bucket{pipeline=\"0\",le=\"10\"} 2\n"));
+        assert!(rendered.contains("connectcare_test_seconds_bucket{pipeline=\"0\",le=\"+Inf\"} 3\n"));
+        assert!(rendered.contains("connectcare_test_seconds_sum{pipeline=\"0\"} 60.043\n"));
+        assert!(rendered.contains("connectcare_test_seconds_count{pipeline=\"0\"} 3\n"));
+    }
+
+    #[test]
+    fn test_updates_are_pushed_to_statsd() {
+        let agent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        agent.set_read_timeout(Some(std::time::Duration::from_secs(5))).unwrap();
+
+        let metrics = Metrics::default();
+        let address = agent.local_addr().unwrap().to_string();
+        metrics.export_to(StatsdExporter::connect(&address, "connectcare.").unwrap());
+
+        metrics.add("connectcare_events_total", &[("source", "jira"), ("pipeline", "0")], 2);
+        metrics.set_gauge("connectcare_pipeline_backlog", &[], 7);
+
+        let mut buffer = [0; 512];
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            let len = agent.recv(&mut buffer).unwrap();
+            received.push(String::from_utf8_lossy(&buffer[..len]).to_string());
+        }
+
+        assert_eq!(received, vec![
+            "connectcare.connectcare_events_total:2|c|#source:jira,pipeline:0",
+            "connectcare.connectcare_pipeline_backlog:7|g",
+        ]);
+        // The registry is still kept for `/-/metrics`
+        assert_eq!(metrics.counter("connectcare_events_total", &[("source", "jira"), ("pipeline", "0")]), 2);
+    }
+
+    #[test]
+    fn test_scoped_registry_replaces_global_on_its_thread() {
+        let scoped = Metrics::scoped();
+        global().increment("connectcare_test_scoped_total", &[]);
+
+        assert_eq!(scoped.counter("connectcare_test_scoped_total", &[]), 1);
+        let other_thread = std::thread::spawn(|| global().counter("connectcare_test_scoped_total", &[]));
+        assert_eq!(other_thread.join().unwrap(), 0);
+
+        drop(scoped);
+        assert_eq!(global().counter("connectcare_test_scoped_total", &[]), 0);
+    }
+}
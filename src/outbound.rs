@@ -0,0 +1,152 @@
+use crate::metrics;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Cap on outbound HTTP requests in flight, e.g. to Jira's REST API
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OutboundLimit {
+    pub max_concurrent: usize,
+    #[serde(default)]
+    pub scope: LimitScope,
+}
+
+/// What `max_concurrent` applies to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitScope {
+    /// All requests together
+    #[default]
+    Global,
+    /// The requests to each host, separately
+    Target,
+}
+
+impl OutboundLimit {
+    /// Make this the limit of every outbound request of the process
+    pub fn install(&self) {
+        if LIMITER.set(OutboundLimiter::new(self)).is_ok() {
+            tracing::info!("Limiting outbound requests to {} in flight ({:?} scope)", self.max_concurrent, self.scope);
+        }
+    }
+}
+
+/// Hands out a permit per outbound request, waiting while the limit is reached
+pub struct OutboundLimiter {
+    max_concurrent: usize,
+    scope: LimitScope,
+    global: Arc<Semaphore>,
+    targets: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+static LIMITER: OnceLock<OutboundLimiter> = OnceLock::new();
+
+/// Wait for a slot for a request to `url`; requests are unbounded if no limit is installed
+pub async fn acquire(url: &str) -> Option<OwnedSemaphorePermit> {
+    match LIMITER.get() {
+        Some(limiter) => Some(limiter.acquire(url).await),
+        None => None,
+    }
+}
+
+impl OutboundLimiter {
+    pub fn new(config: &OutboundLimit) -> Self {
+        Self {
+            max_concurrent: config.max_concurrent.max(1),
+            scope: config.scope,
+            global: Arc::new(Semaphore::new(config.max_concurrent.max(1))),
+            targets: Mutex::default(),
+        }
+    }
+
+    /// Hold the returned permit until the request to `url` is done
+    pub async fn acquire(&self, url: &str) -> OwnedSemaphorePermit {
+        let target = target_of(url);
+        let semaphore = match self.scope {
+            LimitScope::Global => self.global.clone(),
+            LimitScope::Target => self
+                .targets
+                .lock()
+                .unwrap()
+                .entry(target.clone())
+                .or_insert_with(|| Arc::new(Semaphore::new(self.max_concurrent)))
+                .clone(),
+        };
+
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            return permit;
+        }
+
+        metrics::global().increment("connectcare_outbound_throttled_total", &[("target", &target)]);
+        // The semaphore is never closed
+        semaphore.acquire_owned().await.expect("outbound semaphore closed")
+    }
+}
+
+/// `host:port` of a URL, or the URL itself if it can't be parsed
+fn target_of(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => match (parsed.host_str(), parsed.port_or_known_default()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            _ => url.to_string(),
+        },
+        Err(_) => url.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Send a request to each URL at once, returning the most seen in flight together
+    async fn burst(limiter: Arc<OutboundLimiter>, urls: Vec<&'static str>) -> usize {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let calls: Vec<_> = urls
+            .into_iter()
+            .map(|url| {
+                let (limiter, in_flight, peak) = (limiter.clone(), in_flight.clone(), peak.clone());
+                tokio::spawn(async move {
+                    let _permit = limiter.acquire(url).await;
+                    let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+
+        for call in calls {
+            call.await.unwrap();
+        }
+        peak.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn test_burst_never_exceeds_the_limit() {
+        let limiter = Arc::new(OutboundLimiter::new(&OutboundLimit { max_concurrent: 3, scope: LimitScope::Global }));
+        let urls = std::iter::repeat_n("https://a.atlassian.net/rest/api/2/field", 20)
+            .chain(std::iter::repeat_n("https://b.atlassian.net/rest/api/2/field", 20))
+            .collect();
+
+        assert!(burst(limiter, urls).await <= 3);
+    }
+
+    #[tokio::test]
+    async fn test_target_scope_limits_each_host_separately() {
+        let limiter = Arc::new(OutboundLimiter::new(&OutboundLimit { max_concurrent: 2, scope: LimitScope::Target }));
+        let urls = std::iter::repeat_n("https://a.atlassian.net/rest/api/2/field", 10)
+            .chain(std::iter::repeat_n("http://b.atlassian.net:8080/rest/api/2/field", 10))
+            .collect();
+
+        let peak = burst(limiter.clone(), urls).await;
+        assert!(peak > 2 && peak <= 4, "{} requests in flight", peak);
+        assert_eq!(limiter.targets.lock().unwrap().len(), 2);
+        assert!(limiter.targets.lock().unwrap().contains_key("b.atlassian.net:8080"));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::pipeline::sinks::SinkOutcome;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// How an event fared against the pipelines' required sinks
+#[derive(Debug, Clone, PartialEq)]
+pub enum AckOutcome {
+    /// At least one required sink wrote the event, with what the first one reported
+    Acknowledged(SinkOutcome),
+    /// A pipeline with required sinks dropped or failed the event and none succeeded
+    Failed,
+    /// No pipeline with required sinks handled the event (none configured, or it was filtered out)
+    NotRequired,
+}
+
+#[derive(Debug)]
+struct AckState {
+    sender: Mutex<Option<oneshot::Sender<AckOutcome>>>,
+    failed: AtomicBool,
+    /// Acks of events merged into this one, resolved the same way; dropped with it otherwise
+    merged: Mutex<Vec<AckHandle>>,
+}
+
+impl AckState {
+    fn resolve(&self, outcome: AckOutcome) {
+        for merged in self.merged.lock().unwrap().iter() {
+            merged.state.resolve(outcome.clone());
+        }
+        if let Some(sender) = self.sender.lock().unwrap().take() {
+            let _ = sender.send(outcome);
+        }
+    }
+}
+
+impl Drop for AckState {
+    /// Runs once every copy of the event is gone without a required sink succeeding
+    fn drop(&mut self) {
+        let outcome = if self.failed.load(Ordering::SeqCst) {
+            AckOutcome::Failed
+        } else {
+            AckOutcome::NotRequired
+        };
+        self.resolve(outcome);
+    }
+}
+
+/// Travels with an event (and its per-pipeline clones) so required sinks can report back to the source
+#[derive(Debug, Clone)]
+pub struct AckHandle {
+    state: Arc<AckState>,
+}
+
+impl AckHandle {
+    /// A required sink wrote the event; the first call resolves the waiter
+    pub fn acknowledge(&self, outcome: SinkOutcome) {
+        self.state.resolve(AckOutcome::Acknowledged(outcome));
+    }
+
+    /// A pipeline with required sinks could not deliver the event
+    pub fn fail(&self) {
+        self.state.failed.store(true, Ordering::SeqCst);
+        for merged in self.state.merged.lock().unwrap().iter() {
+            merged.fail();
+        }
+    }
+
+    /// Answer `other`, the ack of an event merged into this one, with whatever this one gets
+    pub fn absorb(&self, other: AckHandle) {
+        // Parts of one split event share their ack
+        if !Arc::ptr_eq(&self.state, &other.state) {
+            self.state.merged.lock().unwrap().push(other);
+        }
+    }
+}
+
+/// Source side of an ack, resolved once a required sink succeeds or all copies of the event are dropped
+pub struct AckWaiter {
+    receiver: oneshot::Receiver<AckOutcome>,
+}
+
+impl AckWaiter {
+    pub async fn wait(self) -> AckOutcome {
+        self.receiver.await.unwrap_or(AckOutcome::Failed)
+    }
+}
+
+pub fn ack_channel() -> (AckHandle, AckWaiter) {
+    let (sender, receiver) = oneshot::channel();
+    let state = Arc::new(AckState {
+        sender: Mutex::new(Some(sender)),
+        failed: AtomicBool::new(false),
+        merged: Mutex::default(),
+    });
+
+    (AckHandle { state }, AckWaiter { receiver })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ack_outcomes() {
+        let (handle, waiter) = ack_channel();
+        let copy = handle.clone();
+        copy.fail();
+        handle.acknowledge(SinkOutcome::default());
+        drop((handle, copy));
+        assert_eq!(waiter.wait().await, AckOutcome::Acknowledged(SinkOutcome::default()));
+
+        let (handle, waiter) = ack_channel();
+        handle.clone().fail();
+        drop(handle);
+        assert_eq!(waiter.wait().await, AckOutcome::Failed);
+
+        let (handle, waiter) = ack_channel();
+        drop(handle);
+        assert_eq!(waiter.wait().await, AckOutcome::NotRequired);
+    }
+
+    #[tokio::test]
+    async fn test_absorbed_acks_share_the_outcome() {
+        let (handle, waiter) = ack_channel();
+        let (merged, merged_waiter) = ack_channel();
+        handle.absorb(merged);
+        handle.absorb(handle.clone());
+        handle.fail();
+        drop(handle);
+        assert_eq!(waiter.wait().await, AckOutcome::Failed);
+        assert_eq!(merged_waiter.wait().await, AckOutcome::Failed);
+
+        let (handle, waiter) = ack_channel();
+        let (merged, merged_waiter) = ack_channel();
+        handle.absorb(merged);
+        handle.acknowledge(SinkOutcome::default());
+        assert!(matches!(waiter.wait().await, AckOutcome::Acknowledged(_)));
+        assert!(matches!(merged_waiter.wait().await, AckOutcome::Acknowledged(_)));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::metrics;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// When a sink's circuit opens and how long it stays open
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failed writes that open the circuit
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// How long writes are skipped before one is let through to test the sink again
+    #[serde(default = "default_cooldown_ms")]
+    pub cooldown_ms: u64,
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_cooldown_ms() -> u64 {
+    30_000
+}
+
+/// Exported as the `connectcare_sink_circuit_state` gauge: 0 closed, 1 open, 2 half-open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed = 0,
+    Open = 1,
+    HalfOpen = 2,
+}
+
+struct Breaker {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// A write is testing the sink after the cooldown
+    probing: bool,
+}
+
+/// Skips writes to a sink after repeated failures, instead of waiting on a dependency that is down
+///
+/// After `failure_threshold` consecutive failures the circuit opens and writes are refused for
+/// `cooldown_ms`. Then one write per cooldown is let through as a probe: success closes the
+/// circuit, failure opens it for another cooldown.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    pipeline: String,
+    sink: String,
+    state: Mutex<Breaker>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: &CircuitBreakerConfig, pipeline_idx: usize, sink_idx: usize) -> Self {
+        let breaker = Self {
+            failure_threshold: config.failure_threshold.max(1),
+            cooldown: Duration::from_millis(config.cooldown_ms),
+            pipeline: pipeline_idx.to_string(),
+            sink: sink_idx.to_string(),
+            state: Mutex::new(Breaker { consecutive_failures: 0, opened_at: None, probing: false }),
+        };
+        breaker.export(CircuitState::Closed);
+        breaker
+    }
+
+    /// Whether a write may be attempted; refused writes are counted as short-circuited
+    pub fn allow(&self) -> bool {
+        let mut breaker = self.state.lock().unwrap();
+        let Some(opened_at) = breaker.opened_at else {
+            return true;
+        };
+
+        // A probe restarts the cooldown, so one that never reports back doesn't stall the sink
+        if opened_at.elapsed() < self.cooldown {
+            metrics::global().increment(
+                "connectcare_sink_short_circuited_total",
+                &[("pipeline", &self.pipeline), ("sink", &self.sink)],
+            );
+            return false;
+        }
+
+        breaker.opened_at = Some(Instant::now());
+        breaker.probing = true;
+        self.export(CircuitState::HalfOpen);
+        true
+    }
+
+    pub fn record_success(&self) {
+        let mut breaker = self.state.lock().unwrap();
+        if breaker.opened_at.is_some() {
+            tracing::info!("Sink {} in pipeline {} recovered, closing its circuit", self.sink, self.pipeline);
+        }
+
+        *breaker = Breaker { consecutive_failures: 0, opened_at: None, probing: false };
+        self.export(CircuitState::Closed);
+    }
+
+    pub fn record_failure(&self) {
+        let mut breaker = self.state.lock().unwrap();
+        breaker.consecutive_failures += 1;
+
+        if breaker.probing || breaker.consecutive_failures >= self.failure_threshold {
+            if breaker.opened_at.is_none() || breaker.probing {
+                tracing::warn!(
+                    "Sink {} in pipeline {} failed {} times in a row, skipping writes for {:?}",
+                    self.sink, self.pipeline, breaker.consecutive_failures, self.cooldown
+                );
+            }
+            breaker.opened_at = Some(Instant::now());
+            breaker.probing = false;
+            self.export(CircuitState::Open);
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let breaker = self.state.lock().unwrap();
+        match (breaker.opened_at, breaker.probing) {
+            (None, _) => CircuitState::Closed,
+            (Some(_), true) => CircuitState::HalfOpen,
+            (Some(_), false) => CircuitState::Open,
+        }
+    }
+
+    fn export(&self, state: CircuitState) {
+        metrics::global().set_gauge(
+            "connectcare_sink_circuit_state",
+            &[("pipeline", &self.pipeline), ("sink", &self.sink)],
+            state as i64,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_probe_reopens_circuit() {
+        let metrics = metrics::Metrics::scoped();
+        let breaker = CircuitBreaker::new(&CircuitBreakerConfig { failure_threshold: 2, cooldown_ms: 20 }, 0, 0);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.allow());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        // Only one probe at a time
+        assert!(!breaker.allow());
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow());
+        assert_eq!(metrics.gauge("connectcare_sink_circuit_state", &[("pipeline", "0"), ("sink", "0")]), 1);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::metrics;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Bounds of the in-memory dead-letter queue
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DeadLetterConfig {
+    /// Number of events kept; the oldest is evicted first
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+    /// Events older than this are evicted; kept until pushed out by newer ones if unset
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age_secs: Option<u64>,
+}
+
+fn default_max_entries() -> usize {
+    1000
+}
+
+/// An event a pipeline failed to process
+#[derive(Debug, Clone, Serialize)]
+pub struct DeadLetter {
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    pub pipeline: usize,
+    pub event_id: String,
+    pub event_type: String,
+    /// Body the pipeline failed on
+    pub body: Value,
+    /// Body as the source created the event, before any processor
+    pub raw_body: Arc<Value>,
+    pub error: String,
+}
+
+/// Events that failed in a pipeline, bounded by count and age
+pub struct DeadLetterQueue {
+    max_entries: usize,
+    max_age: Option<Duration>,
+    entries: Mutex<VecDeque<(Instant, DeadLetter)>>,
+}
+
+impl DeadLetterQueue {
+    pub fn new(config: &DeadLetterConfig) -> Self {
+        Self {
+            max_entries: config.max_entries,
+            max_age: config.max_age_secs.map(Duration::from_secs),
+            entries: Mutex::default(),
+        }
+    }
+
+    pub fn push(&self, pipeline: usize, event_id: String, event_type: String, body: Value, raw_body: Arc<Value>, error: String) {
+        let pipeline_label = pipeline.to_string();
+        metrics::global().increment("connectcare_dead_lettered_total", &[("pipeline", &pipeline_label)]);
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        let mut entries = self.entries.lock().unwrap();
+        self.evict_expired(&mut entries);
+
+        entries.push_back((Instant::now(), DeadLetter { timestamp, pipeline, event_id, event_type, body, raw_body, error }));
+
+        let overflow = entries.len().saturating_sub(self.max_entries);
+        entries.drain(..overflow);
+        if overflow > 0 {
+            metrics::global().add("connectcare_dead_letter_evicted_total", &[("reason", "capacity")], overflow as u64);
+        }
+
+        Self::record_size(&entries);
+    }
+
+    /// Stored events, oldest first
+    pub fn entries(&self) -> Vec<DeadLetter> {
+        let mut entries = self.entries.lock().unwrap();
+        self.evict_expired(&mut entries);
+        Self::record_size(&entries);
+
+        entries.iter().map(|(_, entry)| entry.clone()).collect()
+    }
+
+    fn evict_expired(&self, entries: &mut VecDeque<(Instant, DeadLetter)>) {
+        let Some(max_age) = self.max_age else {
+            return;
+        };
+
+        let expired = entries
+            .iter()
+            .take_while(|(added, _)| added.elapsed() > max_age)
+            .count();
+        entries.drain(..expired);
+        if expired > 0 {
+            metrics::global().add("connectcare_dead_letter_evicted_total", &[("reason", "age")], expired as u64);
+        }
+    }
+
+    fn record_size(entries: &VecDeque<(Instant, DeadLetter)>) {
+        metrics::global().set_gauge("connectcare_dead_letter_size", &[], entries.len() as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn push(queue: &DeadLetterQueue, id: &str) {
+        queue.push(0, id.to_string(), "test_event".to_string(), json!({"id": id}), Arc::new(json!({"key": id})), "sink failed".to_string());
+    }
+
+    #[test]
+    fn test_oldest_entries_are_evicted_past_the_cap() {
+        let queue = DeadLetterQueue::new(&DeadLetterConfig { max_entries: 2, max_age_secs: None });
+        let evicted = || metrics::global().counter("connectcare_dead_letter_evicted_total", &[("reason", "capacity")]);
+        let before = evicted();
+
+        for id in ["1", "2", "3"] {
+            push(&queue, id);
+        }
+
+        let ids: Vec<_> = queue.entries().into_iter().map(|entry| entry.event_id).collect();
+        assert_eq!(ids, vec!["2", "3"]);
+        assert!(evicted() > before);
+    }
+
+    #[test]
+    fn test_expired_entries_are_evicted() {
+        let queue = DeadLetterQueue {
+            max_entries: 10,
+            max_age: Some(Duration::from_millis(20)),
+            entries: Mutex::default(),
+        };
+
+        push(&queue, "1");
+        std::thread::sleep(Duration::from_millis(40));
+        push(&queue, "2");
+
+        let ids: Vec<_> = queue.entries().into_iter().map(|entry| entry.event_id).collect();
+        assert_eq!(ids, vec!["2"]);
+    }
+}
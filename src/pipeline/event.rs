@@ -1,13 +1,20 @@
+use crate::pipeline::ack::AckHandle;
+use crate::pipeline::handoff::HandoffHandle;
+use crate::pipeline::in_flight::InFlightPermit;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Sha256, Digest};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Operation {
     Write,
     Delete,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PkField {
     pub key: String,
     pub value: String,
@@ -18,10 +25,28 @@ pub type PkFields = Vec<PkField>;
 #[derive(Debug, Clone)]
 pub struct PipelineEvent {
     pub id: String,
-    pub body: Value,
+    /// Shared with `raw_body` until a processor changes it through `body_mut`
+    pub body: Arc<Value>,
+    /// The body as the source created the event, kept untouched by processors for dead letters
+    pub raw_body: Arc<Value>,
     pub event_type: String,
     pub pk_fields: PkFields,
     pub operation: Operation,
+    /// Asks sinks that support it to insert the event instead of upserting it
+    pub insert_only: bool,
+    /// Set by sources waiting for a required sink to persist the event
+    pub ack: Option<AckHandle>,
+    /// Set by sources waiting for the executor to queue the event; taken off by the executor
+    pub handoff: Option<HandoffHandle>,
+    /// Provider's id for the webhook delivery the event came in, or a generated one
+    pub delivery_id: String,
+    /// Slot of the global in-flight limit, held until every pipeline is done with the event
+    pub in_flight: Option<InFlightPermit>,
+    /// Unix time in milliseconds the event was created at
+    pub received_at: u64,
+    /// Index of the integration whose source created the event, whose routing table it follows;
+    /// routed by every integration's table if unset
+    pub integration: Option<usize>,
 }
 
 impl PipelineEvent {
@@ -32,16 +57,104 @@ impl PipelineEvent {
         operation: Operation,
     ) -> Self {
         let id = Self::generate_id(&pk_fields);
+        let body = Arc::new(body);
         
         Self {
             id,
+            raw_body: body.clone(),
             body,
             event_type,
             pk_fields,
             operation,
+            insert_only: false,
+            ack: None,
+            handoff: None,
+            delivery_id: uuid::Uuid::new_v4().to_string(),
+            in_flight: None,
+            received_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            integration: None,
         }
     }
     
+    pub fn with_ack(mut self, ack: AckHandle) -> Self {
+        self.ack = Some(ack);
+        self
+    }
+    
+    pub fn with_handoff(mut self, handoff: HandoffHandle) -> Self {
+        self.handoff = Some(handoff);
+        self
+    }
+    
+    pub fn with_delivery_id(mut self, delivery_id: String) -> Self {
+        self.delivery_id = delivery_id;
+        self
+    }
+    
+    pub fn with_in_flight_permit(mut self, permit: InFlightPermit) -> Self {
+        self.in_flight = Some(permit);
+        self
+    }
+    
+    pub fn with_integration(mut self, integration: usize) -> Self {
+        self.integration = Some(integration);
+        self
+    }
+    
+    /// The body, for changing it; copied first if it is still shared with the raw body or
+    /// another event
+    pub fn body_mut(&mut self) -> &mut Value {
+        Arc::make_mut(&mut self.body)
+    }
+    
+    /// Replace the body
+    pub fn set_body(&mut self, body: Value) {
+        self.body = Arc::new(body);
+    }
+    
+    /// A new event with its own body and primary key, keeping this event's type, operation and
+    /// delivery details; for processors splitting an event into several
+    pub fn derive(&self, body: Value, pk_fields: PkFields) -> Self {
+        Self {
+            id: Self::generate_id(&pk_fields),
+            body: Arc::new(body),
+            raw_body: self.raw_body.clone(),
+            event_type: self.event_type.clone(),
+            pk_fields,
+            operation: self.operation.clone(),
+            insert_only: self.insert_only,
+            ack: self.ack.clone(),
+            handoff: None,
+            delivery_id: self.delivery_id.clone(),
+            in_flight: self.in_flight.clone(),
+            received_at: self.received_at,
+            integration: self.integration,
+        }
+    }
+    
+    /// The event with its metadata, as written by sinks with `payload: envelope`
+    ///
+    /// The shape is part of the sink contract: fields may be added but not renamed or removed.
+    pub fn envelope(&self) -> Value {
+        let pk_fields: Vec<Value> = self.pk_fields
+            .iter()
+            .map(|pk| serde_json::json!({ "key": pk.key, "value": pk.value }))
+            .collect();
+        
+        serde_json::json!({
+            "id": self.id,
+            "event_type": self.event_type,
+            "operation": self.operation,
+            "pk_fields": pk_fields,
+            "delivery_id": self.delivery_id,
+            "received_at": self.received_at,
+            "body": self.body,
+        })
+    }
+    
     fn generate_id(pk_fields: &PkFields) -> String {
         let mut hasher = Sha256::new();
         
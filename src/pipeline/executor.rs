@@ -1,156 +1,2110 @@
-use crate::config::{AppConfig, Pipeline};
-use crate::pipeline::processors::ProcessorConfig;
-use crate::error::Result;
-use crate::pipeline::event::PipelineEvent;
-use crate::pipeline::processors::{Processor, filter::FilterProcessor, mapper::MapperProcessor};
-use crate::pipeline::sinks::{Sink, database::DatabaseSink, DatabaseProvider};
+use crate::config::{AppConfig, OrderingKey, Pipeline, SampleEvent, SourceConfig};
+use crate::pipeline::processors::{PipelineProcessor, ProcessorConfig};
+use crate::error::{AppError, Result};
+use crate::pipeline::event::{Operation, PipelineEvent};
+use crate::pipeline::processors::{path, Processor, ProcessorOutput, filter::FilterProcessor, mapper::MapperProcessor};
+use crate::pipeline::processors::jira_custom_fields::JiraCustomFieldsProcessor;
+use crate::pipeline::processors::jira_users::JiraUsersProcessor;
+use crate::pipeline::processors::parse_json::ParseJsonProcessor;
+use crate::pipeline::processors::inject_primary_key::InjectPrimaryKeyProcessor;
+use crate::pipeline::processors::sample::SampleProcessor;
+use crate::pipeline::processors::adf_to_text::AdfToTextProcessor;
+use crate::pipeline::processors::anonymize::AnonymizeProcessor;
+use crate::pipeline::processors::assign_id::AssignIdProcessor;
+use crate::pipeline::processors::base64::Base64Processor;
+use crate::pipeline::processors::canonicalize::CanonicalizeProcessor;
+use crate::pipeline::processors::changelog_split::ChangelogSplitProcessor;
+use crate::pipeline::processors::compute::ComputeProcessor;
+use crate::pipeline::processors::deadband::DeadbandProcessor;
+use crate::pipeline::processors::echo::EchoProcessor;
+use crate::pipeline::processors::merge::MergeProcessor;
+use crate::pipeline::processors::merge_patch::MergePatchProcessor;
+use crate::pipeline::processors::on_error::{ProcessorErrorPolicy, SkipOnError};
+use crate::pipeline::processors::promote::PromoteProcessor;
+use crate::pipeline::processors::require::RequireProcessor;
+use crate::pipeline::processors::to_boolean::ToBooleanProcessor;
+use crate::pipeline::processors::stop::StopProcessor;
+use crate::pipeline::circuit_breaker::CircuitBreaker;
+use crate::pipeline::dead_letter::DeadLetterQueue;
+use crate::pipeline::retry_queue::{RetryQueue, RetryTarget, RetryTargets};
+use crate::pipeline::routing::RoutingTable;
+use crate::pipeline::sinks::{ConnectRetry, PipelineSink, Sink, SinkPayload, SinkRegistry};
 use crate::pipeline::PipelineReceiver;
+use crate::pipeline::status::PipelineStatus;
+use crate::debug;
+use crate::metrics;
+use std::collections::hash_map::DefaultHasher;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
-use tracing::{info, error, debug};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{info, info_span, warn, error, debug, Instrument};
+
+/// Events buffered per pipeline worker before the dispatch loop waits for room
+const PIPELINE_QUEUE_SIZE: usize = 100;
+
+/// How often the dispatch loop reports itself alive in the pipeline status
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
 
 pub struct PipelineExecutor {
-    pipelines: Vec<PipelineInstance>,
+    /// Workers of each pipeline, fed by its `ordering_key`
+    pipelines: Vec<Vec<PipelineInstance>>,
+    routing: Vec<RoutingTable>,
+    status: Arc<PipelineStatus>,
+    dead_letter: Option<Arc<DeadLetterQueue>>,
+    retry_queue: Option<Arc<RetryQueue>>,
+}
+
+/// A secret or dependency that `--check-connectivity` couldn't resolve or reach
+#[derive(Debug)]
+pub struct ConnectivityFailure {
+    /// What was checked, e.g. `pipeline 0 sink 1 (mongo)`
+    pub target: String,
+    pub error: String,
 }
 
+/// One worker of a pipeline
+///
+/// Each worker has processors of its own, so the state some keep (merge windows, deadband values)
+/// only ever sees the events of the worker's ordering keys. The sinks are shared by all of them.
 struct PipelineInstance {
+    /// Name of the pipeline, or its index if it has none, for the retry queue
+    name: String,
     processors: Vec<Box<dyn Processor>>,
-    sinks: Vec<Arc<dyn Sink>>,
+    /// `type` of each processor, for metrics
+    processor_kinds: Vec<&'static str>,
+    sinks: Arc<[SinkInstance]>,
+    max_processing: Option<Duration>,
+    ordering_key: OrderingKey,
+    /// Wait for room in a full worker queue before refusing an event its source waits on
+    queue_timeout: Duration,
+    /// Key and version stamped into every body before the sinks
+    schema_version: Option<(String, u32)>,
+}
+
+struct SinkInstance {
+    sink: Arc<dyn Sink>,
+    /// See `PipelineSink::names`
+    name: String,
+    timeout: Duration,
+    tap: bool,
+    required: bool,
+    /// Operations written to the sink; all if empty
+    operations: Vec<Operation>,
+    breaker: Option<CircuitBreaker>,
+    payload: SinkPayload,
+}
+
+impl SinkInstance {
+    fn accepts(&self, operation: &Operation) -> bool {
+        self.operations.is_empty() || self.operations.contains(operation)
+    }
+}
+
+impl PipelineInstance {
+    fn has_required_sinks(&self) -> bool {
+        self.sinks.iter().any(|sink| sink.required)
+    }
 }
 
 impl PipelineExecutor {
     pub async fn new(config: &AppConfig) -> Result<Self> {
+        Self::with_sinks(config, &SinkRegistry::default()).await
+    }
+    
+    /// Like `new`, with sink types looked up in `sinks` instead of the built-in ones only
+    pub async fn with_sinks(config: &AppConfig, sinks: &SinkRegistry) -> Result<Self> {
         let mut pipelines = Vec::new();
+        let mut routing = Vec::new();
         
         for integration in &config.integrations {
+            routing.push(RoutingTable::build(integration, pipelines.len())?);
+            
             for pipeline_config in &integration.pipelines {
-                let pipeline = Self::create_pipeline(config, pipeline_config).await?;
+                let pipeline_config = integration.with_default_processors(pipeline_config);
+                let pipeline = Self::create_pipeline(config, sinks, &pipeline_config, pipelines.len()).await?;
                 pipelines.push(pipeline);
             }
         }
         
-        Ok(Self { pipelines })
+        let dead_letter = config.dead_letter.as_ref().map(|dl| Arc::new(DeadLetterQueue::new(dl)));
+        
+        let retry_queue = match &config.retry_queue {
+            Some(retry_config) => {
+                let queue = Self::with_connect_retry(&config.sink_connect_retry, || RetryQueue::connect(retry_config)).await?;
+                Some(Arc::new(queue))
+            }
+            None => None,
+        };
+        
+        Ok(Self { pipelines, routing, status: Arc::default(), dead_letter, retry_queue })
     }
     
-    async fn create_pipeline(_config: &AppConfig, pipeline_config: &Pipeline) -> Result<PipelineInstance> {
-        // Build processors
-        let mut processors: Vec<Box<dyn Processor>> = Vec::new();
+    /// Counters updated while the executor runs, for the `/-/status` route
+    pub fn status(&self) -> Arc<PipelineStatus> {
+        self.status.clone()
+    }
+    
+    /// Events that failed in a pipeline, if a dead-letter queue is configured
+    pub fn dead_letter(&self) -> Option<Arc<DeadLetterQueue>> {
+        self.dead_letter.clone()
+    }
+    
+    /// Build the workers of a pipeline
+    async fn create_pipeline(config: &AppConfig, sinks: &SinkRegistry, pipeline_config: &Pipeline, pipeline_idx: usize) -> Result<Vec<PipelineInstance>> {
+        let processors = Self::build_processors(pipeline_config).await?;
+        
+        if let Some(sample) = &pipeline_config.sample_event {
+            Self::self_test(pipeline_config, &processors, sample, pipeline_idx).await?;
+        }
+        
+        let names = PipelineSink::names(&pipeline_config.sinks)?;
+        let mut instances: Vec<SinkInstance> = Vec::new();
         
-        for processor_config in &pipeline_config.processors {
-            match processor_config {
-                ProcessorConfig::Filter { cel_expression } => {
-                    let filter = FilterProcessor::new(cel_expression)?;
-                    processors.push(Box::new(filter));
+        for ((sink_idx, pipeline_sink), name) in pipeline_config.sinks.iter().enumerate().zip(names) {
+            let sink = Self::with_connect_retry(&config.sink_connect_retry, || async {
+                let sink = sinks.connect(&pipeline_sink.config).await?;
+                sink.prepare().await?;
+                Ok(sink)
+            }).await?;
+            
+            let timeout = pipeline_sink.timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or_else(AppConfig::sink_timeout);
+            
+            instances.push(SinkInstance {
+                sink,
+                name,
+                timeout,
+                tap: pipeline_sink.tap,
+                required: pipeline_sink.required,
+                operations: pipeline_sink.operations.clone(),
+                breaker: pipeline_sink.circuit_breaker.as_ref().map(|config| CircuitBreaker::new(config, pipeline_idx, sink_idx)),
+                payload: pipeline_sink.payload,
+            });
+        }
+        
+        let sinks: Arc<[SinkInstance]> = instances.into();
+        let shared: Vec<Arc<dyn Processor>> = processors.into_iter().map(Arc::from).collect();
+        let first = shared.iter().map(|processor| Box::new(processor.clone()) as Box<dyn Processor>).collect();
+        let mut workers = vec![Self::worker(pipeline_config, pipeline_idx, first, sinks.clone())];
+        while workers.len() < pipeline_config.concurrency {
+            workers.push(Self::worker(pipeline_config, pipeline_idx, Self::worker_processors(pipeline_config, &shared).await?, sinks.clone()));
+        }
+        
+        Ok(workers)
+    }
+    
+    fn worker(pipeline_config: &Pipeline, pipeline_idx: usize, processors: Vec<Box<dyn Processor>>, sinks: Arc<[SinkInstance]>) -> PipelineInstance {
+        PipelineInstance {
+            name: pipeline_config.name.clone().unwrap_or_else(|| pipeline_idx.to_string()),
+            processors,
+            processor_kinds: pipeline_config.processors.iter().map(|processor| processor.config.kind()).collect(),
+            sinks,
+            max_processing: pipeline_config.max_processing_ms.map(Duration::from_millis),
+            ordering_key: pipeline_config.ordering_key.clone(),
+            queue_timeout: Duration::from_millis(pipeline_config.queue_timeout_ms),
+            schema_version: pipeline_config.schema_version.map(|version| (pipeline_config.schema_version_key.clone(), version)),
+        }
+    }
+    
+    /// Resolve every secret and connect to every sink and the retry queue once, without retries
+    ///
+    /// Unlike `new`, processors are not built and sample events are not run. Sinks are only
+    /// connected and pinged, nothing is written; a sink that doesn't answer within `timeout` fails.
+    pub async fn check_connectivity(config: &AppConfig, sinks: &SinkRegistry, timeout: Duration) -> Vec<ConnectivityFailure> {
+        let mut failures = Vec::new();
+        let mut check = |target: String, result: Result<()>| {
+            if let Err(e) = result {
+                failures.push(ConnectivityFailure { target, error: e.to_string() });
+            }
+        };
+        
+        if let Some(debug) = &config.debug {
+            check("debug token".to_string(), debug.token.resolve().map(drop));
+        }
+        
+        let mut pipeline_idx = 0;
+        for (integration_idx, integration) in config.integrations.iter().enumerate() {
+            if let SourceConfig::Jira(jira) = &integration.source {
+                check(format!("integration {} source secret", integration_idx), jira.authentication.secret.resolve().map(drop));
+            }
+            
+            for pipeline in &integration.pipelines {
+                let pipeline = integration.with_default_processors(pipeline);
+                for processor in &pipeline.processors {
+                    if let ProcessorConfig::JiraCustomFields { field_api: Some(api), .. } = &processor.config {
+                        let target = format!("pipeline {} jiraCustomFields credentials", pipeline_idx);
+                        let email = api.email.as_ref().map(|email| email.resolve()).transpose();
+                        check(target, email.and_then(|_| api.api_token.resolve()).map(drop));
+                    }
                 }
-                ProcessorConfig::Mapper { output_event } => {
-                    let mapper = MapperProcessor::new(output_event.clone())?;
-                    processors.push(Box::new(mapper));
+                
+                for (sink_idx, pipeline_sink) in pipeline.sinks.iter().enumerate() {
+                    let target = format!("pipeline {} sink {} ({})", pipeline_idx, sink_idx, pipeline_sink.config.kind());
+                    let result = match sinks.connect(&pipeline_sink.config).await {
+                        Ok(sink) => tokio::time::timeout(timeout, sink.ping())
+                            .await
+                            .unwrap_or_else(|_| Err(AppError::Sink(format!("no answer within {:?}", timeout)))),
+                        Err(e) => Err(e),
+                    };
+                    check(target, result);
                 }
+                
+                pipeline_idx += 1;
             }
         }
         
-        let mut sinks: Vec<Arc<dyn Sink>> = Vec::new();
+        if let Some(retry_config) = &config.retry_queue {
+            check("retry queue".to_string(), RetryQueue::connect(retry_config).await.map(drop));
+        }
         
-        for sink_config in &pipeline_config.sinks {
-            match sink_config {
-                crate::pipeline::sinks::SinkConfig::Mongo { url, collection, insert_only } => {
-                    let mongo_url = url.resolve()?;
-                    
-                    let (base_url, database) = Self::parse_mongo_url_for_sink(&mongo_url)?;
-                    let sink = DatabaseSink::with_collection(&base_url, &database, collection, *insert_only).await?;
-                    
-                    sinks.push(Arc::new(sink));
-                }
-                crate::pipeline::sinks::SinkConfig::Database { provider } => {
-                    match provider {
-                        DatabaseProvider::Mongo => {
-                            let mongo_url = crate::config::AppConfig::mongodb_url()?;
-                            
-                            let sink = DatabaseSink::new(&mongo_url, false).await?;
-                            
-                            sinks.push(Arc::new(sink));
-                        }
-                    }
+        failures
+    }
+    
+    /// Run a sink constructor until it succeeds, backing off exponentially between attempts
+    ///
+    /// Configuration errors are returned straight away, since trying again can't fix them.
+    async fn with_connect_retry<T, F, Fut>(retry: &ConnectRetry, mut connect: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let max_backoff = Duration::from_millis(retry.max_backoff_ms);
+        let mut backoff = Duration::from_millis(retry.initial_backoff_ms).min(max_backoff);
+        let mut attempt = 1;
+        
+        loop {
+            match connect().await {
+                Ok(value) => return Ok(value),
+                Err(e @ AppError::Config(_)) => return Err(e),
+                Err(e) if attempt >= retry.attempts => return Err(e),
+                Err(e) => {
+                    warn!("Sink connection attempt {} of {} failed: {}, retrying in {:?}", attempt, retry.attempts, e, backoff);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(max_backoff);
+                    attempt += 1;
                 }
             }
         }
+    }
+    
+    /// Build a pipeline's processors, without connecting any sinks
+    pub(crate) async fn build_processors(pipeline_config: &Pipeline) -> Result<Vec<Box<dyn Processor>>> {
+        let mut processors: Vec<Box<dyn Processor>> = Vec::new();
+        
+        for pipeline_processor in &pipeline_config.processors {
+            processors.push(Self::build_processor(pipeline_processor).await?);
+        }
+        
+        Ok(processors)
+    }
+    
+    /// Processors for a worker after the first: the stateful ones are built afresh, the others
+    /// are shared with the first worker, so state fetched at startup is only fetched once
+    async fn worker_processors(pipeline_config: &Pipeline, shared: &[Arc<dyn Processor>]) -> Result<Vec<Box<dyn Processor>>> {
+        let mut processors: Vec<Box<dyn Processor>> = Vec::new();
+        
+        for (pipeline_processor, processor) in pipeline_config.processors.iter().zip(shared) {
+            if pipeline_processor.config.per_worker() {
+                processors.push(Self::build_processor(pipeline_processor).await?);
+            } else {
+                processors.push(Box::new(processor.clone()));
+            }
+        }
         
-        Ok(PipelineInstance { processors, sinks })
+        Ok(processors)
     }
     
-    fn parse_mongo_url_for_sink(url: &str) -> Result<(String, String)> {
-        let url_without_protocol = url.strip_prefix("mongodb://")
-            .or_else(|| url.strip_prefix("mongodb+srv://"))
-            .ok_or_else(|| crate::error::AppError::Config(
-                "Invalid MongoDB URL: must start with mongodb:// or mongodb+srv://".to_string()
-            ))?;
+    async fn build_processor(pipeline_processor: &PipelineProcessor) -> Result<Box<dyn Processor>> {
+        let processor: Box<dyn Processor> = match &pipeline_processor.config {
+            ProcessorConfig::Filter { cel_expression, on_error } => {
+                let filter = FilterProcessor::new(cel_expression)?.with_on_error(*on_error);
+                Box::new(filter)
+            }
+            ProcessorConfig::Mapper { output_event } => {
+                let mapper = MapperProcessor::new(output_event.clone())?;
+                Box::new(mapper)
+            }
+            ProcessorConfig::JiraCustomFields { mapping, field_api } => {
+                let renamer = JiraCustomFieldsProcessor::new(mapping.clone(), field_api.as_ref()).await?;
+                Box::new(renamer)
+            }
+            ProcessorConfig::ParseJson { paths, on_invalid } => {
+                Box::new(ParseJsonProcessor::new(paths.clone(), *on_invalid))
+            }
+            ProcessorConfig::InjectPrimaryKey { key } => {
+                Box::new(InjectPrimaryKeyProcessor::new(key.clone()))
+            }
+            ProcessorConfig::Sample { rate, deterministic, seed } => {
+                Box::new(SampleProcessor::new(*rate, *deterministic, *seed)?)
+            }
+            ProcessorConfig::AssignId { field, kind } => {
+                Box::new(AssignIdProcessor::new(field.clone(), *kind))
+            }
+            ProcessorConfig::JiraUsers { paths } => {
+                Box::new(JiraUsersProcessor::new(paths.clone()))
+            }
+            ProcessorConfig::Compute { fields } => {
+                Box::new(ComputeProcessor::new(fields)?)
+            }
+            ProcessorConfig::Merge { key, window_ms, max_pending } => {
+                Box::new(MergeProcessor::new(key.clone(), *window_ms, *max_pending))
+            }
+            ProcessorConfig::Echo { label } => {
+                Box::new(EchoProcessor::new(label.clone()))
+            }
+            ProcessorConfig::Promote { from, to, index } => {
+                Box::new(PromoteProcessor::new(from.clone(), to.clone(), *index))
+            }
+            ProcessorConfig::Canonicalize => {
+                Box::new(CanonicalizeProcessor)
+            }
+            ProcessorConfig::MergePatch { patch } => {
+                Box::new(MergePatchProcessor::new(patch.clone()))
+            }
+            ProcessorConfig::Deadband { paths, max_keys } => {
+                Box::new(DeadbandProcessor::new(paths.clone(), *max_keys))
+            }
+            ProcessorConfig::Require { paths, on_missing } => {
+                Box::new(RequireProcessor::new(paths.clone(), *on_missing))
+            }
+            ProcessorConfig::Base64 { decode, encode, on_invalid } => {
+                Box::new(Base64Processor::new(decode.clone(), encode.clone(), *on_invalid))
+            }
+            ProcessorConfig::ToBoolean { paths, truthy, falsy, on_unrecognized } => {
+                Box::new(ToBooleanProcessor::new(paths.clone(), truthy.clone(), falsy.clone(), *on_unrecognized))
+            }
+            ProcessorConfig::ChangelogSplit => {
+                Box::new(ChangelogSplitProcessor)
+            }
+            ProcessorConfig::Anonymize { paths, salt } => {
+                Box::new(AnonymizeProcessor::new(paths.clone(), salt.resolve()?))
+            }
+            ProcessorConfig::AdfToText { paths } => {
+                Box::new(AdfToTextProcessor::new(paths.clone()))
+            }
+            ProcessorConfig::Stop { when } => {
+                Box::new(StopProcessor::new(when)?)
+            }
+        };
         
-        if let Some(slash_pos) = url_without_protocol.find('/') {
-            let base = &url[..url.len() - url_without_protocol.len() + slash_pos];
-            let path = &url_without_protocol[slash_pos + 1..];
-            
-            let database = path.split('?').next().unwrap_or(path).split('/').next().unwrap_or("");
-            
-            if database.is_empty() {
-                return Err(crate::error::AppError::Config(
-                    "MongoDB URL must include database name (format: mongodb://host:port/database)".to_string()
-                ));
+        if pipeline_processor.on_error == ProcessorErrorPolicy::Skip {
+            return Ok(Box::new(SkipOnError::new(processor, pipeline_processor.config.kind())));
+        }
+        
+        Ok(processor)
+    }
+    
+    /// Run the sample event through the processors, failing on the first processor that errors
+    async fn self_test(
+        pipeline_config: &Pipeline,
+        processors: &[Box<dyn Processor>],
+        sample: &SampleEvent,
+        pipeline_idx: usize,
+    ) -> Result<()> {
+        let mut current_event = PipelineEvent::new(
+            sample.body.clone(),
+            sample.event_type.clone(),
+            vec![],
+            Operation::Write,
+        );
+        
+        for (idx, processor) in processors.iter().enumerate() {
+            match processor.run(current_event).await {
+                Ok(ProcessorOutput::Continue(processed_event)) => current_event = *processed_event,
+                Ok(ProcessorOutput::Filtered | ProcessorOutput::Stopped) => {
+                    debug!("Sample event filtered out by processor {} in pipeline {}, skipping remaining self-test", idx, pipeline_idx);
+                    return Ok(());
+                }
+                Ok(ProcessorOutput::Split(events)) => match events.into_iter().next() {
+                    Some(first) => current_event = first,
+                    None => {
+                        debug!("Sample event split into no events by processor {} in pipeline {}, skipping remaining self-test", idx, pipeline_idx);
+                        return Ok(());
+                    }
+                },
+                Ok(ProcessorOutput::Held) => {
+                    // Don't let the sample event reach the sinks once its window closes
+                    processor.due(true);
+                    debug!("Sample event held by processor {} in pipeline {}, skipping remaining self-test", idx, pipeline_idx);
+                    return Ok(());
+                }
+                Err(e) => {
+                    return Err(AppError::Config(format!(
+                        "Pipeline {} processor {} ({}) failed on sample event: {}",
+                        pipeline_idx, idx, pipeline_config.processors[idx].config.kind(), e
+                    )));
+                }
             }
-            
-            Ok((base.to_string(), database.to_string()))
-        } else {
-            Err(crate::error::AppError::Config(
-                "MongoDB URL must include database name (format: mongodb://host:port/database)".to_string()
-            ))
         }
+        
+        info!("Pipeline {} passed self-test on sample event", pipeline_idx);
+        Ok(())
     }
     
     pub async fn run(self, mut receiver: PipelineReceiver) {
         info!("Pipeline executor started with {} pipelines", self.pipelines.len());
         
-        while let Some(event) = receiver.recv().await {
+        // The retry worker writes to the same sinks as the pipelines
+        let retry_worker = self.retry_queue.clone().map(|queue| {
+            let targets: RetryTargets = self.pipelines
+                .iter()
+                .flat_map(|workers| {
+                    let pipeline = &workers[0];
+                    pipeline.sinks.iter().map(|sink| {
+                        let target = RetryTarget { sink: sink.sink.clone(), timeout: sink.timeout };
+                        ((pipeline.name.clone(), sink.name.clone()), target)
+                    })
+                })
+                .collect();
+            tokio::spawn(queue.run(targets, self.dead_letter.clone()))
+        });
+        
+        // Each pipeline runs in its own tasks so a slow or hanging pipeline can't block the others
+        let mut workers = Vec::new();
+        let mut handles = Vec::new();
+        let pipelines: Vec<Vec<Arc<PipelineInstance>>> = self.pipelines
+            .into_iter()
+            .map(|workers| workers.into_iter().map(Arc::new).collect())
+            .collect();
+        for (idx, pipeline) in pipelines.iter().enumerate() {
+            let mut shards = Vec::new();
+            for worker in pipeline {
+                let (worker_tx, worker_rx) = mpsc::channel(PIPELINE_QUEUE_SIZE);
+                handles.push(tokio::spawn(Self::run_pipeline(
+                    worker.clone(),
+                    idx,
+                    self.status.register_worker(idx),
+                    worker_rx,
+                    self.status.clone(),
+                    self.dead_letter.clone(),
+                    self.retry_queue.clone(),
+                )));
+                shards.push(worker_tx);
+            }
+            workers.push(shards);
+        }
+        self.status.watch_queues(&workers);
+        
+        // Heartbeats let `/-/healthz` tell a stuck dispatch loop from an idle one
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        loop {
+            let event = tokio::select! {
+                event = receiver.recv() => event,
+                _ = heartbeat.tick() => {
+                    self.status.record_heartbeat();
+                    continue;
+                }
+            };
+            let Some(mut event) = event else {
+                break;
+            };
+            let handoff = event.handoff.take();
+            
             debug!("Received event: id={}, type={}", event.id, event.event_type);
             
-            // Process the event through all pipelines
-            for (idx, pipeline) in self.pipelines.iter().enumerate() {
-                if let Err(e) = self.process_event(&event, pipeline, idx).await {
-                    error!("Error processing event in pipeline {}: {}", idx, e);
+            // Events follow the routes of the integration they came from; a pipeline listed twice
+            // still gets the event once
+            let mut targets: Vec<usize> = match event.integration.and_then(|idx| self.routing.get(idx)) {
+                Some(table) => table.targets(&event.event_type).to_vec(),
+                None => self.routing.iter().flat_map(|table| table.targets(&event.event_type)).copied().collect(),
+            };
+            targets.sort_unstable();
+            targets.dedup();
+            if targets.is_empty() {
+                debug!("No pipeline is routed event type {}, skipping event {}", event.event_type, event.id);
+            }
+            
+            // Room is reserved in every target queue first, so the event goes to all of its
+            // pipelines or to none and a source retrying a refused event can't duplicate it
+            let mut permits = Vec::with_capacity(targets.len());
+            let mut refused = None;
+            for &idx in &targets {
+                let shard = Self::shard_of(&pipelines[idx], &event);
+                let reserve = workers[idx][shard].reserve();
+                
+                // Without a source to answer busy, wait for room rather than lose the event
+                let reserved = match &handoff {
+                    Some(_) => tokio::time::timeout(pipelines[idx][shard].queue_timeout, reserve).await,
+                    None => Ok(reserve.await),
+                };
+                match reserved {
+                    Ok(Ok(permit)) => permits.push(permit),
+                    Ok(Err(_)) => {
+                        error!("Pipeline {} worker has stopped, dropping event {}", idx, event.id);
+                        refused = Some(idx);
+                        break;
+                    }
+                    Err(_) => {
+                        warn!("Pipeline {} queue stayed full for {:?}, refusing event {}", idx, pipelines[idx][shard].queue_timeout, event.id);
+                        refused = Some(idx);
+                        break;
+                    }
+                }
+            }
+            
+            let accepted = refused.is_none() && handoff.as_ref().is_none_or(|handoff| handoff.accept());
+            if accepted {
+                for permit in permits {
+                    permit.send(event.clone());
                 }
+                continue;
             }
+            
+            // The source answers busy and the event is retried, unless nothing waits on it
+            drop(permits);
+            if let Some(idx) = refused {
+                let pipeline_label = idx.to_string();
+                if handoff.is_some() {
+                    metrics::global().increment("connectcare_pipeline_refused_events_total", &[("pipeline", &pipeline_label)]);
+                } else {
+                    metrics::global().increment("connectcare_pipeline_dropped_events_total", &[("pipeline", &pipeline_label)]);
+                    self.status.record_dropped();
+                    if let Some(ack) = &event.ack {
+                        ack.fail();
+                    }
+                }
+            }
+        }
+        
+        // Let the workers drain their queues
+        drop(workers);
+        for handle in handles {
+            let _ = handle.await;
+        }
+        
+        // Queued retries stay in MongoDB for the next start
+        if let Some(retry_worker) = retry_worker {
+            retry_worker.abort();
         }
         
         info!("Pipeline executor stopped");
     }
     
-    async fn process_event(&self, event: &PipelineEvent, pipeline: &PipelineInstance, pipeline_idx: usize) -> Result<()> {
-        let mut current_event = event.clone();
+    /// Worker of a pipeline an event goes to; events with the same ordering key always share one
+    fn shard_of(workers: &[Arc<PipelineInstance>], event: &PipelineEvent) -> usize {
+        if workers.len() <= 1 {
+            return 0;
+        }
+        
+        let mut hasher = DefaultHasher::new();
+        let by_path = match &workers[0].ordering_key {
+            OrderingKey::EventId => None,
+            OrderingKey::Path(key_path) => path::get(&event.body, key_path),
+        };
+        match by_path {
+            Some(value) => value.to_string().hash(&mut hasher),
+            None => event.id.hash(&mut hasher),
+        }
+        
+        (hasher.finish() % workers.len() as u64) as usize
+    }
+    
+    async fn run_pipeline(
+        pipeline: Arc<PipelineInstance>,
+        pipeline_idx: usize,
+        worker: usize,
+        mut receiver: PipelineReceiver,
+        status: Arc<PipelineStatus>,
+        dead_letter: Option<Arc<DeadLetterQueue>>,
+        retry_queue: Option<Arc<RetryQueue>>,
+    ) {
+        let retry_queue = retry_queue.as_deref();
+        
+        // Processors holding events back are polled for the ones whose time has come
+        let mut poll = pipeline.processors
+            .iter()
+            .filter_map(|processor| processor.poll_interval())
+            .min()
+            .map(tokio::time::interval);
+        
+        loop {
+            let events = tokio::select! {
+                received = receiver.recv() => match received {
+                    Some(event) => vec![(event, 0)],
+                    None => break,
+                },
+                _ = Self::next_poll(&mut poll) => Self::due_events(&pipeline, false),
+            };
+            
+            // Progress is recorded per event so `/-/healthz` can tell a stuck worker from a busy one
+            for (event, first_processor) in events {
+                status.record_worker_busy(worker);
+                Self::handle_event(event, first_processor, &pipeline, pipeline_idx, &status, &dead_letter, retry_queue).await;
+            }
+            status.record_worker_idle(worker);
+        }
+        
+        // Release whatever is still held rather than lose it on shutdown
+        for (event, first_processor) in Self::due_events(&pipeline, true) {
+            Self::handle_event(event, first_processor, &pipeline, pipeline_idx, &status, &dead_letter, retry_queue).await;
+        }
+    }
+    
+    async fn next_poll(poll: &mut Option<tokio::time::Interval>) {
+        match poll {
+            Some(interval) => {
+                interval.tick().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+    
+    /// Events released by holding processors, each paired with the processor it continues from
+    fn due_events(pipeline: &PipelineInstance, all: bool) -> Vec<(PipelineEvent, usize)> {
+        pipeline.processors
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, processor)| processor.due(all).into_iter().map(move |event| (event, idx + 1)))
+            .collect()
+    }
+    
+    async fn handle_event(
+        event: PipelineEvent,
+        first_processor: usize,
+        pipeline: &PipelineInstance,
+        pipeline_idx: usize,
+        status: &PipelineStatus,
+        dead_letter: &Option<Arc<DeadLetterQueue>>,
+        retry_queue: Option<&RetryQueue>,
+    ) {
+        let span = info_span!(
+            "pipeline",
+            pipeline = pipeline_idx,
+            event_id = %event.id,
+            event_type = %event.event_type,
+            delivery_id = %event.delivery_id,
+        );
+        let ack = event.ack.clone();
+        let event_id = event.id.clone();
+        let captured = (dead_letter.is_some() || debug::failures().is_enabled())
+            .then(|| (event.id.clone(), event.event_type.clone(), event.body.clone(), event.raw_body.clone()));
+        let mut failed_body = None;
         
-        // Process through all processors
-        for (idx, processor) in pipeline.processors.iter().enumerate() {
-            match processor.process(current_event).await? {
-                Some(processed_event) => {
-                    current_event = processed_event;
-                    debug!("Event passed through processor {} in pipeline {}", idx, pipeline_idx);
+        let processing = Self::process_event(event, first_processor, pipeline, pipeline_idx, status, retry_queue, &mut failed_body)
+            .instrument(span);
+        
+        // Dropping the future on timeout cancels whatever processor or sink write is in flight
+        let result = match pipeline.max_processing {
+            Some(limit) => tokio::time::timeout(limit, processing).await.unwrap_or_else(|_| {
+                metrics::global().increment(
+                    "connectcare_pipeline_timeouts_total",
+                    &[("pipeline", &pipeline_idx.to_string())],
+                );
+                Err(AppError::Processing(format!("Processing exceeded {:?}, event abandoned", limit)))
+            }),
+            None => processing.await,
+        };
+        
+        if let Err(e) = &result {
+            error!("Error processing event in pipeline {}: {}", pipeline_idx, e);
+            status.record_failed();
+            Self::forget(pipeline, &event_id);
+            
+            if let Some((event_id, event_type, entered_body, raw_body)) = captured {
+                debug::failures().record(&format!("pipeline {}", pipeline_idx), Some(&event_type), &raw_body, &e.to_string());
+                
+                // The body a sink failed to write, otherwise the one the event entered the
+                // pipeline with, as a failing processor or the time limit consumed the event
+                if let Some(dead_letter) = dead_letter {
+                    let body = Arc::unwrap_or_clone(failed_body.unwrap_or(entered_body));
+                    dead_letter.push(pipeline_idx, event_id, event_type, body, raw_body, e.to_string());
+                }
+            }
+            
+            // A no-op if a required sink already acknowledged the event
+            if pipeline.has_required_sinks() {
+                if let Some(ack) = ack {
+                    ack.fail();
+                }
+            }
+        } else if result.is_ok_and(|completed| completed) {
+            // Held events are counted once they are released and run to the end
+            status.record_processed();
+        }
+    }
+    
+    /// Run an event through the processors from `first_processor` on, then write it to the sinks
+    ///
+    /// Returns whether the run completed, rather than leaving every event it became held by a
+    /// processor. The body of an event that failed to be written is left in `failed_body`.
+    async fn process_event(
+        event: PipelineEvent,
+        first_processor: usize,
+        pipeline: &PipelineInstance,
+        pipeline_idx: usize,
+        status: &PipelineStatus,
+        retry_queue: Option<&RetryQueue>,
+        failed_body: &mut Option<Arc<serde_json::Value>>,
+    ) -> Result<bool> {
+        // Events split off by a processor go on from the processor after it, in order
+        let mut pending = vec![(event, first_processor)];
+        let mut result = Ok(());
+        let mut completed = false;
+        
+        'events: while let Some((mut current_event, first)) = pending.pop() {
+            // Process through the remaining processors
+            for (idx, processor) in pipeline.processors.iter().enumerate().skip(first) {
+                let started = Instant::now();
+                let output = processor.run(current_event).await;
+                metrics::global().observe_duration(
+                    "connectcare_processor_duration_seconds",
+                    &[
+                        ("pipeline", &pipeline_idx.to_string()),
+                        ("processor", &idx.to_string()),
+                        ("kind", pipeline.processor_kinds[idx]),
+                    ],
+                    started.elapsed(),
+                );
+                
+                match output? {
+                    ProcessorOutput::Continue(processed_event) => {
+                        current_event = *processed_event;
+                        debug!("Event passed through processor {} in pipeline {}", idx, pipeline_idx);
+                    }
+                    ProcessorOutput::Filtered => {
+                        debug!("Event filtered out by processor {} in pipeline {}", idx, pipeline_idx);
+                        completed = true;
+                        continue 'events; // Event was filtered out
+                    }
+                    ProcessorOutput::Stopped => {
+                        info!("Event stopped by processor {} in pipeline {}, skipping sinks", idx, pipeline_idx);
+                        metrics::global().increment(
+                            "connectcare_pipeline_stopped_events_total",
+                            &[("pipeline", &pipeline_idx.to_string())],
+                        );
+                        completed = true;
+                        continue 'events;
+                    }
+                    ProcessorOutput::Held => {
+                        debug!("Event held by processor {} in pipeline {}", idx, pipeline_idx);
+                        continue 'events;
+                    }
+                    ProcessorOutput::Split(events) => {
+                        debug!("Event split into {} events by processor {} in pipeline {}", events.len(), idx, pipeline_idx);
+                        completed |= events.is_empty();
+                        pending.extend(events.into_iter().rev().map(|event| (event, idx + 1)));
+                        continue 'events;
+                    }
                 }
-                None => {
-                    debug!("Event filtered out by processor {} in pipeline {}", idx, pipeline_idx);
-                    return Ok(()); // Event was filtered out
+            }
+            
+            if let Some((key, version)) = &pipeline.schema_version {
+                match current_event.body_mut().as_object_mut() {
+                    Some(body) => {
+                        body.insert(key.clone(), (*version).into());
+                    }
+                    None => debug!("Event {} body is not an object, not stamping its schema version", current_event.id),
                 }
             }
+            
+            debug::live_events().publish(pipeline_idx, &current_event);
+            
+            // Every split event gets written even if an earlier one failed
+            if let Err(e) = Self::write_to_sinks(&current_event, pipeline, pipeline_idx, status, retry_queue).await {
+                Self::forget(pipeline, &current_event.id);
+                *failed_body = Some(current_event.body);
+                result = Err(e);
+            }
+            completed = true;
         }
         
-        // Write to all sinks
-        for (idx, sink) in pipeline.sinks.iter().enumerate() {
-            match sink.write(&current_event).await {
-                Ok(_) => {
+        result.map(|()| completed)
+    }
+    
+    /// Make the processors forget a failed event, so its redelivery isn't taken for a duplicate
+    fn forget(pipeline: &PipelineInstance, event_id: &str) {
+        for processor in &pipeline.processors {
+            processor.forget(event_id);
+        }
+    }
+    
+    /// Write a processed event to every sink of the pipeline that takes its operation
+    async fn write_to_sinks(
+        current_event: &PipelineEvent,
+        pipeline: &PipelineInstance,
+        pipeline_idx: usize,
+        status: &PipelineStatus,
+        retry_queue: Option<&RetryQueue>,
+    ) -> Result<()> {
+        // Write to all sinks, required ones first so the source isn't kept waiting on the others
+        let mut ordered: Vec<_> = pipeline.sinks.iter().enumerate().collect();
+        ordered.sort_by_key(|(_, sink)| !sink.required);
+        
+        let mut failed_sinks = 0;
+        for (idx, sink) in ordered {
+            if !sink.accepts(&current_event.operation) {
+                debug!("Sink {} in pipeline {} doesn't take {:?} events, skipping it", idx, pipeline_idx, current_event.operation);
+                continue;
+            }
+            
+            let enveloped;
+            let payload_event = match sink.payload {
+                SinkPayload::Body => current_event,
+                SinkPayload::Envelope => {
+                    enveloped = PipelineEvent { body: Arc::new(current_event.envelope()), ..current_event.clone() };
+                    &enveloped
+                }
+            };
+            
+            // An open circuit fails the write without trying it
+            let written = match &sink.breaker {
+                Some(breaker) if !breaker.allow() => None,
+                _ => {
+                    let started = Instant::now();
+                    let written = tokio::time::timeout(sink.timeout, sink.sink.write(payload_event)).await;
+                    metrics::global().observe_duration(
+                        "connectcare_sink_write_duration_seconds",
+                        &[("pipeline", &pipeline_idx.to_string()), ("sink", &idx.to_string()), ("name", &sink.name)],
+                        started.elapsed(),
+                    );
+                    Some(written)
+                }
+            };
+            if let (Some(breaker), Some(written)) = (&sink.breaker, &written) {
+                match written {
+                    Ok(Ok(_)) => breaker.record_success(),
+                    _ => breaker.record_failure(),
+                }
+            }
+            
+            let (reason, message) = match written {
+                Some(Ok(Ok(outcome))) => {
                     debug!("Event written to sink {} in pipeline {}", idx, pipeline_idx);
+                    if sink.required {
+                        if let Some(ack) = &current_event.ack {
+                            ack.acknowledge(outcome);
+                        }
+                    }
+                    if let Some(retry_queue) = retry_queue {
+                        if let Err(e) = retry_queue.written(&pipeline.name, &sink.name, payload_event).await {
+                            error!("Failed to drop queued retries of event {} superseded by its write: {}", current_event.id, e);
+                        }
+                    }
+                    continue;
                 }
-                Err(e) => {
-                    error!("Failed to write event to sink {} in pipeline {}: {}", idx, pipeline_idx, e);
-                    // Continue to other sinks even if one fails
+                Some(Ok(Err(e))) => ("error", e.to_string()),
+                Some(Err(_)) => ("timeout", format!("timed out after {:?}", sink.timeout)),
+                None => ("circuit_open", "circuit open, write skipped".to_string()),
+            };
+            
+            // Continue to other sinks even if one fails
+            if sink.tap {
+                warn!("Failed to mirror event to tap sink {} in pipeline {}: {}", idx, pipeline_idx, message);
+                metrics::global().increment(
+                    "connectcare_tap_write_failures_total",
+                    &[("pipeline", &pipeline_idx.to_string()), ("sink", &idx.to_string())],
+                );
+            } else {
+                // The breaker already logged the sink going down, once
+                if reason == "circuit_open" {
+                    debug!("Skipped write to sink {} in pipeline {}: {}", idx, pipeline_idx, message);
+                } else {
+                    error!("Failed to write event to sink {} in pipeline {}: {}", idx, pipeline_idx, message);
                 }
+                Self::record_sink_failure(pipeline_idx, idx, reason);
+                status.record_sink_error(pipeline_idx, idx);
+                
+                // A queued write is no longer a failure of the event, but it isn't persisted yet, so
+                // a source waiting on a required sink is still told it failed
+                if let Some(retry_queue) = retry_queue {
+                    match retry_queue.enqueue(&pipeline.name, pipeline_idx, &sink.name, payload_event, &message).await {
+                        Ok(()) => {
+                            warn!("Queued event {} for retry on sink {} in pipeline {}", current_event.id, idx, pipeline_idx);
+                            if sink.required {
+                                if let Some(ack) = &current_event.ack {
+                                    ack.fail();
+                                }
+                            }
+                            continue;
+                        }
+                        Err(e) => error!("Failed to queue event {} for retry: {}", current_event.id, e),
+                    }
+                }
+                failed_sinks += 1;
             }
         }
         
+        if failed_sinks > 0 {
+            return Err(AppError::Sink(format!("{} of {} sinks failed", failed_sinks, pipeline.sinks.len())));
+        }
+        
         Ok(())
     }
+    
+    fn record_sink_failure(pipeline_idx: usize, sink_idx: usize, reason: &str) {
+        metrics::global().increment(
+            "connectcare_sink_write_failures_total",
+            &[
+                ("pipeline", &pipeline_idx.to_string()),
+                ("sink", &sink_idx.to_string()),
+                ("reason", reason),
+            ],
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Integration;
+    use crate::pipeline::dead_letter::DeadLetterConfig;
+    use crate::pipeline::ack::{ack_channel, AckOutcome};
+    use crate::pipeline::event::PkField;
+    use crate::pipeline::sinks::{SinkConfig, SinkFactory, SinkOutcome};
+    use crate::metrics::{Metrics, MetricsConfig};
+    use crate::pipeline::status::HealthConfig;
+    use serde_json::json;
+    use std::sync::Mutex;
+    
+    impl PipelineExecutor {
+        /// An executor sending every event to all `pipelines`, each run by a single worker, without
+        /// dead-letter or retry queues
+        fn for_test(pipelines: Vec<PipelineInstance>) -> Self {
+            Self {
+                routing: vec![RoutingTable::all(0..pipelines.len())],
+                pipelines: pipelines.into_iter().map(|pipeline| vec![pipeline]).collect(),
+                status: Arc::default(),
+                dead_letter: None,
+                retry_queue: None,
+            }
+        }
+    }
+    
+    impl PipelineInstance {
+        /// A pipeline worker without a processing limit or schema version
+        fn for_test(processors: Vec<Box<dyn Processor>>, sinks: Vec<SinkInstance>) -> Self {
+            Self {
+                name: "test".to_string(),
+                processor_kinds: vec!["test"; processors.len()],
+                processors,
+                sinks: sinks.into(),
+                max_processing: None,
+                ordering_key: OrderingKey::EventId,
+                queue_timeout: Duration::from_secs(1),
+                schema_version: None,
+            }
+        }
+    }
+    
+    impl SinkInstance {
+        /// A sink taking every operation with a one second timeout, neither tap nor required
+        fn for_test(sink: Arc<dyn Sink>) -> Self {
+            Self {
+                sink,
+                name: "test".to_string(),
+                timeout: Duration::from_secs(1),
+                tap: false,
+                required: false,
+                operations: vec![],
+                breaker: None,
+                payload: SinkPayload::Body,
+            }
+        }
+    }
+    
+    /// Sink that sleeps before succeeding
+    struct SlowSink {
+        delay: Duration,
+    }
+    
+    #[async_trait::async_trait]
+    impl Sink for SlowSink {
+        async fn write(&self, _event: &PipelineEvent) -> Result<SinkOutcome> {
+            tokio::time::sleep(self.delay).await;
+            Ok(SinkOutcome::default())
+        }
+    }
+    
+    /// Processor that never completes
+    struct HangingProcessor;
+    
+    #[async_trait::async_trait]
+    impl Processor for HangingProcessor {
+        async fn process(&self, _event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+            std::future::pending().await
+        }
+    }
+    
+    /// Processor that stalls on events marked `"slow": true` and passes the rest through
+    struct StallingProcessor;
+    
+    #[async_trait::async_trait]
+    impl Processor for StallingProcessor {
+        async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+            if event.body["slow"] == true {
+                tokio::time::sleep(Duration::from_secs(30)).await;
+            }
+            Ok(Some(event))
+        }
+    }
+    
+    /// Sink that always fails
+    struct FailingSink;
+    
+    #[async_trait::async_trait]
+    impl Sink for FailingSink {
+        async fn write(&self, _event: &PipelineEvent) -> Result<SinkOutcome> {
+            Err(AppError::Database("unreachable".to_string()))
+        }
+    }
+    
+    /// Sink that counts write attempts and fails until marked healthy
+    #[derive(Default)]
+    struct FlakySink {
+        attempts: std::sync::atomic::AtomicUsize,
+        healthy: std::sync::atomic::AtomicBool,
+    }
+    
+    #[async_trait::async_trait]
+    impl Sink for FlakySink {
+        async fn write(&self, _event: &PipelineEvent) -> Result<SinkOutcome> {
+            self.attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if self.healthy.load(std::sync::atomic::Ordering::SeqCst) {
+                Ok(SinkOutcome::default())
+            } else {
+                Err(AppError::Database("unreachable".to_string()))
+            }
+        }
+    }
+    
+    /// Processor that sleeps for the body's `delay_ms` before passing the event on
+    struct DelayingProcessor;
+    
+    #[async_trait::async_trait]
+    impl Processor for DelayingProcessor {
+        async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+            if let Some(delay) = event.body["delay_ms"].as_u64() {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+            }
+            Ok(Some(event))
+        }
+    }
+    
+    /// Sink that records the ids and bodies of written events
+    #[derive(Default)]
+    struct RecordingSink {
+        written: Mutex<Vec<String>>,
+        bodies: Mutex<Vec<serde_json::Value>>,
+    }
+    
+    #[async_trait::async_trait]
+    impl Sink for RecordingSink {
+        async fn write(&self, event: &PipelineEvent) -> Result<SinkOutcome> {
+            self.written.lock().unwrap().push(event.id.clone());
+            self.bodies.lock().unwrap().push((*event.body).clone());
+            Ok(SinkOutcome::default())
+        }
+    }
+    
+    fn test_event() -> PipelineEvent {
+        PipelineEvent::new(
+            json!({"id": "1"}),
+            "test_event".to_string(),
+            vec![],
+            Operation::Write,
+        )
+    }
+    
+    fn config_with_pipeline(pipeline: serde_json::Value) -> AppConfig {
+        let mut integration: Integration = serde_json::from_value(json!({
+            "source": {
+                "type": "jira",
+                "authentication": { "secret": "test_secret" }
+            }
+        })).unwrap();
+        integration.pipelines.push(serde_json::from_value(pipeline).unwrap());
+        
+        AppConfig {
+            integrations: vec![integration],
+            base_path: None,
+            debug: None,
+            sink_connect_retry: ConnectRetry::default(),
+            tls: None,
+            dead_letter: None,
+            retry_queue: None,
+            health: HealthConfig::default(),
+            max_in_flight: None,
+            metrics: MetricsConfig::default(),
+            outbound_limit: None,
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_self_test_passes_on_valid_sample() {
+        let config = config_with_pipeline(json!({
+            "processors": [
+                { "type": "mapper", "outputEvent": { "id": { "value": "{{ issue.id }}", "castTo": "number" } } }
+            ],
+            "sinks": [],
+            "sample_event": {
+                "event_type": "jira:issue_created",
+                "body": { "issue": { "id": "123" } }
+            }
+        }));
+        
+        assert!(PipelineExecutor::new(&config).await.is_ok());
+    }
+    
+    #[tokio::test]
+    async fn test_self_test_fails_startup_on_mapper_error() {
+        let config = config_with_pipeline(json!({
+            "processors": [
+                { "type": "filter", "celExpression": "eventType == 'jira:issue_created'" },
+                { "type": "mapper", "outputEvent": { "id": { "value": "{{ issue.key }}", "castTo": "number" } } }
+            ],
+            "sinks": [],
+            "sample_event": {
+                "event_type": "jira:issue_created",
+                "body": { "issue": { "key": "TEST-1" } }
+            }
+        }));
+        
+        let error = PipelineExecutor::new(&config).await.err().unwrap();
+        
+        match error {
+            AppError::Config(msg) => {
+                assert!(msg.contains("processor 1 (mapper)"), "unexpected message: {}", msg);
+            }
+            other => panic!("expected config error, got {:?}", other),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_check_connectivity_reports_unreachable_mongo() {
+        let mut config = config_with_pipeline(json!({
+            "sinks": [
+                { "type": "mongo", "url": "mongodb://127.0.0.1:1/connectcare", "collection": "issues" }
+            ]
+        }));
+        config.debug = Some(serde_json::from_value(json!({ "token": { "fromEnv": "CONNECTCARE_TEST_UNSET_DEBUG_TOKEN" } })).unwrap());
+        
+        let failures = PipelineExecutor::check_connectivity(&config, &SinkRegistry::default(), Duration::from_millis(200)).await;
+        
+        let targets: Vec<&str> = failures.iter().map(|failure| failure.target.as_str()).collect();
+        assert_eq!(targets, vec!["debug token", "pipeline 0 sink 0 (mongo)"]);
+        assert!(failures[1].error.contains("no answer"), "unexpected error: {}", failures[1].error);
+    }
+    
+    /// Factory handing out a shared `RecordingSink` for sinks whose `target` is `audit`
+    struct RecordingSinkFactory {
+        sink: Arc<RecordingSink>,
+    }
+    
+    #[async_trait::async_trait]
+    impl SinkFactory for RecordingSinkFactory {
+        async fn connect(&self, config: &SinkConfig) -> Result<Arc<dyn Sink>> {
+            let SinkConfig::Custom { settings, .. } = config else {
+                return Err(AppError::Config("recording sink expects custom settings".to_string()));
+            };
+            if settings.get("target") != Some(&json!("audit")) {
+                return Err(AppError::Config("recording sink only writes to audit".to_string()));
+            }
+            
+            Ok(self.sink.clone())
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_custom_sink_type_is_built_by_registered_factory() {
+        let config = config_with_pipeline(json!({
+            "sinks": [{ "type": "recording", "target": "audit", "tap": true }]
+        }));
+        assert_eq!(config.integrations[0].pipelines[0].sinks[0].config.kind(), "recording");
+        assert!(config.integrations[0].pipelines[0].sinks[0].tap);
+        
+        let error = PipelineExecutor::new(&config).await.err().expect("unknown sink type should fail");
+        assert!(error.to_string().contains("recording"), "unexpected error: {}", error);
+        
+        let recording = Arc::new(RecordingSink::default());
+        let mut sinks = SinkRegistry::default();
+        sinks.register("recording", RecordingSinkFactory { sink: recording.clone() });
+        let executor = PipelineExecutor::with_sinks(&config, &sinks).await.unwrap();
+        
+        let event = test_event();
+        let id = event.id.clone();
+        PipelineExecutor::process_event(event, 0, &executor.pipelines[0][0], 0, &PipelineStatus::default(), None, &mut None).await.unwrap();
+        assert_eq!(*recording.written.lock().unwrap(), vec![id]);
+    }
+    
+    #[tokio::test]
+    async fn test_injected_primary_key_reaches_the_sink() {
+        let config = config_with_pipeline(json!({
+            "processors": [{ "type": "injectPrimaryKey", "key": "_key" }],
+            "sinks": [{ "type": "recording", "target": "audit" }]
+        }));
+        let recording = Arc::new(RecordingSink::default());
+        let mut sinks = SinkRegistry::default();
+        sinks.register("recording", RecordingSinkFactory { sink: recording.clone() });
+        let executor = PipelineExecutor::with_sinks(&config, &sinks).await.unwrap();
+        
+        let event = PipelineEvent::new(
+            json!({"issue": {"id": "10001"}}),
+            "jira:issue_created".to_string(),
+            vec![PkField { key: "issue.id".to_string(), value: "10001".to_string() }],
+            Operation::Write,
+        );
+        let id = event.id.clone();
+        PipelineExecutor::process_event(event, 0, &executor.pipelines[0][0], 0, &PipelineStatus::default(), None, &mut None).await.unwrap();
+        
+        let bodies = recording.bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 1);
+        assert_eq!(bodies[0]["_key"]["id"], json!(id));
+        assert_eq!(bodies[0]["_key"]["fields"], json!({"issue.id": "10001"}));
+        assert_eq!(*recording.written.lock().unwrap(), vec![id]);
+    }
+    
+    #[tokio::test]
+    async fn test_sink_connects_on_third_attempt() {
+        let retry = ConnectRetry { attempts: 3, initial_backoff_ms: 1, max_backoff_ms: 5 };
+        let attempts = Mutex::new(0);
+        
+        let sink = PipelineExecutor::with_connect_retry(&retry, || async {
+            let mut attempts = attempts.lock().unwrap();
+            *attempts += 1;
+            if *attempts < 3 {
+                return Err(AppError::Database("connection refused".to_string()));
+            }
+            Ok(Arc::new(RecordingSink::default()))
+        }).await;
+        
+        assert!(sink.is_ok());
+        assert_eq!(*attempts.lock().unwrap(), 3);
+    }
+    
+    #[tokio::test]
+    async fn test_sink_connect_gives_up_after_attempts() {
+        let fail_fast = ConnectRetry { attempts: 1, ..ConnectRetry::default() };
+        let attempts = Mutex::new(0);
+        
+        let result: Result<()> = PipelineExecutor::with_connect_retry(&fail_fast, || async {
+            *attempts.lock().unwrap() += 1;
+            Err(AppError::Database("connection refused".to_string()))
+        }).await;
+        
+        assert!(result.is_err());
+        assert_eq!(*attempts.lock().unwrap(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_sink_config_errors_are_not_retried() {
+        let retry = ConnectRetry { attempts: 5, initial_backoff_ms: 1, max_backoff_ms: 5 };
+        let attempts = Mutex::new(0);
+        
+        let result: Result<()> = PipelineExecutor::with_connect_retry(&retry, || async {
+            *attempts.lock().unwrap() += 1;
+            Err(AppError::Config("invalid url".to_string()))
+        }).await;
+        
+        assert!(matches!(result, Err(AppError::Config(_))));
+        assert_eq!(*attempts.lock().unwrap(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_sink_write_timeout_does_not_block_other_sinks() {
+        let recorder = Arc::new(RecordingSink::default());
+        let pipeline = PipelineInstance::for_test(
+            vec![],
+            vec![
+                SinkInstance {
+                    timeout: Duration::from_millis(20),
+                    ..SinkInstance::for_test(Arc::new(SlowSink { delay: Duration::from_secs(30) }))
+                },
+                SinkInstance::for_test(recorder.clone()),
+            ],
+        );
+        let metrics = Metrics::scoped();
+        
+        let event = test_event();
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            PipelineExecutor::process_event(event.clone(), 0, &pipeline, 0, &PipelineStatus::default(), None, &mut None),
+        ).await;
+        
+        assert!(result.expect("executor blocked on slow sink").is_err());
+        assert_eq!(*recorder.written.lock().unwrap(), vec![event.id.clone()]);
+        assert_eq!(
+            metrics.counter("connectcare_sink_write_failures_total", &[("pipeline", "0"), ("sink", "0"), ("reason", "timeout")]),
+            1
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_tap_sink_failure_does_not_fail_event() {
+        let recorder = Arc::new(RecordingSink::default());
+        let pipeline = PipelineInstance::for_test(
+            vec![],
+            vec![
+                SinkInstance::for_test(recorder.clone()),
+                SinkInstance { tap: true, ..SinkInstance::for_test(Arc::new(FailingSink)) },
+            ],
+        );
+        let metrics = Metrics::scoped();
+        
+        let event = test_event();
+        
+        assert!(PipelineExecutor::process_event(event, 0, &pipeline, 0, &PipelineStatus::default(), None, &mut None).await.is_ok());
+        assert_eq!(recorder.written.lock().unwrap().len(), 1);
+        assert_eq!(metrics.counter("connectcare_sink_write_failures_total", &[("pipeline", "0"), ("sink", "1"), ("reason", "error")]), 0);
+        assert_eq!(metrics.counter("connectcare_tap_write_failures_total", &[("pipeline", "0"), ("sink", "1")]), 1);
+    }
+    
+    /// Sink that keeps the bodies it is given
+    #[derive(Default)]
+    struct BodySink {
+        bodies: Mutex<Vec<serde_json::Value>>,
+    }
+    
+    #[async_trait::async_trait]
+    impl Sink for BodySink {
+        async fn write(&self, event: &PipelineEvent) -> Result<SinkOutcome> {
+            self.bodies.lock().unwrap().push((*event.body).clone());
+            Ok(SinkOutcome::default())
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_envelope_payload_wraps_body_with_metadata() {
+        let plain = Arc::new(BodySink::default());
+        let enveloped = Arc::new(BodySink::default());
+        let sink = |sink: Arc<BodySink>, payload| SinkInstance { payload, ..SinkInstance::for_test(sink) };
+        let pipeline = PipelineInstance::for_test(
+            vec![],
+            vec![sink(plain.clone(), SinkPayload::Body), sink(enveloped.clone(), SinkPayload::Envelope)],
+        );
+        
+        let event = PipelineEvent::new(
+            json!({"id": "1"}),
+            "jira:issue_deleted".to_string(),
+            vec![PkField { key: "issue.id".to_string(), value: "1".to_string() }],
+            Operation::Delete,
+        );
+        let (id, delivery_id, received_at) = (event.id.clone(), event.delivery_id.clone(), event.received_at);
+        PipelineExecutor::process_event(event, 0, &pipeline, 0, &PipelineStatus::default(), None, &mut None).await.unwrap();
+        
+        assert_eq!(*plain.bodies.lock().unwrap(), vec![json!({"id": "1"})]);
+        assert_eq!(*enveloped.bodies.lock().unwrap(), vec![json!({
+            "id": id,
+            "event_type": "jira:issue_deleted",
+            "operation": "delete",
+            "pk_fields": [{ "key": "issue.id", "value": "1" }],
+            "delivery_id": delivery_id,
+            "received_at": received_at,
+            "body": { "id": "1" }
+        })]);
+    }
+    
+    #[tokio::test]
+    async fn test_split_events_continue_from_next_processor_to_sinks() {
+        let sink = Arc::new(BodySink::default());
+        let pipeline = PipelineInstance::for_test(
+            vec![Box::new(ChangelogSplitProcessor), Box::new(CanonicalizeProcessor)],
+            vec![SinkInstance::for_test(sink.clone())],
+        );
+        
+        let event = PipelineEvent::new(
+            json!({
+                "issue": { "id": "1" },
+                "changelog": { "id": "7", "items": [
+                    { "field": "status", "fromString": "Open", "toString": "Done" },
+                    { "field": "priority", "fromString": "Low", "toString": "High" }
+                ] }
+            }),
+            "jira:issue_updated".to_string(),
+            vec![],
+            Operation::Write,
+        );
+        PipelineExecutor::process_event(event, 0, &pipeline, 0, &PipelineStatus::default(), None, &mut None).await.unwrap();
+        
+        let bodies = sink.bodies.lock().unwrap();
+        let fields: Vec<&str> = bodies.iter().map(|body| body["field"].as_str().unwrap()).collect();
+        assert_eq!(fields, vec!["status", "priority"]);
+        // Canonicalized after the split
+        let keys: Vec<&String> = bodies[0].as_object().unwrap().keys().collect();
+        assert_eq!(keys, vec!["changelogId", "field", "fromString", "issueId", "toString"]);
+    }
+    
+    #[tokio::test]
+    async fn test_skip_processor_error_lets_event_reach_sink() {
+        let pipeline: Pipeline = serde_json::from_value(json!({
+            "processors": [
+                { "type": "require", "paths": ["issue.fields.team"], "onProcessorError": "skip" },
+                { "type": "mapper", "outputEvent": { "key": "{{ issue.key }}" } }
+            ],
+            "sinks": []
+        })).unwrap();
+        let sink = Arc::new(BodySink::default());
+        let instance = PipelineInstance::for_test(
+            PipelineExecutor::build_processors(&pipeline).await.unwrap(),
+            vec![SinkInstance::for_test(sink.clone())],
+        );
+        let skipped = || metrics::global().counter("connectcare_processor_errors_skipped_total", &[("processor", "require")]);
+        let before = skipped();
+        
+        let event = PipelineEvent::new(json!({"issue": {"key": "PROJ-1"}}), "jira:issue_created".to_string(), vec![], Operation::Write);
+        PipelineExecutor::process_event(event, 0, &instance, 0, &PipelineStatus::default(), None, &mut None).await.unwrap();
+        
+        assert_eq!(*sink.bodies.lock().unwrap(), vec![json!({"key": "PROJ-1"})]);
+        assert!(skipped() > before);
+        
+        // The default still fails the event
+        let mut failing = pipeline.clone();
+        failing.processors[0].on_error = ProcessorErrorPolicy::Fail;
+        let instance = PipelineInstance { processors: PipelineExecutor::build_processors(&failing).await.unwrap(), ..instance };
+        let event = PipelineEvent::new(json!({"issue": {"key": "PROJ-2"}}), "jira:issue_created".to_string(), vec![], Operation::Write);
+        assert!(PipelineExecutor::process_event(event, 0, &instance, 0, &PipelineStatus::default(), None, &mut None).await.is_err());
+        assert_eq!(sink.bodies.lock().unwrap().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_schema_version_is_stamped_into_sunk_documents() {
+        let plain = Arc::new(BodySink::default());
+        let enveloped = Arc::new(BodySink::default());
+        let sink = |sink: Arc<BodySink>, payload| SinkInstance { payload, ..SinkInstance::for_test(sink) };
+        let pipeline: Pipeline = serde_json::from_value(json!({ "sinks": [], "schema_version": 3 })).unwrap();
+        let instance = PipelineInstance {
+            schema_version: pipeline.schema_version.map(|version| (pipeline.schema_version_key.clone(), version)),
+            ..PipelineInstance::for_test(
+                vec![],
+                vec![sink(plain.clone(), SinkPayload::Body), sink(enveloped.clone(), SinkPayload::Envelope)],
+            )
+        };
+        
+        let event = PipelineEvent::new(json!({"id": "1"}), "jira:issue_created".to_string(), vec![], Operation::Write);
+        PipelineExecutor::process_event(event, 0, &instance, 0, &PipelineStatus::default(), None, &mut None).await.unwrap();
+        
+        assert_eq!(*plain.bodies.lock().unwrap(), vec![json!({"id": "1", "schemaVersion": 3})]);
+        assert_eq!(enveloped.bodies.lock().unwrap()[0]["body"], json!({"id": "1", "schemaVersion": 3}));
+    }
+    
+    #[tokio::test]
+    async fn test_integration_default_processors_run_ahead_of_each_pipeline() {
+        let mut config = config_with_pipeline(json!({
+            "processors": [{ "type": "mergePatch", "patch": { "token": "own" } }],
+            "sinks": []
+        }));
+        let integration = &mut config.integrations[0];
+        integration.default_processors = vec![serde_json::from_value(json!({ "type": "mergePatch", "patch": { "token": null } })).unwrap()];
+        integration.pipelines.push(serde_json::from_value(json!({ "sinks": [] })).unwrap());
+        integration.pipelines.push(serde_json::from_value(json!({ "sinks": [], "skip_default_processors": true })).unwrap());
+        
+        let mut executor = PipelineExecutor::new(&config).await.unwrap();
+        let sinks: Vec<Arc<BodySink>> = executor.pipelines.iter().map(|_| Arc::new(BodySink::default())).collect();
+        for (workers, sink) in executor.pipelines.iter_mut().zip(&sinks) {
+            workers[0].sinks = vec![SinkInstance::for_test(sink.clone())].into();
+        }
+        
+        for (idx, workers) in executor.pipelines.iter().enumerate() {
+            let event = PipelineEvent::new(json!({"id": "1", "token": "secret"}), "jira:issue_created".to_string(), vec![], Operation::Write);
+            PipelineExecutor::process_event(event, 0, &workers[0], idx, &PipelineStatus::default(), None, &mut None).await.unwrap();
+        }
+        
+        // The default runs first, then the pipeline's own processors
+        assert_eq!(*sinks[0].bodies.lock().unwrap(), vec![json!({"id": "1", "token": "own"})]);
+        assert_eq!(*sinks[1].bodies.lock().unwrap(), vec![json!({"id": "1"})]);
+        // Opted out
+        assert_eq!(*sinks[2].bodies.lock().unwrap(), vec![json!({"id": "1", "token": "secret"})]);
+    }
+    
+    #[tokio::test]
+    async fn test_processor_and_sink_durations_are_recorded() {
+        let pipeline: Pipeline = serde_json::from_value(json!({
+            "processors": [
+                { "type": "mapper", "outputEvent": { "key": "{{ issue.key }}" } },
+                { "type": "canonicalize" }
+            ],
+            "sinks": []
+        })).unwrap();
+        let instance = PipelineInstance {
+            processor_kinds: vec!["mapper", "canonicalize"],
+            ..PipelineInstance::for_test(
+                PipelineExecutor::build_processors(&pipeline).await.unwrap(),
+                vec![SinkInstance { name: "audit".to_string(), ..SinkInstance::for_test(Arc::new(BodySink::default())) }],
+            )
+        };
+        let metrics = Metrics::scoped();
+        let processor_runs = |processor: &str, kind: &str| {
+            metrics.histogram_count(
+                "connectcare_processor_duration_seconds",
+                &[("pipeline", "0"), ("processor", processor), ("kind", kind)],
+            )
+        };
+        
+        let event = PipelineEvent::new(json!({"issue": {"key": "PROJ-1"}}), "jira:issue_created".to_string(), vec![], Operation::Write);
+        PipelineExecutor::process_event(event, 0, &instance, 0, &PipelineStatus::default(), None, &mut None).await.unwrap();
+        
+        assert_eq!(processor_runs("0", "mapper"), 1);
+        assert_eq!(processor_runs("1", "canonicalize"), 1);
+        assert_eq!(
+            metrics.histogram_count("connectcare_sink_write_duration_seconds", &[("pipeline", "0"), ("sink", "0"), ("name", "audit")]),
+            1
+        );
+        assert!(metrics.render().contains("connectcare_processor_duration_seconds_count{pipeline=\"0\",processor=\"0\",kind=\"mapper\"} 1"));
+    }
+    
+    #[test]
+    fn test_sink_names_default_to_their_type() {
+        let pipeline: Pipeline = serde_json::from_value(json!({
+            "sinks": [
+                { "type": "mongo", "url": "mongodb://localhost/db", "collection": "issues" },
+                { "type": "audit" },
+                { "type": "mongo", "url": "mongodb://localhost/db", "collection": "history" },
+                { "type": "mongo", "url": "mongodb://localhost/db", "collection": "mirror", "name": "mirror" }
+            ]
+        })).unwrap();
+        
+        assert_eq!(PipelineSink::names(&pipeline.sinks).unwrap(), vec!["mongo-1", "audit", "mongo-2", "mirror"]);
+        
+        let mut clashing = pipeline.sinks.clone();
+        clashing[3].name = Some("audit".to_string());
+        assert_eq!(
+            PipelineSink::names(&clashing).unwrap_err().to_string(),
+            "Configuration error: two sinks of the pipeline are named audit"
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_sink_only_receives_its_operations() {
+        let deletes = Arc::new(RecordingSink::default());
+        let pipeline = PipelineInstance::for_test(
+            vec![],
+            vec![SinkInstance { operations: vec![Operation::Delete], ..SinkInstance::for_test(deletes.clone()) }],
+        );
+        
+        let write = test_event();
+        let mut delete = test_event();
+        delete.operation = Operation::Delete;
+        delete.id = "deleted".to_string();
+        
+        PipelineExecutor::process_event(write, 0, &pipeline, 0, &PipelineStatus::default(), None, &mut None).await.unwrap();
+        PipelineExecutor::process_event(delete, 0, &pipeline, 0, &PipelineStatus::default(), None, &mut None).await.unwrap();
+        
+        assert_eq!(*deletes.written.lock().unwrap(), vec!["deleted".to_string()]);
+    }
+    
+    #[tokio::test]
+    async fn test_open_circuit_skips_writes_until_cooldown() {
+        use crate::pipeline::circuit_breaker::{CircuitBreakerConfig, CircuitState};
+        use std::sync::atomic::Ordering;
+        
+        let sink = Arc::new(FlakySink::default());
+        let breaker_config = CircuitBreakerConfig { failure_threshold: 2, cooldown_ms: 50 };
+        let pipeline = PipelineInstance::for_test(
+            vec![],
+            vec![SinkInstance { breaker: Some(CircuitBreaker::new(&breaker_config, 0, 0)), ..SinkInstance::for_test(sink.clone()) }],
+        );
+        let status = PipelineStatus::default();
+        let metrics = Metrics::scoped();
+        let short_circuited = || metrics.counter("connectcare_sink_short_circuited_total", &[("pipeline", "0"), ("sink", "0")]);
+        let circuit_state = || metrics.gauge("connectcare_sink_circuit_state", &[("pipeline", "0"), ("sink", "0")]);
+        
+        for _ in 0..4 {
+            assert!(PipelineExecutor::process_event(test_event(), 0, &pipeline, 0, &status, None, &mut None).await.is_err());
+        }
+        
+        // Two failures open the circuit, the next two events fail without a write
+        assert_eq!(sink.attempts.load(Ordering::SeqCst), 2);
+        assert_eq!(short_circuited(), 2);
+        assert_eq!(circuit_state(), CircuitState::Open as i64);
+        assert_eq!(
+            metrics.counter("connectcare_sink_write_failures_total", &[("pipeline", "0"), ("sink", "0"), ("reason", "circuit_open")]),
+            2
+        );
+        
+        // After the cooldown a probe goes through and closes the circuit
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        sink.healthy.store(true, Ordering::SeqCst);
+        assert!(PipelineExecutor::process_event(test_event(), 0, &pipeline, 0, &status, None, &mut None).await.is_ok());
+        assert!(PipelineExecutor::process_event(test_event(), 0, &pipeline, 0, &status, None, &mut None).await.is_ok());
+        assert_eq!(sink.attempts.load(Ordering::SeqCst), 4);
+        assert_eq!(circuit_state(), CircuitState::Closed as i64);
+    }
+    
+    #[tokio::test]
+    async fn test_stop_processor_halts_before_sinks() {
+        let recorder = Arc::new(RecordingSink::default());
+        let pipeline = PipelineInstance::for_test(
+            vec![
+                Box::new(MapperProcessor::new(json!({"id": "{{ id }}", "closed": true})).unwrap()),
+                Box::new(StopProcessor::new("closed == true").unwrap()),
+            ],
+            vec![SinkInstance::for_test(recorder.clone())],
+        );
+        let metrics = Metrics::scoped();
+        
+        assert!(PipelineExecutor::process_event(test_event(), 0, &pipeline, 0, &PipelineStatus::default(), None, &mut None).await.is_ok());
+        assert!(recorder.written.lock().unwrap().is_empty());
+        assert_eq!(metrics.counter("connectcare_pipeline_stopped_events_total", &[("pipeline", "0")]), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_hanging_pipeline_does_not_block_siblings() {
+        let recorder = Arc::new(RecordingSink::default());
+        let executor = PipelineExecutor::for_test(vec![
+            PipelineInstance::for_test(vec![Box::new(HangingProcessor)], vec![]),
+            PipelineInstance::for_test(vec![], vec![SinkInstance::for_test(recorder.clone())]),
+        ]);
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        tokio::spawn(executor.run(rx));
+        
+        for id in ["1", "2"] {
+            tx.send(PipelineEvent::new(
+                json!({"id": id}),
+                "test_event".to_string(),
+                vec![],
+                Operation::Write,
+            )).await.unwrap();
+        }
+        
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while recorder.written.lock().unwrap().len() < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("pipeline 1 was blocked by pipeline 0");
+    }
+    
+    #[tokio::test]
+    async fn test_full_pipeline_queue_refuses_event_for_every_pipeline() {
+        use crate::pipeline::handoff::handoff_channel;
+        
+        let recorder = Arc::new(RecordingSink::default());
+        let executor = PipelineExecutor::for_test(vec![
+            PipelineInstance::for_test(vec![], vec![SinkInstance::for_test(recorder.clone())]),
+            PipelineInstance {
+                queue_timeout: Duration::from_millis(20),
+                ..PipelineInstance::for_test(vec![Box::new(HangingProcessor)], vec![])
+            },
+        ]);
+        let metrics = Metrics::scoped();
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        tokio::spawn(executor.run(rx));
+        
+        let event = |id: usize| PipelineEvent::new(
+            json!({"id": id}),
+            "test_event".to_string(),
+            vec![PkField { key: "id".to_string(), value: id.to_string() }],
+            Operation::Write,
+        );
+        
+        // One event hangs in pipeline 1 and the next ones fill its queue
+        for id in 0..=PIPELINE_QUEUE_SIZE {
+            tx.send(event(id)).await.unwrap();
+        }
+        
+        let (handoff, mut waiter) = handoff_channel();
+        let refused = event(PIPELINE_QUEUE_SIZE + 1);
+        tx.send(refused.clone().with_handoff(handoff)).await.unwrap();
+        assert!(!tokio::time::timeout(Duration::from_secs(5), waiter.queued()).await.unwrap());
+        assert_eq!(metrics.counter("connectcare_pipeline_refused_events_total", &[("pipeline", "1")]), 1);
+        
+        // Pipeline 0 had room, yet doesn't get the refused event either
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while recorder.written.lock().unwrap().len() < PIPELINE_QUEUE_SIZE + 1 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+        assert!(!recorder.written.lock().unwrap().contains(&refused.id));
+    }
+    
+    #[tokio::test]
+    async fn test_concurrent_workers_have_their_own_processors() {
+        let config = config_with_pipeline(json!({
+            "processors": [{ "type": "merge", "key": "issue.id", "windowMs": 50 }],
+            "sinks": [],
+            "concurrency": 3
+        }));
+        
+        let executor = PipelineExecutor::new(&config).await.unwrap();
+        let workers = &executor.pipelines[0];
+        
+        assert_eq!(workers.len(), 3);
+        assert!(workers.iter().all(|worker| Arc::ptr_eq(&worker.sinks, &workers[0].sinks)));
+        assert!(!std::ptr::addr_eq(&*workers[0].processors[0], &*workers[1].processors[0]));
+    }
+    
+    #[tokio::test]
+    async fn test_concurrent_workers_fetch_jira_fields_once() {
+        let fetches = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = fetches.clone();
+        let app = axum::Router::new().route("/rest/api/2/field", axum::routing::get(move || async move {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            axum::Json(json!([{ "id": "customfield_10001", "name": "Story Points", "custom": true }]))
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        
+        let config = config_with_pipeline(json!({
+            "processors": [{
+                "type": "jiraCustomFields",
+                "fieldApi": { "baseUrl": base_url, "apiToken": "token" }
+            }],
+            "sinks": [],
+            "concurrency": 3
+        }));
+        
+        let executor = PipelineExecutor::new(&config).await.unwrap();
+        
+        assert_eq!(executor.pipelines[0].len(), 3);
+        assert_eq!(fetches.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_ordering_key_keeps_updates_of_an_issue_in_order() {
+        let recorder = Arc::new(RecordingSink::default());
+        let sinks: Arc<[SinkInstance]> = vec![SinkInstance::for_test(recorder.clone())].into();
+        let workers = (0..8)
+            .map(|_| PipelineInstance {
+                ordering_key: OrderingKey::Path("issue.id".to_string()),
+                sinks: sinks.clone(),
+                ..PipelineInstance::for_test(vec![Box::new(DelayingProcessor)], vec![])
+            })
+            .collect();
+        let executor = PipelineExecutor {
+            routing: vec![RoutingTable::all(0..1)],
+            pipelines: vec![workers],
+            ..PipelineExecutor::for_test(vec![])
+        };
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        tokio::spawn(executor.run(rx));
+        
+        let update = |issue: &str, seq: &str, delay_ms: u64| PipelineEvent::new(
+            json!({"issue": {"id": issue}, "delay_ms": delay_ms}),
+            "jira:issue_updated".to_string(),
+            vec![PkField { key: "seq".to_string(), value: seq.to_string() }],
+            Operation::Write,
+        );
+        // The first update of issue 1 is the slowest, yet must still be written before the second
+        let first = update("1", "1", 100);
+        let second = update("1", "2", 0);
+        let others: Vec<PipelineEvent> = (3..10).map(|n| update(&format!("other-{}", n), &n.to_string(), 0)).collect();
+        let (first_id, second_id) = (first.id.clone(), second.id.clone());
+        
+        tx.send(first).await.unwrap();
+        tx.send(second).await.unwrap();
+        for event in others {
+            tx.send(event).await.unwrap();
+        }
+        
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while recorder.written.lock().unwrap().len() < 9 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("events were not all written");
+        
+        let written = recorder.written.lock().unwrap().clone();
+        let position = |id: &str| written.iter().position(|written| written == id).unwrap();
+        assert!(position(&first_id) < position(&second_id), "updates of issue 1 were reordered: {:?}", written);
+        // Other issues didn't wait for the slow update
+        assert_ne!(position(&first_id), 0, "events were processed one at a time: {:?}", written);
+    }
+    
+    #[tokio::test]
+    async fn test_routes_dispatch_event_types_to_named_pipelines() {
+        let integration: Integration = serde_json::from_value(json!({
+            "source": {
+                "type": "jira",
+                "authentication": { "secret": "test_secret" }
+            },
+            "pipelines": [
+                { "name": "issues", "sinks": [] },
+                { "name": "versions", "sinks": [] }
+            ],
+            "routes": {
+                "jira:issue_created": ["issues"],
+                "jira:version_released": ["versions"]
+            }
+        })).unwrap();
+        
+        let issues = Arc::new(RecordingSink::default());
+        let versions = Arc::new(RecordingSink::default());
+        let pipeline = |sink: Arc<RecordingSink>| PipelineInstance::for_test(vec![], vec![SinkInstance::for_test(sink)]);
+        let executor = PipelineExecutor {
+            routing: vec![RoutingTable::build(&integration, 0).unwrap()],
+            ..PipelineExecutor::for_test(vec![pipeline(issues.clone()), pipeline(versions.clone())])
+        };
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        let handle = tokio::spawn(executor.run(rx));
+        
+        let events: Vec<_> = ["jira:issue_created", "jira:version_released", "jira:sprint_started"]
+            .into_iter()
+            .map(|event_type| PipelineEvent::new(json!({"type": event_type}), event_type.to_string(), vec![], Operation::Write))
+            .collect();
+        for event in &events {
+            tx.send(event.clone()).await.unwrap();
+        }
+        
+        // Closing the channel lets the executor drain its pipelines and stop
+        drop(tx);
+        tokio::time::timeout(Duration::from_secs(5), handle).await.unwrap().unwrap();
+        
+        assert_eq!(*issues.written.lock().unwrap(), vec![events[0].id.clone()]);
+        assert_eq!(*versions.written.lock().unwrap(), vec![events[1].id.clone()]);
+    }
+    
+    #[tokio::test]
+    async fn test_events_follow_their_integration_routes_once() {
+        let first = Arc::new(RecordingSink::default());
+        let second = Arc::new(RecordingSink::default());
+        let pipeline = |sink: Arc<RecordingSink>| PipelineInstance::for_test(vec![], vec![SinkInstance::for_test(sink)]);
+        let executor = PipelineExecutor {
+            routing: vec![RoutingTable::all(0..2), RoutingTable::all(1..2)],
+            ..PipelineExecutor::for_test(vec![pipeline(first.clone()), pipeline(second.clone())])
+        };
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        let handle = tokio::spawn(executor.run(rx));
+        
+        let event = |id: &str| PipelineEvent::new(json!({}), "test_event".to_string(), vec![PkField { key: "id".to_string(), value: id.to_string() }], Operation::Write);
+        let from_second = event("1").with_integration(1);
+        // Without an integration, the event follows every table, but reaches each pipeline once
+        let unknown = event("2");
+        tx.send(from_second.clone()).await.unwrap();
+        tx.send(unknown.clone()).await.unwrap();
+        
+        drop(tx);
+        tokio::time::timeout(Duration::from_secs(5), handle).await.unwrap().unwrap();
+        
+        assert_eq!(*first.written.lock().unwrap(), vec![unknown.id.clone()]);
+        assert_eq!(*second.written.lock().unwrap(), vec![from_second.id.clone(), unknown.id.clone()]);
+    }
+    
+    #[tokio::test]
+    async fn test_status_reports_pipeline_queue_depth() {
+        let executor = PipelineExecutor::for_test(vec![PipelineInstance::for_test(
+            vec![Box::new(HangingProcessor)],
+            vec![SinkInstance::for_test(Arc::new(RecordingSink::default()))],
+        )]);
+        
+        let status = executor.status();
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        let handle = tokio::spawn(executor.run(rx));
+        
+        for _ in 0..3 {
+            tx.send(PipelineEvent::new(json!({}), "test_event".to_string(), vec![], Operation::Write)).await.unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        
+        // The worker hangs on the first event, the other two wait in its queue
+        let report = status.report(&tx);
+        assert_eq!(report.backlog.depth, 0);
+        assert_eq!(report.pipeline_queues.len(), 1);
+        assert_eq!(report.pipeline_queues[0].depth, 2);
+        assert_eq!(report.pipeline_queues[0].capacity, PIPELINE_QUEUE_SIZE);
+        
+        handle.abort();
+    }
+    
+    #[tokio::test]
+    async fn test_status_tracks_workers_stuck_on_an_event() {
+        let executor = PipelineExecutor::for_test(vec![
+            PipelineInstance::for_test(vec![], vec![SinkInstance::for_test(Arc::new(RecordingSink::default()))]),
+            PipelineInstance::for_test(vec![Box::new(HangingProcessor)], vec![SinkInstance::for_test(Arc::new(RecordingSink::default()))]),
+        ]);
+        
+        let status = executor.status();
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        let handle = tokio::spawn(executor.run(rx));
+        
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(status.slowest_worker().is_none());
+        
+        // Both pipelines get the event; the first finishes it while the second hangs on it
+        tx.send(PipelineEvent::new(json!({}), "test_event".to_string(), vec![], Operation::Write)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let (pipeline, busy) = status.slowest_worker().unwrap();
+        assert_eq!(pipeline, 1);
+        assert!(busy >= Duration::from_millis(50));
+        
+        handle.abort();
+    }
+    
+    #[tokio::test]
+    async fn test_merged_event_reaches_sinks_when_window_closes() {
+        let recorder = Arc::new(RecordingSink::default());
+        let executor = PipelineExecutor::for_test(vec![PipelineInstance::for_test(
+            vec![Box::new(MergeProcessor::new("issue.id".to_string(), 50, 100))],
+            vec![SinkInstance::for_test(recorder.clone())],
+        )]);
+        
+        let status = executor.status();
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        let handle = tokio::spawn(executor.run(rx));
+        
+        let first = PipelineEvent::new(json!({"issue": {"id": "1"}}), "test_event".to_string(), vec![], Operation::Write);
+        let second = PipelineEvent::new(json!({"issue": {"id": "1"}, "links": []}), "test_event".to_string(), vec![], Operation::Write);
+        tx.send(first.clone()).await.unwrap();
+        tx.send(second).await.unwrap();
+        
+        // Released by the poll once the window closes, while the executor is still running
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(*recorder.written.lock().unwrap(), vec![first.id.clone()]);
+        
+        drop(tx);
+        tokio::time::timeout(Duration::from_secs(5), handle).await.unwrap().unwrap();
+        assert_eq!(recorder.written.lock().unwrap().len(), 1);
+        
+        // The first event counts once, when released, and the second as merged away
+        let (tx_probe, _) = crate::pipeline::create_pipeline_channel(1);
+        assert_eq!(status.report(&tx_probe).processed, 2);
+    }
+    
+    #[tokio::test]
+    async fn test_required_sink_acknowledges_despite_best_effort_failure() {
+        let recorder = Arc::new(RecordingSink::default());
+        let executor = PipelineExecutor::for_test(vec![PipelineInstance::for_test(
+            vec![],
+            vec![
+                SinkInstance::for_test(Arc::new(FailingSink)),
+                SinkInstance { required: true, ..SinkInstance::for_test(recorder.clone()) },
+            ],
+        )]);
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        tokio::spawn(executor.run(rx));
+        
+        let (ack, waiter) = ack_channel();
+        tx.send(test_event().with_ack(ack)).await.unwrap();
+        
+        assert!(matches!(waiter.wait().await, AckOutcome::Acknowledged(_)));
+        assert_eq!(recorder.written.lock().unwrap().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_failed_required_sink_fails_ack() {
+        let executor = PipelineExecutor::for_test(vec![PipelineInstance::for_test(
+            vec![],
+            vec![SinkInstance { required: true, ..SinkInstance::for_test(Arc::new(FailingSink)) }],
+        )]);
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        tokio::spawn(executor.run(rx));
+        
+        let (ack, waiter) = ack_channel();
+        tx.send(test_event().with_ack(ack)).await.unwrap();
+        
+        assert_eq!(waiter.wait().await, AckOutcome::Failed);
+    }
+    
+    #[tokio::test]
+    async fn test_failed_merged_write_fails_every_merged_ack() {
+        let executor = PipelineExecutor::for_test(vec![PipelineInstance::for_test(
+            vec![Box::new(MergeProcessor::new("issue.id".to_string(), 20, 100))],
+            vec![SinkInstance { required: true, ..SinkInstance::for_test(Arc::new(FailingSink)) }],
+        )]);
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        tokio::spawn(executor.run(rx));
+        
+        let (first_ack, first_waiter) = ack_channel();
+        let (second_ack, second_waiter) = ack_channel();
+        let first = PipelineEvent::new(json!({"issue": {"id": "1"}}), "test_event".to_string(), vec![], Operation::Write);
+        let second = PipelineEvent::new(json!({"issue": {"id": "1"}, "links": []}), "test_event".to_string(), vec![], Operation::Write);
+        tx.send(first.with_ack(first_ack)).await.unwrap();
+        tx.send(second.with_ack(second_ack)).await.unwrap();
+        
+        // The event merged away isn't answered before the merged write, and fails with it
+        let outcomes = tokio::time::timeout(Duration::from_secs(5), async {
+            (first_waiter.wait().await, second_waiter.wait().await)
+        }).await.unwrap();
+        assert_eq!(outcomes, (AckOutcome::Failed, AckOutcome::Failed));
+    }
+    
+    #[tokio::test]
+    async fn test_deadband_lets_redelivery_of_failed_event_through() {
+        let sink = Arc::new(FlakySink { attempts: Default::default(), healthy: Default::default() });
+        let executor = PipelineExecutor::for_test(vec![PipelineInstance::for_test(
+            vec![Box::new(DeadbandProcessor::new(vec!["issue.key".to_string()], 100))],
+            vec![SinkInstance::for_test(sink.clone())],
+        )]);
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        let handle = tokio::spawn(executor.run(rx));
+        
+        // The first write fails, so the identical redelivery must reach the sink again
+        tx.send(test_event()).await.unwrap();
+        tx.send(test_event()).await.unwrap();
+        drop(tx);
+        handle.await.unwrap();
+        
+        assert_eq!(sink.attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+    
+    #[tokio::test]
+    async fn test_failed_event_is_dead_lettered() {
+        let dead_letter = Arc::new(DeadLetterQueue::new(&DeadLetterConfig { max_entries: 10, max_age_secs: None }));
+        let executor = PipelineExecutor {
+            dead_letter: Some(dead_letter.clone()),
+            ..PipelineExecutor::for_test(vec![PipelineInstance::for_test(
+                vec![Box::new(MergePatchProcessor::new(json!({"mapped": true})))],
+                vec![SinkInstance::for_test(Arc::new(FailingSink))],
+            )])
+        };
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        let handle = tokio::spawn(executor.run(rx));
+        
+        let event = test_event();
+        tx.send(event.clone()).await.unwrap();
+        drop(tx);
+        tokio::time::timeout(Duration::from_secs(5), handle).await.unwrap().unwrap();
+        
+        let entries = dead_letter.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].event_id, event.id);
+        // The body the sink failed on, next to the one the source created
+        assert_eq!(entries[0].body, json!({"id": "1", "mapped": true}));
+        assert_eq!(*entries[0].raw_body, *event.body);
+        assert!(entries[0].error.contains("sinks failed"), "unexpected error: {}", entries[0].error);
+    }
+    
+    #[tokio::test]
+    async fn test_max_processing_abandons_slow_event() {
+        let recorder = Arc::new(RecordingSink::default());
+        let executor = PipelineExecutor::for_test(vec![PipelineInstance {
+            max_processing: Some(Duration::from_millis(50)),
+            ..PipelineInstance::for_test(vec![Box::new(StallingProcessor)], vec![SinkInstance::for_test(recorder.clone())])
+        }]);
+        let status = executor.status();
+        
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        tokio::spawn(executor.run(rx));
+        
+        let event = |id: &str, slow: bool| PipelineEvent::new(
+            json!({"slow": slow}),
+            "test_event".to_string(),
+            vec![PkField { key: "id".to_string(), value: id.to_string() }],
+            Operation::Write,
+        );
+        let slow = event("1", true);
+        let fast = event("2", false);
+        let fast_id = fast.id.clone();
+        tx.send(slow).await.unwrap();
+        tx.send(fast).await.unwrap();
+        
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while recorder.written.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("event after the slow one was blocked");
+        
+        assert_eq!(*recorder.written.lock().unwrap(), vec![fast_id]);
+        
+        let (tx_probe, _) = crate::pipeline::create_pipeline_channel(1);
+        assert_eq!(status.report(&tx_probe).failed, 1);
+    }
 }
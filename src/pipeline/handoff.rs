@@ -0,0 +1,86 @@
+use crate::pipeline::PipelineReceiver;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+
+/// Travels with an event from its source to the executor, which accepts it once every pipeline
+/// the event is routed to has room for it
+///
+/// Dropping every copy without accepting refuses the event, and the source answers busy.
+#[derive(Debug, Clone)]
+pub struct HandoffHandle {
+    sender: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+}
+
+impl HandoffHandle {
+    /// Tell the source the event is queued; `false` if it stopped waiting, so the event must be
+    /// dropped since the source already reported it as refused
+    pub fn accept(&self) -> bool {
+        match self.sender.lock().unwrap().take() {
+            Some(sender) => sender.send(()).is_ok(),
+            None => false,
+        }
+    }
+}
+
+/// Source side of a hand-off
+pub struct HandoffWaiter {
+    receiver: oneshot::Receiver<()>,
+}
+
+impl HandoffWaiter {
+    /// Whether the executor queued the event, once it has decided
+    pub async fn queued(&mut self) -> bool {
+        (&mut self.receiver).await.is_ok()
+    }
+
+    /// Stop waiting, returning whether the event was queued in the meantime; if not, it never will be
+    pub fn give_up(mut self) -> bool {
+        self.receiver.close();
+        self.receiver.try_recv().is_ok()
+    }
+}
+
+pub fn handoff_channel() -> (HandoffHandle, HandoffWaiter) {
+    let (sender, receiver) = oneshot::channel();
+    (HandoffHandle { sender: Arc::new(Mutex::new(Some(sender))) }, HandoffWaiter { receiver })
+}
+
+/// Accept every event of `receiver` once there is room for it in the returned channel
+///
+/// Stands in for the executor where events are read straight off the pipeline channel, as in tests
+/// of sources, which would otherwise wait on a hand-off forever.
+pub fn accept_handoffs(mut receiver: PipelineReceiver, buffer_size: usize) -> PipelineReceiver {
+    let (sender, accepted) = mpsc::channel(buffer_size);
+    tokio::spawn(async move {
+        while let Some(mut event) = receiver.recv().await {
+            let Ok(permit) = sender.reserve().await else {
+                break;
+            };
+            if event.handoff.take().is_none_or(|handoff| handoff.accept()) {
+                permit.send(event);
+            }
+        }
+    });
+    accepted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_handoff_outcomes() {
+        let (handle, mut waiter) = handoff_channel();
+        assert!(handle.accept());
+        assert!(waiter.queued().await);
+
+        let (handle, mut waiter) = handoff_channel();
+        drop(handle);
+        assert!(!waiter.queued().await);
+
+        // An event the source gave up on is no longer accepted
+        let (handle, waiter) = handoff_channel();
+        assert!(!waiter.give_up());
+        assert!(!handle.accept());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::error::{AppError, Result};
+use crate::metrics;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Caps the events accepted by sources but not yet fully processed, across all sources
+///
+/// Unlike the pipeline channel, which only bounds events waiting for the executor, a permit is
+/// held until every pipeline is done with the event.
+#[derive(Debug, Clone)]
+pub struct InFlightLimit {
+    semaphore: Arc<Semaphore>,
+    max: usize,
+}
+
+/// Travels with an event and its per-pipeline copies; the slot frees up when the last one is dropped
+#[derive(Debug, Clone)]
+pub struct InFlightPermit {
+    _permit: Arc<OwnedSemaphorePermit>,
+}
+
+impl InFlightLimit {
+    pub fn new(max: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max)),
+            max,
+        }
+    }
+
+    /// Take a slot for a new event, shedding it when all are in use
+    pub fn try_acquire(&self, source: &str) -> Result<InFlightPermit> {
+        let permit = self.semaphore.clone().try_acquire_owned().map_err(|_| {
+            metrics::global().increment("connectcare_events_shed_total", &[("source", source)]);
+            tracing::warn!("{} events already in flight, shedding {} event", self.max, source);
+            AppError::Overloaded
+        })?;
+
+        Ok(InFlightPermit { _permit: Arc::new(permit) })
+    }
+
+    /// Events currently holding a slot
+    pub fn in_flight(&self) -> usize {
+        self.max - self.semaphore.available_permits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slot_is_freed_with_last_copy() {
+        let limit = InFlightLimit::new(1);
+
+        let permit = limit.try_acquire("test").unwrap();
+        let copy = permit.clone();
+        assert!(matches!(limit.try_acquire("test"), Err(AppError::Overloaded)));
+
+        drop(permit);
+        assert_eq!(limit.in_flight(), 1);
+        drop(copy);
+        assert_eq!(limit.in_flight(), 0);
+        assert!(limit.try_acquire("test").is_ok());
+    }
+}
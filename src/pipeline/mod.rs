@@ -1,10 +1,21 @@
+pub mod ack;
+pub mod circuit_breaker;
+pub mod dead_letter;
 pub mod event;
+pub mod handoff;
+pub mod in_flight;
 pub mod processors;
+pub mod retry_queue;
+pub mod routing;
 pub mod sinks;
+pub mod status;
 pub mod executor;
 
 use tokio::sync::mpsc;
+use crate::config::Pipeline;
+use crate::error::Result;
 use event::PipelineEvent;
+use processors::ProcessorOutput;
 
 pub type PipelineSender = mpsc::Sender<PipelineEvent>;
 pub type PipelineReceiver = mpsc::Receiver<PipelineEvent>;
@@ -12,3 +23,28 @@ pub type PipelineReceiver = mpsc::Receiver<PipelineEvent>;
 pub fn create_pipeline_channel(buffer_size: usize) -> (PipelineSender, PipelineReceiver) {
     mpsc::channel(buffer_size)
 }
+
+/// Run an event through a pipeline's processors without writing to its sinks
+///
+/// Meant for testing processor configurations: returns the events that would reach the sinks,
+/// empty if the event was filtered out. Held events are released right away instead of waiting
+/// for their window.
+pub async fn run_pipeline(config: &Pipeline, event: PipelineEvent) -> Result<Vec<PipelineEvent>> {
+    let processors = executor::PipelineExecutor::build_processors(config).await?;
+    
+    let mut events = vec![event];
+    for processor in &processors {
+        let mut next = Vec::new();
+        for event in events {
+            match processor.run(event).await? {
+                ProcessorOutput::Continue(processed_event) => next.push(*processed_event),
+                ProcessorOutput::Split(events) => next.extend(events),
+                ProcessorOutput::Filtered | ProcessorOutput::Stopped | ProcessorOutput::Held => {}
+            }
+        }
+        next.extend(processor.due(true));
+        events = next;
+    }
+    
+    Ok(events)
+}
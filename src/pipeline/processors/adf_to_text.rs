@@ -0,0 +1,133 @@
+use crate::error::Result;
+use crate::pipeline::event::PipelineEvent;
+use super::{path, Processor};
+use serde_json::Value;
+
+/// Inline ADF nodes; every other node is a block and starts on a new line
+const INLINE_NODES: &[&str] = &["text", "hardBreak", "mention", "emoji", "inlineCard", "date", "status"];
+
+/// Processor that replaces Atlassian Document Format documents at `paths` with their plain text
+///
+/// Text nodes are concatenated in document order, blocks (paragraphs, headings, list items, table
+/// cells...) are put on their own lines, and mentions, emojis and cards are rendered as their text
+/// or URL. Values that aren't ADF documents, such as the wiki markup strings of API v2 payloads,
+/// are left alone.
+pub struct AdfToTextProcessor {
+    paths: Vec<String>,
+}
+
+impl AdfToTextProcessor {
+    pub fn new(paths: Vec<String>) -> Self {
+        Self { paths }
+    }
+
+    fn is_document(value: &Value) -> bool {
+        value.get("type").and_then(Value::as_str) == Some("doc")
+    }
+
+    fn node_type(node: &Value) -> &str {
+        node.get("type").and_then(Value::as_str).unwrap_or_default()
+    }
+
+    fn attr<'a>(node: &'a Value, name: &str) -> &'a str {
+        node.pointer(&format!("/attrs/{}", name)).and_then(Value::as_str).unwrap_or_default()
+    }
+
+    fn render(node: &Value, output: &mut String) {
+        match Self::node_type(node) {
+            "text" => output.push_str(node.get("text").and_then(Value::as_str).unwrap_or_default()),
+            "hardBreak" => output.push('\n'),
+            "mention" | "status" => output.push_str(Self::attr(node, "text")),
+            "emoji" => {
+                let text = Self::attr(node, "text");
+                output.push_str(if text.is_empty() { Self::attr(node, "shortName") } else { text });
+            }
+            "inlineCard" | "blockCard" | "embedCard" => output.push_str(Self::attr(node, "url")),
+            _ => {
+                for child in node.get("content").and_then(Value::as_array).into_iter().flatten() {
+                    let block = !INLINE_NODES.contains(&Self::node_type(child));
+                    if block && !output.is_empty() && !output.ends_with('\n') {
+                        output.push('\n');
+                    }
+                    Self::render(child, output);
+                }
+            }
+        }
+    }
+
+    /// Plain text of an ADF document
+    pub fn to_text(document: &Value) -> String {
+        let mut output = String::new();
+        Self::render(document, &mut output);
+        output.trim_end().to_string()
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for AdfToTextProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        for field_path in &self.paths {
+            if let Some(value) = path::get_mut(event.body_mut(), field_path) {
+                if Self::is_document(value) {
+                    *value = Value::String(Self::to_text(value));
+                } else {
+                    tracing::debug!("Value at {} is not an ADF document, leaving it", field_path);
+                }
+            }
+        }
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::event::Operation;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_adf_description_becomes_plain_text() {
+        let description = json!({
+            "type": "doc",
+            "version": 1,
+            "content": [
+                { "type": "heading", "attrs": { "level": 2 }, "content": [{ "type": "text", "text": "Steps" }] },
+                {
+                    "type": "paragraph",
+                    "content": [
+                        { "type": "text", "text": "Login " },
+                        { "type": "text", "text": "fails", "marks": [{ "type": "strong" }] },
+                        { "type": "text", "text": " for " },
+                        { "type": "mention", "attrs": { "id": "5b10ac8d", "text": "@Ada" } },
+                        { "type": "hardBreak" },
+                        { "type": "text", "text": "since Monday " },
+                        { "type": "emoji", "attrs": { "shortName": ":cry:", "text": "😢" } }
+                    ]
+                },
+                {
+                    "type": "bulletList",
+                    "content": [
+                        { "type": "listItem", "content": [{ "type": "paragraph", "content": [{ "type": "text", "text": "Open the app" }] }] },
+                        { "type": "listItem", "content": [{ "type": "paragraph", "content": [
+                            { "type": "text", "text": "See " },
+                            { "type": "inlineCard", "attrs": { "url": "https://example.atlassian.net/browse/PROJ-2" } }
+                        ] }] }
+                    ]
+                }
+            ]
+        });
+        let processor = AdfToTextProcessor::new(vec!["issue.fields.description".to_string(), "issue.fields.environment".to_string()]);
+        let body = json!({ "issue": { "fields": { "description": description, "environment": "h2. Wiki markup" } } });
+        let event = PipelineEvent::new(body, "jira:issue_created".to_string(), vec![], Operation::Write);
+
+        let body = processor.process(event).await.unwrap().unwrap().body;
+
+        assert_eq!(
+            body["issue"]["fields"]["description"],
+            "Steps\nLogin fails for @Ada\nsince Monday 😢\nOpen the app\nSee https://example.atlassian.net/browse/PROJ-2"
+        );
+        // Not ADF, so left as it is
+        assert_eq!(body["issue"]["fields"]["environment"], "h2. Wiki markup");
+    }
+}
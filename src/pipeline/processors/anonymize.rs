@@ -0,0 +1,113 @@
+use crate::error::Result;
+use crate::pipeline::event::PipelineEvent;
+use crate::sources::webhook::hmac::hex_digest;
+use super::{path, Processor};
+use serde_json::Value;
+
+/// Processor that replaces the values at `paths` with their salted HMAC-SHA256, as hex
+///
+/// The same value always gives the same digest under a salt, so anonymized ids still join.
+/// Strings are hashed as they are and other values by their JSON text; nulls and missing paths
+/// are left alone. Primary key fields read from an anonymized path are replaced too, and the event
+/// id derived from them again, so the raw value doesn't reach sinks through the event's metadata.
+pub struct AnonymizeProcessor {
+    paths: Vec<String>,
+    salt: Vec<u8>,
+}
+
+impl AnonymizeProcessor {
+    pub fn new(paths: Vec<String>, salt: String) -> Self {
+        Self { paths, salt: salt.into_bytes() }
+    }
+
+    fn digest(&self, value: &Value) -> String {
+        match value {
+            Value::String(s) => hex_digest(&self.salt, s.as_bytes()),
+            other => hex_digest(&self.salt, other.to_string().as_bytes()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for AnonymizeProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        for field_path in &self.paths {
+            if let Some(value) = path::get_mut(event.body_mut(), field_path) {
+                if !value.is_null() {
+                    *value = Value::String(self.digest(value));
+                }
+            }
+        }
+
+        if !event.pk_fields.iter().any(|pk| self.paths.contains(&pk.key)) {
+            return Ok(Some(event));
+        }
+
+        let mut pk_fields = std::mem::take(&mut event.pk_fields);
+        for pk in pk_fields.iter_mut().filter(|pk| self.paths.contains(&pk.key)) {
+            pk.value = hex_digest(&self.salt, pk.value.as_bytes());
+        }
+        let body = std::mem::take(event.body_mut());
+
+        Ok(Some(event.derive(body, pk_fields)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::event::{Operation, PkField};
+    use serde_json::json;
+
+    async fn anonymize(salt: &str, body: Value) -> PipelineEvent {
+        let processor = AnonymizeProcessor::new(vec!["issue.id".to_string(), "issue.fields.reporter.accountId".to_string()], salt.to_string());
+        let pk_fields = vec![PkField { key: "issue.id".to_string(), value: body["issue"]["id"].as_str().unwrap_or_default().to_string() }];
+        let event = PipelineEvent::new(body, "test_event".to_string(), pk_fields, Operation::Write);
+        processor.process(event).await.unwrap().unwrap()
+    }
+
+    fn issue(id: &str, account_id: &str) -> Value {
+        json!({ "issue": { "id": id, "key": "PROJ-1", "fields": { "reporter": { "accountId": account_id } } } })
+    }
+
+    #[tokio::test]
+    async fn test_same_input_gives_same_output() {
+        let first = anonymize("salt", issue("10001", "5b10ac8d")).await;
+        let second = anonymize("salt", issue("10001", "5b10ac8d")).await;
+
+        assert_eq!(first.body, second.body);
+        assert_ne!(first.body["issue"]["id"], "10001");
+        assert_eq!(first.body["issue"]["id"].as_str().unwrap().len(), 64);
+        assert_eq!(first.body["issue"]["key"], "PROJ-1");
+        // The primary key carries the digest, not the raw id, and so does the id derived from it
+        assert_eq!(first.pk_fields[0].value, first.body["issue"]["id"]);
+        let raw_pk = vec![PkField { key: "issue.id".to_string(), value: "10001".to_string() }];
+        let raw = PipelineEvent::new(Value::Null, "test_event".to_string(), raw_pk, Operation::Write);
+        let derived = raw.derive(Value::Null, first.pk_fields.clone());
+        assert_ne!(first.id, raw.id);
+        assert_eq!(first.id, derived.id);
+    }
+
+    #[tokio::test]
+    async fn test_different_inputs_or_salts_differ() {
+        let first = anonymize("salt", issue("10001", "5b10ac8d")).await;
+        let other_id = anonymize("salt", issue("10002", "5b10ac8d")).await;
+        let other_salt = anonymize("pepper", issue("10001", "5b10ac8d")).await;
+
+        assert_ne!(first.body["issue"]["id"], other_id.body["issue"]["id"]);
+        assert_eq!(first.body["issue"]["fields"], other_id.body["issue"]["fields"]);
+        assert_ne!(first.body["issue"]["id"], other_salt.body["issue"]["id"]);
+    }
+
+    #[tokio::test]
+    async fn test_numbers_are_hashed_and_nulls_kept() {
+        let processor = AnonymizeProcessor::new(vec!["a".to_string(), "b".to_string(), "missing".to_string()], "salt".to_string());
+        let event = PipelineEvent::new(json!({ "a": 42, "b": null }), "test_event".to_string(), vec![], Operation::Write);
+
+        let body = processor.process(event).await.unwrap().unwrap().body;
+
+        assert_eq!(body["a"], hex_digest(b"salt", b"42"));
+        assert_eq!(body["b"], Value::Null);
+        assert!(body.get("missing").is_none());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::error::{AppError, Result};
+use crate::pipeline::event::PipelineEvent;
+use super::{path, Processor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdKind {
+    /// Random UUID, e.g. `1b4e28ba-2fa1-4d3b-a3f5-ef19b5a7633b`
+    UuidV4,
+    /// Time-sortable ULID, e.g. `01ARZ3NDEKTSV4RRFFQ69G5FAV`
+    Ulid,
+}
+
+/// Processor that writes a freshly generated identifier to a body path
+///
+/// The event's own content-hash `id` is left alone; only the configured body path is written.
+pub struct AssignIdProcessor {
+    field: String,
+    kind: IdKind,
+    /// Hands out ULIDs that still sort in order when generated within the same millisecond
+    ulids: Mutex<ulid::Generator>,
+}
+
+impl AssignIdProcessor {
+    pub fn new(field: String, kind: IdKind) -> Self {
+        Self {
+            field,
+            kind,
+            ulids: Mutex::new(ulid::Generator::new()),
+        }
+    }
+    
+    /// Fails only when a millisecond's ULIDs are exhausted, which would break their order
+    fn generate(&self) -> Result<String> {
+        match self.kind {
+            IdKind::UuidV4 => Ok(uuid::Uuid::new_v4().to_string()),
+            IdKind::Ulid => self.ulids
+                .lock()
+                .unwrap()
+                .generate()
+                .map(|ulid| ulid.to_string())
+                .map_err(|e| AppError::Processing(format!("Cannot generate a ULID: {}", e))),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for AssignIdProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let id = self.generate()?;
+        if !path::set(event.body_mut(), &self.field, Value::String(id)) {
+            return Err(AppError::Processing(format!(
+                "Cannot assign id at '{}': parent is not an object",
+                self.field
+            )));
+        }
+        
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::pipeline::event::Operation;
+    
+    fn event() -> PipelineEvent {
+        PipelineEvent::new(json!({"id": "10001"}), "test_event".to_string(), vec![], Operation::Write)
+    }
+    
+    #[tokio::test]
+    async fn test_assigns_uuid_v4() {
+        let processor = AssignIdProcessor::new("meta.eventId".to_string(), IdKind::UuidV4);
+        
+        let result = processor.process(event()).await.unwrap().unwrap();
+        
+        let id = result.body["meta"]["eventId"].as_str().unwrap();
+        assert_eq!(uuid::Uuid::parse_str(id).unwrap().get_version_num(), 4);
+        assert_eq!(result.body["id"], "10001");
+    }
+    
+    #[tokio::test]
+    async fn test_assigns_sortable_ulids() {
+        let processor = AssignIdProcessor::new("eventId".to_string(), IdKind::Ulid);
+        
+        let mut ids = Vec::new();
+        for _ in 0..100 {
+            let result = processor.process(event()).await.unwrap().unwrap();
+            ids.push(result.body["eventId"].as_str().unwrap().to_string());
+        }
+        
+        for id in &ids {
+            assert_eq!(id.len(), 26);
+            assert!(ulid::Ulid::from_string(id).is_ok(), "invalid ULID {}", id);
+        }
+        
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert_eq!(sorted, ids);
+        
+        // The timestamp part is the creation time in milliseconds
+        let millis = ulid::Ulid::from_string(&ids[0]).unwrap().timestamp_ms();
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_millis() as u64;
+        assert!(now - millis < 60_000);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::{AppError, Result};
+use crate::pipeline::event::PipelineEvent;
+use super::{path, Processor};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// What to do with a string that is not valid base64, or doesn't decode to UTF-8
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvalidBase64Policy {
+    /// Leave the string as it is
+    #[default]
+    Skip,
+    /// Fail the event
+    Error,
+}
+
+/// Processor that base64-decodes the strings at `decode` and encodes the ones at `encode`
+///
+/// Decoded bytes must be UTF-8, since the body is JSON. Values that are not strings and missing
+/// paths are left alone.
+pub struct Base64Processor {
+    decode: Vec<String>,
+    encode: Vec<String>,
+    on_invalid: InvalidBase64Policy,
+}
+
+impl Base64Processor {
+    pub fn new(decode: Vec<String>, encode: Vec<String>, on_invalid: InvalidBase64Policy) -> Self {
+        Self { decode, encode, on_invalid }
+    }
+
+    fn decode(encoded: &str) -> std::result::Result<String, String> {
+        let bytes = STANDARD.decode(encoded.trim()).map_err(|e| e.to_string())?;
+        String::from_utf8(bytes).map_err(|e| e.to_string())
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for Base64Processor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        for field_path in &self.decode {
+            let Some(Value::String(encoded)) = path::get_mut(event.body_mut(), field_path) else {
+                continue;
+            };
+
+            match Self::decode(encoded) {
+                Ok(decoded) => *encoded = decoded,
+                Err(e) => match self.on_invalid {
+                    InvalidBase64Policy::Skip => {
+                        tracing::debug!("Leaving string at {} undecoded: {}", field_path, e);
+                    }
+                    InvalidBase64Policy::Error => {
+                        return Err(AppError::Processing(format!("Field {} is not valid base64: {}", field_path, e)));
+                    }
+                },
+            }
+        }
+
+        for field_path in &self.encode {
+            if let Some(Value::String(plain)) = path::get_mut(event.body_mut(), field_path) {
+                *plain = STANDARD.encode(plain.as_bytes());
+            }
+        }
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use serde_json::json;
+    use crate::pipeline::event::Operation;
+
+    async fn run(processor: Base64Processor, body: Value) -> Result<Value> {
+        let event = PipelineEvent::new(body, "test_event".to_string(), vec![], Operation::Write);
+        Ok(Arc::unwrap_or_clone(processor.process(event).await?.unwrap().body))
+    }
+
+    #[tokio::test]
+    async fn test_decodes_and_encodes_fields() {
+        let processor = Base64Processor::new(
+            vec!["issue.fields.payload".to_string()],
+            vec!["issue.fields.summary".to_string(), "issue.fields.missing".to_string()],
+            InvalidBase64Policy::Error,
+        );
+        let body = json!({
+            "issue": { "fields": { "payload": "eyJzdGF0dXMiOiAiZG9uZSJ9", "summary": "Café ready", "votes": 3 } }
+        });
+
+        let body = run(processor, body).await.unwrap();
+
+        assert_eq!(body["issue"]["fields"]["payload"], r#"{"status": "done"}"#);
+        assert_eq!(body["issue"]["fields"]["summary"], "Q2Fmw6kgcmVhZHk=");
+        assert_eq!(body["issue"]["fields"]["votes"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_base64_follows_policy() {
+        let body = json!({ "a": "not base64!", "b": "//8=" });
+        let paths = vec!["a".to_string(), "b".to_string()];
+
+        let skipped = run(Base64Processor::new(paths.clone(), vec![], InvalidBase64Policy::Skip), body.clone()).await.unwrap();
+        assert_eq!(skipped, body);
+
+        assert!(run(Base64Processor::new(vec!["a".to_string()], vec![], InvalidBase64Policy::Error), body.clone()).await.is_err());
+        // Valid base64, but the bytes are not UTF-8
+        assert!(run(Base64Processor::new(vec!["b".to_string()], vec![], InvalidBase64Policy::Error), body).await.is_err());
+    }
+}
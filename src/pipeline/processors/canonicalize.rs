@@ -0,0 +1,64 @@
+use crate::error::Result;
+use crate::pipeline::event::PipelineEvent;
+use super::Processor;
+use serde_json::{Map, Value};
+
+/// Processor that sorts object keys at every level of the body, keeping array order
+///
+/// Bodies keep the key order they arrived with, so the same document can be stored with its
+/// keys in different orders; sorting them makes stored documents comparable across versions.
+pub struct CanonicalizeProcessor;
+
+/// Rebuild `value` with the keys of every object sorted
+pub fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            Value::Object(entries.into_iter().map(|(key, value)| (key, sort_keys(value))).collect::<Map<_, _>>())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        scalar => scalar,
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for CanonicalizeProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let body = std::mem::take(event.body_mut());
+        event.set_body(sort_keys(body));
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::event::Operation;
+    use crate::sources::webhook::hmac::canonical_json;
+
+    fn keys(value: &Value) -> Vec<&str> {
+        value.as_object().unwrap().keys().map(String::as_str).collect()
+    }
+
+    #[tokio::test]
+    async fn test_sorts_nested_object_keys() {
+        let body: Value = serde_json::from_str(
+            r#"{"issue":{"key":"PROJ-1","fields":{"summary":"s","assignee":null}},"webhookEvent":"e","changelog":{"items":[{"to":"2","from":"1"},{"b":1,"a":2}]}}"#,
+        )
+        .unwrap();
+        let event = PipelineEvent::new(body.clone(), "test_event".to_string(), vec![], Operation::Write);
+
+        let sorted = CanonicalizeProcessor.process(event).await.unwrap().unwrap().body;
+
+        assert_eq!(keys(&sorted), vec!["changelog", "issue", "webhookEvent"]);
+        assert_eq!(keys(&sorted["issue"]), vec!["fields", "key"]);
+        assert_eq!(keys(&sorted["issue"]["fields"]), vec!["assignee", "summary"]);
+        assert_eq!(keys(&sorted["changelog"]["items"][0]), vec!["from", "to"]);
+        assert_eq!(keys(&sorted["changelog"]["items"][1]), vec!["a", "b"]);
+        assert_eq!(*sorted, body);
+        assert_eq!(serde_json::to_vec(&sorted).unwrap(), canonical_json(&body));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::error::Result;
+use crate::pipeline::event::{PipelineEvent, PkField};
+use super::{Processor, ProcessorOutput};
+use serde_json::{json, Value};
+
+/// Processor that splits a Jira event into one event per `changelog.items` entry
+///
+/// Each event's body is `{ issueId, changelogId, field, fromString, toString }`, keyed on the
+/// issue, the changelog and the field, so sinks keep one document per field change. Events
+/// without changelog items produce no events.
+pub struct ChangelogSplitProcessor;
+
+impl ChangelogSplitProcessor {
+    /// String form of a body value used in a primary key; empty when missing
+    fn key_value(value: &Value) -> String {
+        match value {
+            Value::String(s) => s.clone(),
+            Value::Null => String::new(),
+            other => other.to_string(),
+        }
+    }
+
+    fn split(event: &PipelineEvent) -> Vec<PipelineEvent> {
+        let issue_id = event.body.pointer("/issue/id").cloned().unwrap_or(Value::Null);
+        let changelog_id = event.body.pointer("/changelog/id").cloned().unwrap_or(Value::Null);
+        let items = event.body
+            .pointer("/changelog/items")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        items
+            .iter()
+            .filter_map(|item| {
+                let field = item.get("field")?.as_str()?;
+                let body = json!({
+                    "issueId": issue_id,
+                    "changelogId": changelog_id,
+                    "field": field,
+                    "fromString": item.get("fromString").cloned().unwrap_or(Value::Null),
+                    "toString": item.get("toString").cloned().unwrap_or(Value::Null),
+                });
+                let pk_fields = vec![
+                    PkField { key: "issueId".to_string(), value: Self::key_value(&issue_id) },
+                    PkField { key: "changelogId".to_string(), value: Self::key_value(&changelog_id) },
+                    PkField { key: "field".to_string(), value: field.to_string() },
+                ];
+
+                Some(event.derive(body, pk_fields))
+            })
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for ChangelogSplitProcessor {
+    /// The first field change only; pipelines use `run`, which returns all of them
+    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        Ok(Self::split(&event).into_iter().next())
+    }
+
+    async fn run(&self, event: PipelineEvent) -> Result<ProcessorOutput> {
+        let deltas = Self::split(&event);
+        if deltas.is_empty() {
+            tracing::debug!("Event {} has no changelog items, dropping it", event.id);
+            return Ok(ProcessorOutput::Filtered);
+        }
+
+        Ok(ProcessorOutput::Split(deltas))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::event::Operation;
+
+    #[tokio::test]
+    async fn test_two_item_changelog_produces_two_deltas() {
+        let body = json!({
+            "webhookEvent": "jira:issue_updated",
+            "issue": { "id": "10001", "key": "PROJ-1" },
+            "changelog": {
+                "id": "20500",
+                "items": [
+                    { "field": "status", "from": "1", "fromString": "To Do", "to": "3", "toString": "In Progress" },
+                    { "field": "assignee", "from": null, "fromString": null, "to": "u1", "toString": "Ada" }
+                ]
+            }
+        });
+        let event = PipelineEvent::new(body, "jira:issue_updated".to_string(), vec![], Operation::Write)
+            .with_delivery_id("delivery-1".to_string());
+
+        let ProcessorOutput::Split(deltas) = ChangelogSplitProcessor.run(event).await.unwrap() else {
+            panic!("expected the event to be split");
+        };
+
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(*deltas[0].body, json!({
+            "issueId": "10001", "changelogId": "20500", "field": "status", "fromString": "To Do", "toString": "In Progress"
+        }));
+        assert_eq!(*deltas[1].body, json!({
+            "issueId": "10001", "changelogId": "20500", "field": "assignee", "fromString": null, "toString": "Ada"
+        }));
+        assert_ne!(deltas[0].id, deltas[1].id);
+        assert_eq!(deltas[1].pk_fields[2].value, "assignee");
+        assert!(deltas.iter().all(|delta| delta.delivery_id == "delivery-1" && delta.event_type == "jira:issue_updated"));
+        assert_eq!(deltas[0].raw_body["changelog"]["items"][1]["toString"], "Ada");
+    }
+
+    #[tokio::test]
+    async fn test_event_without_changelog_is_filtered() {
+        let event = PipelineEvent::new(json!({ "issue": { "id": "1" } }), "jira:issue_created".to_string(), vec![], Operation::Write);
+
+        assert!(matches!(ChangelogSplitProcessor.run(event).await.unwrap(), ProcessorOutput::Filtered));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::error::{AppError, Result};
+use crate::pipeline::event::PipelineEvent;
+use super::filter::event_context;
+use super::{path, Processor};
+use cel_interpreter::Program;
+use std::collections::HashMap;
+
+/// Processor that writes the results of CEL expressions to body paths
+///
+/// Every expression sees the event as it entered the processor, so one computed field can't
+/// refer to another computed by the same processor.
+pub struct ComputeProcessor {
+    fields: Vec<(String, Program)>,
+}
+
+impl ComputeProcessor {
+    pub fn new(fields: &HashMap<String, String>) -> Result<Self> {
+        let mut fields = fields
+            .iter()
+            .map(|(field, expression)| {
+                let program = Program::compile(expression).map_err(|e| AppError::Config(format!(
+                    "Failed to compile CEL expression for '{}': {}",
+                    field, e
+                )))?;
+                Ok((field.clone(), program))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Write in a stable order, so nested paths always land the same way
+        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(Self { fields })
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for ComputeProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let context = event_context(&event)?;
+
+        let mut values = Vec::with_capacity(self.fields.len());
+        for (field, program) in &self.fields {
+            let result = program.execute(&context).map_err(|e| AppError::Processing(format!(
+                "Failed to evaluate CEL expression for '{}': {}",
+                field, e
+            )))?;
+            let value = result.json().map_err(|e| AppError::Processing(format!(
+                "Result for '{}' can't be represented as JSON: {}",
+                field, e
+            )))?;
+            values.push((field, value));
+        }
+
+        for (field, value) in values {
+            if !path::set(event.body_mut(), field, value) {
+                return Err(AppError::Processing(format!(
+                    "Cannot write computed field '{}': parent is not an object",
+                    field
+                )));
+            }
+        }
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::event::Operation;
+    use serde_json::json;
+
+    async fn compute(fields: &[(&str, &str)], body: serde_json::Value) -> Result<PipelineEvent> {
+        let fields = fields
+            .iter()
+            .map(|(field, expression)| (field.to_string(), expression.to_string()))
+            .collect();
+        let processor = ComputeProcessor::new(&fields)?;
+
+        let event = PipelineEvent::new(body, "jira:issue_created".to_string(), vec![], Operation::Write);
+        Ok(processor.process(event).await?.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_computes_boolean_field() {
+        let result = compute(&[("isHighPriority", "priority > 3")], json!({"priority": 4})).await.unwrap();
+
+        assert_eq!(result.body["isHighPriority"], true);
+        assert_eq!(result.body["priority"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_computes_concatenated_string_into_nested_path() {
+        let result = compute(
+            &[("meta.title", "issue.key + ': ' + issue.fields.summary")],
+            json!({"issue": {"key": "PROJ-1", "fields": {"summary": "Broken login"}}}),
+        ).await.unwrap();
+
+        assert_eq!(result.body["meta"]["title"], "PROJ-1: Broken login");
+    }
+
+    #[tokio::test]
+    async fn test_maps_lists_maps_and_numbers_to_json() {
+        let result = compute(
+            &[
+                ("labels", "[eventType, 'extra']"),
+                ("summary", "{'count': size(items), 'ratio': 1.5}"),
+            ],
+            json!({"items": [1, 2, 3]}),
+        ).await.unwrap();
+
+        assert_eq!(result.body["labels"], json!(["jira:issue_created", "extra"]));
+        assert_eq!(result.body["summary"], json!({"count": 3, "ratio": 1.5}));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_expression_fails_at_construction() {
+        assert!(compute(&[("broken", "priority >")], json!({})).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_evaluation_error_fails_event() {
+        assert!(compute(&[("missing", "nope + 1")], json!({})).await.is_err());
+    }
+}
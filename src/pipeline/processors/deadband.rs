@@ -0,0 +1,143 @@
+use crate::error::Result;
+use crate::pipeline::event::{Operation, PipelineEvent};
+use super::{path, Processor};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Values of the watched paths last seen per event id, bounded in number of ids
+#[derive(Default)]
+struct LastSeen {
+    values: HashMap<String, Vec<Option<Value>>>,
+    /// Ids in the order they were first seen, evicted oldest first
+    order: VecDeque<String>,
+}
+
+impl LastSeen {
+    fn remove(&mut self, id: &str) {
+        if self.values.remove(id).is_some() {
+            self.order.retain(|seen| seen != id);
+        }
+    }
+}
+
+/// Processor that drops writes leaving a set of body paths unchanged since the last event with
+/// the same id
+///
+/// Event ids are derived from the primary keys, so each document is compared with its own
+/// previous version. Deletes always pass and forget the document, as does a failure of the event
+/// further down the pipeline, so a redelivery isn't dropped as unchanged.
+pub struct DeadbandProcessor {
+    paths: Vec<String>,
+    max_keys: usize,
+    last_seen: Mutex<LastSeen>,
+}
+
+impl DeadbandProcessor {
+    pub fn new(paths: Vec<String>, max_keys: usize) -> Self {
+        Self {
+            paths,
+            max_keys: max_keys.max(1),
+            last_seen: Mutex::default(),
+        }
+    }
+
+    fn watched(&self, body: &Value) -> Vec<Option<Value>> {
+        self.paths.iter().map(|path| path::get(body, path).cloned()).collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for DeadbandProcessor {
+    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let mut last_seen = self.last_seen.lock().unwrap();
+
+        if event.operation == Operation::Delete {
+            last_seen.remove(&event.id);
+            return Ok(Some(event));
+        }
+
+        let values = self.watched(&event.body);
+        match last_seen.values.get_mut(&event.id) {
+            Some(previous) if *previous == values => {
+                tracing::debug!("Event {} leaves the watched fields unchanged, dropping it", event.id);
+                return Ok(None);
+            }
+            Some(previous) => *previous = values,
+            None => {
+                if last_seen.order.len() >= self.max_keys {
+                    if let Some(oldest) = last_seen.order.pop_front() {
+                        last_seen.values.remove(&oldest);
+                    }
+                }
+                last_seen.order.push_back(event.id.clone());
+                last_seen.values.insert(event.id.clone(), values);
+            }
+        }
+
+        Ok(Some(event))
+    }
+
+    fn forget(&self, event_id: &str) {
+        self.last_seen.lock().unwrap().remove(event_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::event::PkField;
+    use serde_json::json;
+
+    fn update(issue_id: &str, status: &str, updated: &str) -> PipelineEvent {
+        PipelineEvent::new(
+            json!({"issue": {"id": issue_id, "fields": {"status": status, "updated": updated}}}),
+            "jira:issue_updated".to_string(),
+            vec![PkField { key: "issue.id".to_string(), value: issue_id.to_string() }],
+            Operation::Write,
+        )
+    }
+
+    fn deadband(max_keys: usize) -> DeadbandProcessor {
+        DeadbandProcessor::new(vec!["issue.fields.status".to_string()], max_keys)
+    }
+
+    #[tokio::test]
+    async fn test_identical_update_is_dropped() {
+        let processor = deadband(100);
+
+        assert!(processor.process(update("1", "Open", "10:00")).await.unwrap().is_some());
+        // Only an unwatched field differs
+        assert!(processor.process(update("1", "Open", "10:05")).await.unwrap().is_none());
+        assert!(processor.process(update("1", "Done", "10:10")).await.unwrap().is_some());
+        // Other issues are tracked separately
+        assert!(processor.process(update("2", "Done", "10:10")).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_delete_and_eviction_forget_last_values() {
+        let processor = deadband(1);
+
+        processor.process(update("1", "Open", "10:00")).await.unwrap();
+        let mut delete = update("1", "Open", "10:00");
+        delete.operation = Operation::Delete;
+        assert!(processor.process(delete).await.unwrap().is_some());
+        assert!(processor.process(update("1", "Open", "10:00")).await.unwrap().is_some());
+
+        // Issue 2 evicts issue 1, so its next update passes again
+        processor.process(update("2", "Open", "10:00")).await.unwrap();
+        assert!(processor.process(update("1", "Open", "10:00")).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_failed_event_is_forgotten() {
+        let processor = deadband(100);
+
+        processor.process(update("1", "Open", "10:00")).await.unwrap();
+        let event_id = update("1", "Open", "10:00").id;
+        processor.forget(&event_id);
+
+        // The redelivery of a failed event gets through again
+        assert!(processor.process(update("1", "Open", "10:00")).await.unwrap().is_some());
+    }
+}
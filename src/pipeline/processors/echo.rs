@@ -0,0 +1,78 @@
+use crate::error::Result;
+use crate::pipeline::event::PipelineEvent;
+use super::Processor;
+
+/// Processor that logs events at debug level and passes them on unchanged
+///
+/// Useful as a marker when checking what reaches a given point of a pipeline.
+pub struct EchoProcessor {
+    label: Option<String>,
+}
+
+impl EchoProcessor {
+    pub fn new(label: Option<String>) -> Self {
+        Self { label }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for EchoProcessor {
+    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        tracing::debug!(
+            label = self.label.as_deref().unwrap_or("echo"),
+            event_id = %event.id,
+            event_type = %event.event_type,
+            body = %event.body,
+            "Echo",
+        );
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use crate::pipeline::event::Operation;
+
+    /// Log output captured by the test subscriber
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_passes_event_through_and_logs_label() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let event = PipelineEvent::new(json!({"id": "1"}), "test_event".to_string(), vec![], Operation::Write);
+        let echo = EchoProcessor::new(Some("after-mapper".to_string()));
+
+        let passed = echo.process(event.clone()).await.unwrap().unwrap();
+        assert_eq!(passed.id, event.id);
+        assert_eq!(passed.body, event.body);
+        assert_eq!(passed.event_type, event.event_type);
+
+        let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("label=\"after-mapper\""), "unexpected logs: {}", logs);
+        assert!(logs.contains(&event.id), "unexpected logs: {}", logs);
+    }
+}
@@ -2,10 +2,26 @@ use crate::error::{AppError, Result};
 use crate::pipeline::event::PipelineEvent;
 use super::Processor;
 use cel_interpreter::{Context, Program};
+use serde::{Deserialize, Serialize};
+
+/// What to do with an event the expression can't be evaluated for, e.g. because it reads a
+/// field the body doesn't have
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterErrorPolicy {
+    /// Fail the event
+    #[default]
+    Error,
+    /// Keep the event, as if the expression were true
+    Pass,
+    /// Filter the event out, as if the expression were false
+    Drop,
+}
 
 /// Filter processor that evaluates CEL expressions
 pub struct FilterProcessor {
     program: Program,
+    on_error: FilterErrorPolicy,
 }
 
 impl FilterProcessor {
@@ -13,31 +29,17 @@ impl FilterProcessor {
         let program = Program::compile(cel_expression)
             .map_err(|e| AppError::Config(format!("Failed to compile CEL expression: {}", e)))?;
         
-        Ok(Self { program })
+        Ok(Self { program, on_error: FilterErrorPolicy::default() })
     }
-}
-
-#[async_trait::async_trait]
-impl Processor for FilterProcessor {
-    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
-        // Create CEL context with event data
-        let mut context = Context::default();
-        
-        // Add event fields to context
-        context.add_variable("eventType", event.event_type.clone())
-            .map_err(|e| AppError::Processing(format!("Failed to add eventType to context: {}", e)))?;
-        
-        // Add the entire body as a variable
-        context.add_variable("body", event.body.clone())
-            .map_err(|e| AppError::Processing(format!("Failed to add body to context: {}", e)))?;
-        
-        // If body is an object, add its top-level fields directly
-        if let Some(obj) = event.body.as_object() {
-            for (key, value) in obj {
-                context.add_variable(key, value.clone())
-                    .map_err(|e| AppError::Processing(format!("Failed to add field {} to context: {}", key, e)))?;
-            }
-        }
+    
+    pub fn with_on_error(mut self, on_error: FilterErrorPolicy) -> Self {
+        self.on_error = on_error;
+        self
+    }
+    
+    /// Evaluate the expression against the event
+    pub fn matches(&self, event: &PipelineEvent) -> Result<bool> {
+        let context = event_context(event)?;
         
         // Evaluate the expression
         let result = self.program.execute(&context)
@@ -46,8 +48,7 @@ impl Processor for FilterProcessor {
         // Check if result is a boolean true
         // CEL interpreter returns a cel_interpreter::Value, check if it's a boolean
         match &result {
-            cel_interpreter::Value::Bool(true) => Ok(Some(event)),
-            cel_interpreter::Value::Bool(false) => Ok(None),
+            cel_interpreter::Value::Bool(matched) => Ok(*matched),
             _ => Err(AppError::Processing(
                 format!("CEL expression did not evaluate to boolean, got: {:?}", result)
             )),
@@ -55,6 +56,51 @@ impl Processor for FilterProcessor {
     }
 }
 
+/// CEL context exposing `eventType`, `body` and the body's top-level fields
+pub(crate) fn event_context(event: &PipelineEvent) -> Result<Context<'static>> {
+    // Create CEL context with event data
+    let mut context = Context::default();
+    
+    // Add event fields to context
+    context.add_variable("eventType", event.event_type.clone())
+        .map_err(|e| AppError::Processing(format!("Failed to add eventType to context: {}", e)))?;
+    
+    // Add the entire body as a variable
+    context.add_variable("body", event.body.clone())
+        .map_err(|e| AppError::Processing(format!("Failed to add body to context: {}", e)))?;
+    
+    // If body is an object, add its top-level fields directly
+    if let Some(obj) = event.body.as_object() {
+        for (key, value) in obj {
+            context.add_variable(key, value.clone())
+                .map_err(|e| AppError::Processing(format!("Failed to add field {} to context: {}", key, e)))?;
+        }
+    }
+    
+    Ok(context)
+}
+
+#[async_trait::async_trait]
+impl Processor for FilterProcessor {
+    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let matched = match (self.matches(&event), self.on_error) {
+            (Ok(matched), _) => matched,
+            (Err(e), FilterErrorPolicy::Error) => return Err(e),
+            (Err(e), policy) => {
+                tracing::debug!("Filter failed for event {}, applying {:?}: {}", event.id, policy, e);
+                policy == FilterErrorPolicy::Pass
+            }
+        };
+        
+        if matched {
+            Ok(Some(event))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,4 +151,22 @@ mod tests {
         let result = filter.process(event).await.unwrap();
         assert!(result.is_some());
     }
+    
+    #[tokio::test]
+    async fn test_on_error_policies_for_missing_field() {
+        // The issue has no priority, so the expression fails to evaluate
+        let issue = || PipelineEvent::new(
+            json!({"issue": {"key": "PROJ-1", "fields": {}}}),
+            "test_event".to_string(),
+            vec![],
+            Operation::Write,
+        );
+        let filter = |on_error| {
+            FilterProcessor::new("issue.fields.priority.name == 'High'").unwrap().with_on_error(on_error)
+        };
+        
+        assert!(filter(FilterErrorPolicy::Error).process(issue()).await.is_err());
+        assert!(filter(FilterErrorPolicy::Pass).process(issue()).await.unwrap().is_some());
+        assert!(filter(FilterErrorPolicy::Drop).process(issue()).await.unwrap().is_none());
+    }
 }
@@ -0,0 +1,77 @@
+use crate::error::{AppError, Result};
+use crate::pipeline::event::PipelineEvent;
+use super::{path, Processor};
+use serde_json::{json, Map, Value};
+
+/// Processor that writes the event's primary key fields and derived `id` into the body
+///
+/// Sinks only store the body, so without this the canonical key is only visible if the body
+/// already happened to contain it.
+pub struct InjectPrimaryKeyProcessor {
+    key: String,
+}
+
+impl InjectPrimaryKeyProcessor {
+    pub fn new(key: String) -> Self {
+        Self { key }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for InjectPrimaryKeyProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let fields: Map<String, Value> = event.pk_fields
+            .iter()
+            .map(|field| (field.key.clone(), Value::String(field.value.clone())))
+            .collect();
+
+        let value = json!({
+            "id": event.id,
+            "fields": fields,
+        });
+
+        if !path::set(event.body_mut(), &self.key, value) {
+            return Err(AppError::Processing(format!(
+                "Cannot inject primary key at '{}': parent is not an object",
+                self.key
+            )));
+        }
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::event::{Operation, PkField};
+
+    #[tokio::test]
+    async fn test_injected_key_matches_event_id() {
+        let processor = InjectPrimaryKeyProcessor::new("_key".to_string());
+
+        let event = PipelineEvent::new(
+            json!({"summary": "Test"}),
+            "jira:issue_created".to_string(),
+            vec![PkField { key: "issue.id".to_string(), value: "10001".to_string() }],
+            Operation::Write,
+        );
+        let id = event.id.clone();
+
+        let result = processor.process(event).await.unwrap().unwrap();
+
+        assert_eq!(result.body["summary"], "Test");
+        assert_eq!(result.body["_key"]["id"], id.as_str());
+        assert_eq!(result.body["_key"]["fields"], json!({"issue.id": "10001"}));
+        assert_eq!(result.id, id);
+    }
+
+    #[tokio::test]
+    async fn test_non_object_body_is_rejected() {
+        let processor = InjectPrimaryKeyProcessor::new("_key".to_string());
+
+        let event = PipelineEvent::new(json!([1, 2]), "test_event".to_string(), vec![], Operation::Write);
+
+        assert!(processor.process(event).await.is_err());
+    }
+}
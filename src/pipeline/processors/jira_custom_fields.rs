@@ -0,0 +1,195 @@
+use crate::config::secret::SecretSource;
+use crate::error::{AppError, Result};
+use crate::pipeline::event::PipelineEvent;
+use super::Processor;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Connection settings for fetching custom field names from Jira's field API
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct JiraFieldApi {
+    #[serde(rename = "baseUrl")]
+    pub base_url: String,
+    /// Account email; when set, basic auth is used, otherwise the token is sent as a bearer token
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<SecretSource>,
+    #[serde(rename = "apiToken")]
+    pub api_token: SecretSource,
+    /// How long the request may take before startup fails
+    #[serde(rename = "timeoutMs", default = "default_field_api_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_field_api_timeout_ms() -> u64 {
+    10_000
+}
+
+/// Processor that renames `customfield_*` keys under `issue.fields` to their human names
+pub struct JiraCustomFieldsProcessor {
+    mapping: HashMap<String, String>,
+}
+
+impl JiraCustomFieldsProcessor {
+    pub async fn new(mapping: HashMap<String, String>, field_api: Option<&JiraFieldApi>) -> Result<Self> {
+        let mut resolved = match field_api {
+            Some(api) => Self::fetch_mapping(api).await?,
+            None => HashMap::new(),
+        };
+
+        // Static entries take precedence over the fetched ones
+        resolved.extend(mapping);
+
+        Ok(Self { mapping: resolved })
+    }
+
+    async fn fetch_mapping(api: &JiraFieldApi) -> Result<HashMap<String, String>> {
+        let url = format!("{}/rest/api/2/field", api.base_url.trim_end_matches('/'));
+        let token = api.api_token.resolve()?;
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(api.timeout_ms))
+            .build()
+            .map_err(|e| AppError::Config(format!("Failed to build Jira field API client: {}", e)))?;
+        let request = client.get(&url);
+        let request = match &api.email {
+            Some(email) => request.basic_auth(email.resolve()?, Some(token)),
+            None => request.bearer_auth(token),
+        };
+
+        let _permit = crate::outbound::acquire(&url).await;
+        let response = request
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| AppError::Config(format!("Failed to fetch Jira fields from {}: {}", url, e)))?;
+
+        let fields: Value = response
+            .json()
+            .await
+            .map_err(|e| AppError::Config(format!("Invalid Jira field API response: {}", e)))?;
+
+        let mapping = Self::parse_field_list(&fields)?;
+        tracing::info!("Loaded {} Jira custom field names from {}", mapping.len(), url);
+
+        Ok(mapping)
+    }
+
+    /// Build an id -> name mapping from the `/rest/api/2/field` response
+    fn parse_field_list(fields: &Value) -> Result<HashMap<String, String>> {
+        let fields = fields.as_array()
+            .ok_or_else(|| AppError::Config("Jira field API response is not an array".to_string()))?;
+
+        Ok(fields
+            .iter()
+            .filter(|f| f.get("custom").and_then(Value::as_bool).unwrap_or(false))
+            .filter_map(|f| {
+                let id = f.get("id")?.as_str()?;
+                let name = f.get("name")?.as_str()?;
+                Some((id.to_string(), name.to_string()))
+            })
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for JiraCustomFieldsProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let fields = event.body_mut()
+            .get_mut("issue")
+            .and_then(|issue| issue.get_mut("fields"))
+            .and_then(Value::as_object_mut);
+
+        if let Some(fields) = fields {
+            for (id, name) in &self.mapping {
+                // Never overwrite a field that already has the name; the custom field keeps its id
+                if fields.contains_key(name) {
+                    tracing::debug!("Field {} already exists, leaving {} unrenamed", name, id);
+                    continue;
+                }
+                if let Some(value) = fields.remove(id) {
+                    fields.insert(name.clone(), value);
+                }
+            }
+        }
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::pipeline::event::Operation;
+
+    #[tokio::test]
+    async fn test_static_mapping_renames_custom_fields() {
+        let mapping = HashMap::from([
+            ("customfield_10001".to_string(), "Story Points".to_string()),
+            ("customfield_10002".to_string(), "Team".to_string()),
+        ]);
+        let processor = JiraCustomFieldsProcessor::new(mapping, None).await.unwrap();
+
+        let event = PipelineEvent::new(
+            json!({
+                "issue": {
+                    "id": "1",
+                    "fields": {
+                        "summary": "Test",
+                        "customfield_10001": 5,
+                        "customfield_10002": {"value": "Platform"},
+                        "customfield_10003": "untouched"
+                    }
+                }
+            }),
+            "jira:issue_updated".to_string(),
+            vec![],
+            Operation::Write,
+        );
+
+        let result = processor.process(event).await.unwrap().unwrap();
+        let fields = &result.body["issue"]["fields"];
+
+        assert_eq!(fields["Story Points"], 5);
+        assert_eq!(fields["Team"]["value"], "Platform");
+        assert_eq!(fields["customfield_10003"], "untouched");
+        assert_eq!(fields["summary"], "Test");
+        assert!(fields.get("customfield_10001").is_none());
+        assert!(fields.get("customfield_10002").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mapped_name_never_overwrites_an_existing_field() {
+        let mapping = HashMap::from([("customfield_10001".to_string(), "summary".to_string())]);
+        let processor = JiraCustomFieldsProcessor::new(mapping, None).await.unwrap();
+
+        let event = PipelineEvent::new(
+            json!({"issue": {"fields": {"summary": "Test", "customfield_10001": 5}}}),
+            "jira:issue_updated".to_string(),
+            vec![],
+            Operation::Write,
+        );
+
+        let result = processor.process(event).await.unwrap().unwrap();
+        let fields = &result.body["issue"]["fields"];
+
+        assert_eq!(fields["summary"], "Test");
+        assert_eq!(fields["customfield_10001"], 5);
+    }
+
+    #[test]
+    fn test_parse_field_list_keeps_custom_fields_only() {
+        let fields = json!([
+            {"id": "summary", "name": "Summary", "custom": false},
+            {"id": "customfield_10001", "name": "Story Points", "custom": true}
+        ]);
+
+        let mapping = JiraCustomFieldsProcessor::parse_field_list(&fields).unwrap();
+
+        assert_eq!(mapping.len(), 1);
+        assert_eq!(mapping["customfield_10001"], "Story Points");
+    }
+}
@@ -0,0 +1,118 @@
+use crate::error::Result;
+use crate::pipeline::event::PipelineEvent;
+use super::{path, Processor};
+use serde_json::{json, Value};
+
+/// Processor that rewrites Jira user objects into a single `{ id, display_name, email }` shape
+///
+/// Cloud payloads identify users by `accountId`, while Server/Data Center ones use `key` or
+/// `name`; the first of these present becomes `id`. Missing attributes are written as `null`.
+pub struct JiraUsersProcessor {
+    paths: Vec<String>,
+}
+
+impl JiraUsersProcessor {
+    pub fn new(paths: Vec<String>) -> Self {
+        Self { paths }
+    }
+
+    fn normalize(user: &Value) -> Value {
+        let field = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| user.get(*name).filter(|value| !value.is_null()))
+                .cloned()
+                .unwrap_or(Value::Null)
+        };
+
+        json!({
+            "id": field(&["accountId", "key", "name"]),
+            "display_name": field(&["displayName", "name"]),
+            "email": field(&["emailAddress"]),
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for JiraUsersProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        for user_path in &self.paths {
+            // Unassigned users are `null` and absent ones are skipped, both are left as they are
+            if let Some(user) = path::get_mut(event.body_mut(), user_path) {
+                if user.is_object() {
+                    *user = Self::normalize(user);
+                }
+            }
+        }
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::event::Operation;
+
+    fn processor() -> JiraUsersProcessor {
+        JiraUsersProcessor::new(vec![
+            "issue.fields.assignee".to_string(),
+            "issue.fields.reporter".to_string(),
+        ])
+    }
+
+    fn event(fields: Value) -> PipelineEvent {
+        PipelineEvent::new(
+            json!({"issue": {"id": "1", "fields": fields}}),
+            "jira:issue_updated".to_string(),
+            vec![],
+            Operation::Write,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_account_id_only_user() {
+        let result = processor()
+            .process(event(json!({"assignee": {"accountId": "5b10a2844c20165700ede21g", "active": true}})))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            result.body["issue"]["fields"]["assignee"],
+            json!({"id": "5b10a2844c20165700ede21g", "display_name": null, "email": null})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_legacy_name_based_user() {
+        let result = processor()
+            .process(event(json!({
+                "reporter": {"name": "jdoe", "displayName": "Jane Doe", "emailAddress": "jdoe@example.com"},
+                "assignee": null
+            })))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let fields = &result.body["issue"]["fields"];
+        assert_eq!(
+            fields["reporter"],
+            json!({"id": "jdoe", "display_name": "Jane Doe", "email": "jdoe@example.com"})
+        );
+        assert!(fields["assignee"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_key_is_preferred_over_name() {
+        let result = processor()
+            .process(event(json!({"reporter": {"key": "JIRAUSER10100", "name": "jdoe"}})))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let reporter = &result.body["issue"]["fields"]["reporter"];
+        assert_eq!(reporter["id"], "JIRAUSER10100");
+        assert_eq!(reporter["display_name"], "jdoe");
+    }
+}
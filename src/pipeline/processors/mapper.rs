@@ -1,6 +1,6 @@
 use crate::error::{AppError, Result};
 use crate::pipeline::event::PipelineEvent;
-use super::Processor;
+use super::{path, Processor};
 use handlebars::Handlebars;
 use serde_json::Value;
 
@@ -16,6 +16,11 @@ impl MapperProcessor {
         Ok(Self { handlebars, template })
     }
     
+    /// Render the template against a body
+    pub fn render(&self, body: &Value) -> Result<Value> {
+        self.render_value(&self.template, body)
+    }
+    
     /// Recursively render a template value
     fn render_value(&self, value: &Value, context: &Value) -> Result<Value> {
         match value {
@@ -32,7 +37,7 @@ impl MapperProcessor {
                     
                     // Check if it's a pure variable reference (no filters, no string concatenation)
                     if !inner.contains('|') && !trimmed.contains("{{") || trimmed.matches("{{").count() == 1 {
-                        if let Some(raw_value) = self.extract_value_from_path(inner, context) {
+                        if let Some(raw_value) = path::get(context, inner) {
                             return Ok(raw_value.clone());
                         } 
                         
@@ -65,9 +70,20 @@ impl MapperProcessor {
                     
                     let rendered_value = self.render_value(value_template, context)?;
                     
+                    if rendered_value.is_null() {
+                        if let Some(null_as) = map.get("nullAs") {
+                            return self.cast_null(null_as, cast_to);
+                        }
+                    }
+                    
                     return self.cast_value(&rendered_value, cast_to);
                 }
                 
+                // Check if this is an array mapping with "forEach" and "template" fields
+                if map.contains_key("forEach") && map.contains_key("template") {
+                    return self.render_for_each(map, context);
+                }
+                
                 // Otherwise, recursively render all values in the object
                 let mut result = serde_json::Map::new();
                 for (key, val) in map {
@@ -87,25 +103,50 @@ impl MapperProcessor {
         }
     }
     
-    /// Extract a value from a JSON path like "foo.bar.0.baz"
-    fn extract_value_from_path<'a>(&self, path: &str, context: &'a Value) -> Option<&'a Value> {
-        let parts: Vec<&str> = path.split('.').collect();
-        let mut current = context;
-        
-        for part in parts {
-            match current {
-                Value::Object(map) => {
-                    current = map.get(part)?;
-                }
-                Value::Array(arr) => {
-                    let index: usize = part.parse().ok()?;
-                    current = arr.get(index)?;
-                }
-                _ => return None,
+    /// Render `template` once per element of the `forEach` array, with the element in the
+    /// context under `as` (default `item`)
+    fn render_for_each(&self, map: &serde_json::Map<String, Value>, context: &Value) -> Result<Value> {
+        let name = match map.get("as") {
+            None => "item",
+            Some(Value::String(name)) => name.as_str(),
+            Some(_) => return Err(AppError::Processing("as must be a string".to_string())),
+        };
+        
+        let items = match self.render_value(&map["forEach"], context)? {
+            Value::Array(items) => items,
+            // A missing list maps to an empty one
+            Value::Null => Vec::new(),
+            other => {
+                return Err(AppError::Processing(format!("forEach must render to an array, got {}", other)));
             }
+        };
+        
+        // Copied once, then only the `as` entry changes from one element to the next
+        let mut item_context = Value::Object(match context {
+            Value::Object(fields) => fields.clone(),
+            _ => serde_json::Map::new(),
+        });
+        
+        let mut result = Vec::with_capacity(items.len());
+        for item in items {
+            item_context[name] = item;
+            result.push(self.render_value(&map["template"], &item_context)?);
         }
         
-        Some(current)
+        Ok(Value::Array(result))
+    }
+    
+    /// Resolve a null value according to `nullAs`: `"null"` keeps it, `"error"` fails and
+    /// anything else is a default cast like the value would have been
+    fn cast_null(&self, null_as: &Value, cast_to: &str) -> Result<Value> {
+        match null_as.as_str() {
+            Some("null") => Ok(Value::Null),
+            Some("error") => Err(AppError::Processing(
+                format!("Cannot cast null to {}", cast_to)
+            )),
+            _ if null_as.is_null() => Ok(Value::Null),
+            _ => self.cast_value(null_as, cast_to),
+        }
     }
     
     fn cast_value(&self, value: &Value, cast_to: &str) -> Result<Value> {
@@ -142,8 +183,46 @@ impl MapperProcessor {
                     )),
                 }
             }
+            "integer" => {
+                let float = match value {
+                    Value::Number(n) if n.is_i64() || n.is_u64() => return Ok(Value::Number(n.clone())),
+                    Value::Number(n) => n.as_f64(),
+                    Value::String(s) => {
+                        if let Ok(i) = s.trim().parse::<i64>() {
+                            return Ok(serde_json::json!(i));
+                        }
+                        s.trim().parse::<f64>().ok()
+                    }
+                    Value::Bool(b) => return Ok(serde_json::json!(if *b { 1 } else { 0 })),
+                    _ => None,
+                };
+                
+                match float {
+                    Some(f) if f.fract() == 0.0 && f >= i64::MIN as f64 && f <= i64::MAX as f64 => {
+                        Ok(serde_json::json!(f as i64))
+                    }
+                    _ => Err(AppError::Processing(
+                        format!("Cannot cast {} to integer", value)
+                    )),
+                }
+            }
+            "float" => {
+                let float = match value {
+                    Value::Number(n) => n.as_f64(),
+                    Value::String(s) => s.trim().parse::<f64>().ok(),
+                    Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+                    _ => None,
+                };
+                
+                float
+                    .filter(|f| f.is_finite())
+                    .map(|f| serde_json::json!(f))
+                    .ok_or_else(|| AppError::Processing(
+                        format!("Cannot cast {} to float", value)
+                    ))
+            }
             _ => Err(AppError::Processing(
-                format!("Unsupported cast type: '{}'. Supported types are: string, number", cast_to)
+                format!("Unsupported cast type: '{}'. Supported types are: string, number, integer, float", cast_to)
             )),
         }
     }
@@ -153,10 +232,10 @@ impl MapperProcessor {
 impl Processor for MapperProcessor {
     async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
         // Render the template with the event body as context
-        let new_body = self.render_value(&self.template, &event.body)?;
+        let new_body = self.render(&event.body)?;
         
         // Update the event body
-        event.body = new_body;
+        event.set_body(new_body);
         
         Ok(Some(event))
     }
@@ -490,4 +569,189 @@ mod tests {
         assert!(result_event.body["missingField"].is_null());
         assert!(result_event.body["nestedMissing"].is_null());
     }
+    
+    #[tokio::test]
+    async fn test_json_pointer_references() {
+        let template = json!({
+            "status": "{{ /issue/fields/status/name }}",
+            "team": "{{ /issue/fields/team.name }}",
+            "firstVersion": {
+                "value": "{{ /issue/fields/fixVersions/0/id }}",
+                "castTo": "number"
+            }
+        });
+        
+        let mapper = MapperProcessor::new(template).unwrap();
+        
+        let event = PipelineEvent::new(
+            json!({
+                "issue": {
+                    "fields": {
+                        "status": { "name": "Open" },
+                        "team.name": "Platform",
+                        "fixVersions": [{ "id": "10" }]
+                    }
+                }
+            }),
+            "test_event".to_string(),
+            vec![],
+            Operation::Write,
+        );
+        
+        let result_event = mapper.process(event).await.unwrap().unwrap();
+        
+        assert_eq!(result_event.body["status"], "Open");
+        assert_eq!(result_event.body["team"], "Platform");
+        assert_eq!(result_event.body["firstVersion"], 10);
+    }
+    
+    #[tokio::test]
+    async fn test_cast_integer_and_float() {
+        let template = json!({
+            "asFloat": { "value": "{{ a }}", "castTo": "float" },
+            "asInteger": { "value": "{{ b }}", "castTo": "integer" },
+            "wholeFloatAsInteger": { "value": "{{ a }}", "castTo": "integer" }
+        });
+        
+        let mapper = MapperProcessor::new(template).unwrap();
+        
+        let event = PipelineEvent::new(
+            json!({ "a": "3.0", "b": "3" }),
+            "test_event".to_string(),
+            vec![],
+            Operation::Write,
+        );
+        
+        let result_event = mapper.process(event).await.unwrap().unwrap();
+        
+        assert!(result_event.body["asFloat"].is_f64());
+        assert_eq!(result_event.body["asFloat"], 3.0);
+        assert!(result_event.body["asInteger"].is_i64());
+        assert_eq!(result_event.body["asInteger"], 3);
+        assert!(result_event.body["wholeFloatAsInteger"].is_i64());
+    }
+    
+    #[tokio::test]
+    async fn test_cast_fractional_to_integer_fails() {
+        let template = json!({
+            "asInteger": { "value": "{{ a }}", "castTo": "integer" }
+        });
+        
+        let mapper = MapperProcessor::new(template).unwrap();
+        
+        let event = PipelineEvent::new(
+            json!({ "a": "3.5" }),
+            "test_event".to_string(),
+            vec![],
+            Operation::Write,
+        );
+        
+        assert!(mapper.process(event).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_mapped_event_keeps_raw_body() {
+        let mapper = MapperProcessor::new(json!({ "key": "{{ issue.key }}" })).unwrap();
+        let body = json!({ "issue": { "key": "PROJ-1", "fields": { "summary": "s" } } });
+        
+        let event = PipelineEvent::new(body.clone(), "test_event".to_string(), vec![], Operation::Write);
+        // Until a processor changes the body, it is the raw body
+        assert!(std::sync::Arc::ptr_eq(&event.body, &event.raw_body));
+        let result_event = mapper.process(event).await.unwrap().unwrap();
+        
+        assert_eq!(*result_event.body, json!({ "key": "PROJ-1" }));
+        assert_eq!(*result_event.raw_body, body);
+    }
+    
+    #[test]
+    fn test_null_as_keeps_null() {
+        let template = json!({
+            "asString": { "value": "{{ missing }}", "castTo": "string", "nullAs": "null" },
+            "asNumber": { "value": "{{ missing }}", "castTo": "number", "nullAs": "null" },
+            "asInteger": { "value": "{{ missing }}", "castTo": "integer", "nullAs": null },
+            "asFloat": { "value": "{{ missing }}", "castTo": "float", "nullAs": "null" }
+        });
+        
+        let body = MapperProcessor::new(template).unwrap().render(&json!({})).unwrap();
+        
+        assert_eq!(body, json!({ "asString": null, "asNumber": null, "asInteger": null, "asFloat": null }));
+    }
+    
+    #[test]
+    fn test_null_as_default_is_cast() {
+        let template = json!({
+            "asString": { "value": "{{ missing }}", "castTo": "string", "nullAs": "n/a" },
+            "asNumber": { "value": "{{ missing }}", "castTo": "number", "nullAs": 0 },
+            "asInteger": { "value": "{{ missing }}", "castTo": "integer", "nullAs": "-1" },
+            "asFloat": { "value": "{{ missing }}", "castTo": "float", "nullAs": 1 },
+            "present": { "value": "{{ a }}", "castTo": "number", "nullAs": 0 }
+        });
+        
+        let body = MapperProcessor::new(template).unwrap().render(&json!({ "a": "7" })).unwrap();
+        
+        assert_eq!(body["asString"], "n/a");
+        assert_eq!(body["asNumber"], 0);
+        assert_eq!(body["asInteger"], -1);
+        assert!(body["asFloat"].is_f64());
+        assert_eq!(body["present"], 7);
+    }
+    
+    #[test]
+    fn test_null_as_error_fails() {
+        for cast_to in ["string", "number", "integer", "float"] {
+            let template = json!({
+                "field": { "value": "{{ missing }}", "castTo": cast_to, "nullAs": "error" }
+            });
+            
+            let mapper = MapperProcessor::new(template).unwrap();
+            
+            assert!(mapper.render(&json!({})).is_err(), "{} should fail on null", cast_to);
+            assert!(!mapper.render(&json!({ "missing": "1" })).unwrap()["field"].is_null());
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_for_each_maps_array_elements() {
+        let template = json!({
+            "key": "{{ issue.key }}",
+            "components": {
+                "forEach": "{{ issue.fields.components }}",
+                "as": "component",
+                "template": {
+                    "id": { "value": "{{ component.id }}", "castTo": "integer" },
+                    "name": "{{ component.name }}",
+                    "issue": "{{ issue.key }}"
+                }
+            }
+        });
+        
+        let mapper = MapperProcessor::new(template).unwrap();
+        
+        let event = PipelineEvent::new(
+            json!({
+                "issue": {
+                    "key": "PROJ-1",
+                    "fields": {
+                        "components": [
+                            { "id": "10", "name": "Backend", "self": "https://example.atlassian.net/rest/api/2/component/10" },
+                            { "id": "11", "name": "Frontend", "self": "https://example.atlassian.net/rest/api/2/component/11" }
+                        ]
+                    }
+                }
+            }),
+            "test_event".to_string(),
+            vec![],
+            Operation::Write,
+        );
+        
+        let result_event = mapper.process(event).await.unwrap().unwrap();
+        
+        assert_eq!(*result_event.body, json!({
+            "key": "PROJ-1",
+            "components": [
+                { "id": 10, "name": "Backend", "issue": "PROJ-1" },
+                { "id": 11, "name": "Frontend", "issue": "PROJ-1" }
+            ]
+        }));
+    }
 }
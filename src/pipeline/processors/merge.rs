@@ -0,0 +1,215 @@
+use crate::error::Result;
+use crate::pipeline::event::PipelineEvent;
+use crate::sources::webhook::types::extract_value_by_path;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use super::{Processor, ProcessorOutput};
+
+/// Longest the pipeline waits between checks for windows that have closed
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+struct Pending {
+    deadline: Instant,
+    event: PipelineEvent,
+}
+
+#[derive(Default)]
+struct Windows {
+    open: HashMap<String, Pending>,
+    /// Events flushed early to make room for a new key, released on the next check
+    flushed: Vec<PipelineEvent>,
+}
+
+/// Processor that deep-merges the bodies of events sharing a key within a time window
+///
+/// The first event of a key opens the window and is held; later events with the same key are
+/// merged into it and filtered out. When the window closes, the merged event goes on with the id
+/// and type of the first one, and its write answers the acks of every event merged into it. At most `max_pending` windows are open at once: a new key past
+/// that closes the oldest window early.
+pub struct MergeProcessor {
+    key: String,
+    window: Duration,
+    max_pending: usize,
+    windows: Mutex<Windows>,
+}
+
+impl MergeProcessor {
+    pub fn new(key: String, window_ms: u64, max_pending: usize) -> Self {
+        Self {
+            key,
+            window: Duration::from_millis(window_ms),
+            max_pending: max_pending.max(1),
+            windows: Mutex::default(),
+        }
+    }
+
+    fn key_of(&self, event: &PipelineEvent) -> Option<String> {
+        match extract_value_by_path(&event.body, &self.key).ok()? {
+            Value::Null => None,
+            Value::String(key) => Some(key.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+}
+
+/// Merge `patch` into `target`: objects are merged key by key, anything else is replaced
+fn deep_merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for MergeProcessor {
+    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        match self.run(event).await? {
+            ProcessorOutput::Continue(event) => Ok(Some(*event)),
+            ProcessorOutput::Filtered | ProcessorOutput::Stopped | ProcessorOutput::Held | ProcessorOutput::Split(_) => Ok(None),
+        }
+    }
+
+    async fn run(&self, mut event: PipelineEvent) -> Result<ProcessorOutput> {
+        let Some(key) = self.key_of(&event) else {
+            tracing::debug!("Event {} has no merge key at {}, passing it on", event.id, self.key);
+            return Ok(ProcessorOutput::Continue(Box::new(event)));
+        };
+
+        let mut windows = self.windows.lock().unwrap();
+        if let Some(held) = windows.open.get_mut(&key) {
+            tracing::debug!("Merged event {} into event {} on key {}", event.id, held.event.id, key);
+            let merged_ack = event.ack.take();
+            match &held.event.ack {
+                Some(ack) => {
+                    if let Some(merged_ack) = merged_ack {
+                        ack.absorb(merged_ack);
+                    }
+                }
+                None => held.event.ack = merged_ack,
+            }
+            deep_merge(held.event.body_mut(), Arc::unwrap_or_clone(event.body));
+            return Ok(ProcessorOutput::Filtered);
+        }
+
+        if windows.open.len() >= self.max_pending {
+            let oldest = windows.open
+                .iter()
+                .min_by_key(|(_, held)| held.deadline)
+                .map(|(key, _)| key.clone());
+            if let Some(held) = oldest.and_then(|oldest| windows.open.remove(&oldest)) {
+                tracing::debug!("{} merge windows open, closing the one of event {} early", self.max_pending, held.event.id);
+                windows.flushed.push(held.event);
+            }
+        }
+
+        let deadline = Instant::now() + self.window;
+        windows.open.insert(key, Pending { deadline, event });
+        Ok(ProcessorOutput::Held)
+    }
+
+    fn due(&self, all: bool) -> Vec<PipelineEvent> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap();
+
+        let closed: Vec<String> = windows.open
+            .iter()
+            .filter(|(_, held)| all || held.deadline <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        let mut due: Vec<Pending> = closed.iter().filter_map(|key| windows.open.remove(key)).collect();
+        due.sort_by_key(|held| held.deadline);
+
+        let flushed = std::mem::take(&mut windows.flushed);
+        flushed.into_iter().chain(due.into_iter().map(|held| held.event)).collect()
+    }
+
+    fn poll_interval(&self) -> Option<Duration> {
+        Some(self.window.clamp(Duration::from_millis(1), MAX_POLL_INTERVAL))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::pipeline::event::Operation;
+
+    fn event(body: Value) -> PipelineEvent {
+        PipelineEvent::new(body, "test_event".to_string(), vec![], Operation::Write)
+    }
+
+    #[tokio::test]
+    async fn test_merges_events_sharing_a_key_after_the_window() {
+        let merge = MergeProcessor::new("issue.id".to_string(), 20, 100);
+
+        let first = event(json!({"issue": {"id": "1", "fields": {"summary": "Login fails"}}}));
+        let first_id = first.id.clone();
+        assert!(matches!(merge.run(first).await.unwrap(), ProcessorOutput::Held));
+        assert!(matches!(
+            merge.run(event(json!({"issue": {"id": "1", "fields": {"status": "Open"}}, "links": ["PROJ-2"]}))).await.unwrap(),
+            ProcessorOutput::Filtered
+        ));
+        assert!(merge.due(false).is_empty());
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+
+        let due = merge.due(false);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, first_id);
+        assert_eq!(*due[0].body, json!({
+            "issue": {"id": "1", "fields": {"summary": "Login fails", "status": "Open"}},
+            "links": ["PROJ-2"]
+        }));
+        assert!(merge.due(true).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_different_keys_are_held_separately() {
+        let merge = MergeProcessor::new("id".to_string(), 60_000, 100);
+
+        merge.run(event(json!({"id": 1, "a": 1}))).await.unwrap();
+        merge.run(event(json!({"id": 2, "b": 2}))).await.unwrap();
+
+        let mut bodies: Vec<Value> = merge.due(true).into_iter().map(|event| Arc::unwrap_or_clone(event.body)).collect();
+        bodies.sort_by_key(|body| body["id"].as_i64());
+        assert_eq!(bodies, vec![json!({"id": 1, "a": 1}), json!({"id": 2, "b": 2})]);
+    }
+
+    #[tokio::test]
+    async fn test_events_without_key_pass_through() {
+        let merge = MergeProcessor::new("issue.id".to_string(), 60_000, 100);
+
+        match merge.run(event(json!({"other": true}))).await.unwrap() {
+            ProcessorOutput::Continue(event) => assert_eq!(*event.body, json!({"other": true})),
+            _ => panic!("expected the event to continue"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_key_past_max_pending_flushes_oldest_window() {
+        let merge = MergeProcessor::new("id".to_string(), 60_000, 2);
+
+        merge.run(event(json!({"id": 1}))).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        merge.run(event(json!({"id": 2}))).await.unwrap();
+        merge.run(event(json!({"id": 3}))).await.unwrap();
+
+        // The window of key 1 closed early; the others stay open
+        let due = merge.due(false);
+        assert_eq!(due.len(), 1);
+        assert_eq!(*due[0].body, json!({"id": 1}));
+        assert_eq!(merge.due(true).len(), 2);
+    }
+}
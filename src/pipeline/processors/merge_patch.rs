@@ -0,0 +1,106 @@
+use crate::error::Result;
+use crate::pipeline::event::PipelineEvent;
+use super::Processor;
+use serde_json::{Map, Value};
+
+/// Processor that applies a JSON Merge Patch (RFC 7386) to the body
+///
+/// Unlike the mapper, which builds a new body, the patch only touches the keys it names: objects
+/// are patched key by key, `null` removes a key and any other value replaces it.
+pub struct MergePatchProcessor {
+    patch: Value,
+}
+
+impl MergePatchProcessor {
+    pub fn new(patch: Value) -> Self {
+        Self { patch }
+    }
+}
+
+/// Apply `patch` to `target` following RFC 7386
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!("target was just made an object");
+    };
+
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for MergePatchProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        merge_patch(event.body_mut(), &self.patch);
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use serde_json::json;
+    use crate::pipeline::event::Operation;
+
+    async fn patch(patch: Value, body: Value) -> Value {
+        let event = PipelineEvent::new(body, "test_event".to_string(), vec![], Operation::Write);
+        Arc::unwrap_or_clone(MergePatchProcessor::new(patch).process(event).await.unwrap().unwrap().body)
+    }
+
+    #[tokio::test]
+    async fn test_adds_and_overwrites_keys() {
+        let body = json!({"issue": {"key": "PROJ-1", "fields": {"status": "Open"}}, "labels": ["a", "b"]});
+
+        let patched = patch(json!({"issue": {"fields": {"status": "Done", "resolution": "Fixed"}}, "labels": ["c"], "source": "jira"}), body).await;
+
+        assert_eq!(patched, json!({
+            "issue": {"key": "PROJ-1", "fields": {"status": "Done", "resolution": "Fixed"}},
+            "labels": ["c"],
+            "source": "jira"
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_null_deletes_keys() {
+        let body = json!({"issue": {"key": "PROJ-1", "fields": {"status": "Open", "watches": 3}}, "user": {"name": "jdoe"}});
+
+        let patched = patch(json!({"issue": {"fields": {"watches": null}}, "user": null, "missing": null}), body).await;
+
+        assert_eq!(patched, json!({"issue": {"key": "PROJ-1", "fields": {"status": "Open"}}}));
+    }
+
+    #[test]
+    fn test_rfc_7386_examples() {
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "b"}), json!({"b": "c"}), json!({"a": "b", "b": "c"})),
+            (json!({"a": "b", "b": "c"}), json!({"a": null}), json!({"b": "c"})),
+            (json!({"a": [{"b": "c"}]}), json!({"a": [1]}), json!({"a": [1]})),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+            (json!({"e": null}), json!({"a": 1}), json!({"e": null, "a": 1})),
+            (json!([1, 2]), json!({"a": "b", "c": null}), json!({"a": "b"})),
+            (json!({}), json!({"a": {"bb": {"ccc": null}}}), json!({"a": {"bb": {}}})),
+        ];
+
+        for (target, patch, expected) in cases {
+            let mut patched = target.clone();
+            merge_patch(&mut patched, &patch);
+            assert_eq!(patched, expected, "{} patched with {}", target, patch);
+        }
+    }
+}
@@ -1,9 +1,67 @@
+pub mod adf_to_text;
+pub mod anonymize;
+pub mod assign_id;
+pub mod base64;
+pub mod canonicalize;
+pub mod changelog_split;
+pub mod compute;
+pub mod deadband;
+pub mod echo;
 pub mod filter;
+pub mod inject_primary_key;
+pub mod jira_custom_fields;
+pub mod jira_users;
 pub mod mapper;
+pub mod merge;
+pub mod merge_patch;
+pub mod on_error;
+pub mod parse_json;
+pub mod path;
+pub mod promote;
+pub mod require;
+pub mod sample;
+pub mod stop;
+pub mod to_boolean;
 
+use crate::config::secret::SecretSource;
 use crate::error::Result;
 use crate::pipeline::event::PipelineEvent;
+use assign_id::IdKind;
+use self::base64::InvalidBase64Policy;
+use filter::FilterErrorPolicy;
+use jira_custom_fields::JiraFieldApi;
+use on_error::ProcessorErrorPolicy;
+use parse_json::InvalidJsonPolicy;
+use require::MissingFieldPolicy;
+use to_boolean::UnrecognizedBooleanPolicy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A processor entry in a pipeline: the processor itself plus options common to every processor type
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PipelineProcessor {
+    #[serde(flatten)]
+    pub config: ProcessorConfig,
+    
+    /// Fail the event when the processor errors, or skip the processor and go on with the event
+    ///
+    /// Named apart from the filter's `onError`, which decides what an expression error means for
+    /// the filter rather than for the pipeline.
+    #[serde(rename = "onProcessorError")]
+    #[serde(default)]
+    pub on_error: ProcessorErrorPolicy,
+}
+
+impl From<ProcessorConfig> for PipelineProcessor {
+    fn from(config: ProcessorConfig) -> Self {
+        Self {
+            config,
+            on_error: ProcessorErrorPolicy::Fail,
+        }
+    }
+}
 
 /// Processor configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -12,13 +70,242 @@ pub enum ProcessorConfig {
     #[serde(rename = "filter")]
     Filter { 
         #[serde(rename = "celExpression")]
-        cel_expression: String 
+        cel_expression: String,
+        /// What happens to events the expression fails on
+        #[serde(rename = "onError")]
+        #[serde(default)]
+        on_error: FilterErrorPolicy,
     },
     #[serde(rename = "mapper")]
     Mapper { 
         #[serde(rename = "outputEvent")]
         output_event: serde_json::Value 
     },
+    #[serde(rename = "jiraCustomFields")]
+    JiraCustomFields {
+        #[serde(default)]
+        mapping: HashMap<String, String>,
+        #[serde(rename = "fieldApi")]
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        field_api: Option<JiraFieldApi>,
+    },
+    #[serde(rename = "parseJson")]
+    ParseJson {
+        paths: Vec<String>,
+        #[serde(rename = "onInvalid")]
+        #[serde(default)]
+        on_invalid: InvalidJsonPolicy,
+    },
+    #[serde(rename = "injectPrimaryKey")]
+    InjectPrimaryKey {
+        /// Dotted path the `{ id, fields }` object is written to
+        #[serde(default = "default_primary_key_field")]
+        key: String,
+    },
+    #[serde(rename = "sample")]
+    Sample {
+        /// Fraction of events passed on, between 0.0 and 1.0
+        rate: f64,
+        /// Decide from the event id, so the same event is always kept or always dropped
+        #[serde(default)]
+        deterministic: bool,
+        /// Seed for the random generator, for reproducible runs
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        seed: Option<u64>,
+    },
+    #[serde(rename = "assignId")]
+    AssignId {
+        /// Body path the generated id is written to
+        field: String,
+        kind: IdKind,
+    },
+    #[serde(rename = "jiraUsers")]
+    JiraUsers {
+        /// Body paths of the user objects to normalize
+        #[serde(default = "default_jira_user_paths")]
+        paths: Vec<String>,
+    },
+    #[serde(rename = "compute")]
+    Compute {
+        /// Body path to the CEL expression whose result is written there
+        fields: HashMap<String, String>,
+    },
+    #[serde(rename = "merge")]
+    Merge {
+        /// Body path of the key events are merged on
+        key: String,
+        /// How long the first event of a key waits for the others, in milliseconds
+        #[serde(rename = "windowMs")]
+        window_ms: u64,
+        /// Windows open at once; a new key past this closes the oldest early
+        #[serde(rename = "maxPending")]
+        #[serde(default = "default_merge_max_pending")]
+        max_pending: usize,
+    },
+    #[serde(rename = "echo")]
+    Echo {
+        /// Shown in the log line, to tell echo points apart
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+    },
+    #[serde(rename = "promote")]
+    Promote {
+        /// Body path of the value to copy
+        from: String,
+        /// Body path it is copied to, usually a top-level key
+        to: String,
+        /// Copy this element of the array at `from` instead of the whole array
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        index: Option<usize>,
+    },
+    #[serde(rename = "canonicalize")]
+    Canonicalize,
+    #[serde(rename = "mergePatch")]
+    MergePatch {
+        /// JSON Merge Patch (RFC 7386) applied to the body; `null` values remove keys
+        patch: serde_json::Value,
+    },
+    #[serde(rename = "deadband")]
+    Deadband {
+        /// Body paths compared with the last event of the same id; the event is dropped if none changed
+        paths: Vec<String>,
+        /// Event ids whose last values are kept; the oldest is forgotten first
+        #[serde(rename = "maxKeys")]
+        #[serde(default = "default_deadband_max_keys")]
+        max_keys: usize,
+    },
+    #[serde(rename = "require")]
+    Require {
+        /// Body paths that must hold a value other than null or an empty string, array or object
+        paths: Vec<String>,
+        #[serde(rename = "onMissing")]
+        #[serde(default)]
+        on_missing: MissingFieldPolicy,
+    },
+    #[serde(rename = "base64")]
+    Base64 {
+        /// Paths of base64 strings replaced with their decoded UTF-8 text
+        #[serde(default)]
+        decode: Vec<String>,
+        /// Paths of strings replaced with their base64 encoding, after decoding
+        #[serde(default)]
+        encode: Vec<String>,
+        #[serde(rename = "onInvalid")]
+        #[serde(default)]
+        on_invalid: InvalidBase64Policy,
+    },
+    #[serde(rename = "toBoolean")]
+    ToBoolean {
+        /// Body paths of strings and numbers replaced with the boolean they spell
+        paths: Vec<String>,
+        /// Words read as `true`, ignoring case
+        #[serde(default = "default_truthy")]
+        truthy: Vec<String>,
+        /// Words read as `false`, ignoring case
+        #[serde(default = "default_falsy")]
+        falsy: Vec<String>,
+        #[serde(rename = "onUnrecognized")]
+        #[serde(default)]
+        on_unrecognized: UnrecognizedBooleanPolicy,
+    },
+    #[serde(rename = "changelogSplit")]
+    ChangelogSplit,
+    #[serde(rename = "anonymize")]
+    Anonymize {
+        /// Body paths whose values are replaced with their salted HMAC-SHA256, in hex
+        paths: Vec<String>,
+        /// HMAC key; keep it secret, or digests of guessable ids can be reversed by brute force
+        salt: SecretSource,
+    },
+    #[serde(rename = "adfToText")]
+    AdfToText {
+        /// Body paths of Atlassian Document Format documents replaced with their plain text
+        paths: Vec<String>,
+    },
+    #[serde(rename = "stop")]
+    Stop {
+        /// CEL expression; when true the event skips the remaining processors and all sinks
+        when: String,
+    },
+}
+
+fn default_primary_key_field() -> String {
+    "_pk".to_string()
+}
+
+fn default_merge_max_pending() -> usize {
+    10_000
+}
+
+fn default_deadband_max_keys() -> usize {
+    10_000
+}
+
+fn default_jira_user_paths() -> Vec<String> {
+    vec!["issue.fields.assignee".to_string(), "issue.fields.reporter".to_string()]
+}
+
+fn default_truthy() -> Vec<String> {
+    to_boolean::DEFAULT_TRUTHY.iter().map(|w| w.to_string()).collect()
+}
+
+fn default_falsy() -> Vec<String> {
+    to_boolean::DEFAULT_FALSY.iter().map(|w| w.to_string()).collect()
+}
+
+impl ProcessorConfig {
+    /// The `type` tag of this processor, used in logs and error messages
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProcessorConfig::Filter { .. } => "filter",
+            ProcessorConfig::Mapper { .. } => "mapper",
+            ProcessorConfig::JiraCustomFields { .. } => "jiraCustomFields",
+            ProcessorConfig::ParseJson { .. } => "parseJson",
+            ProcessorConfig::InjectPrimaryKey { .. } => "injectPrimaryKey",
+            ProcessorConfig::Sample { .. } => "sample",
+            ProcessorConfig::AssignId { .. } => "assignId",
+            ProcessorConfig::JiraUsers { .. } => "jiraUsers",
+            ProcessorConfig::Compute { .. } => "compute",
+            ProcessorConfig::Merge { .. } => "merge",
+            ProcessorConfig::Echo { .. } => "echo",
+            ProcessorConfig::Promote { .. } => "promote",
+            ProcessorConfig::Canonicalize => "canonicalize",
+            ProcessorConfig::MergePatch { .. } => "mergePatch",
+            ProcessorConfig::Deadband { .. } => "deadband",
+            ProcessorConfig::Require { .. } => "require",
+            ProcessorConfig::Base64 { .. } => "base64",
+            ProcessorConfig::ToBoolean { .. } => "toBoolean",
+            ProcessorConfig::ChangelogSplit => "changelogSplit",
+            ProcessorConfig::Anonymize { .. } => "anonymize",
+            ProcessorConfig::AdfToText { .. } => "adfToText",
+            ProcessorConfig::Stop { .. } => "stop",
+        }
+    }
+
+    /// Whether the processor keeps state about the events it has seen, so each worker of a
+    /// pipeline needs its own instance rather than sharing one
+    pub fn per_worker(&self) -> bool {
+        matches!(self, ProcessorConfig::Merge { .. } | ProcessorConfig::Deadband { .. } | ProcessorConfig::Sample { .. })
+    }
+}
+
+/// What happens to an event after a processor has run
+pub enum ProcessorOutput {
+    /// Hand the event to the next processor, or to the sinks after the last one
+    Continue(Box<PipelineEvent>),
+    /// Dropped because it did not match
+    Filtered,
+    /// Halted on purpose: the remaining processors and all sinks are skipped
+    Stopped,
+    /// Kept by the processor, which hands it (or what it became) back later from `due`
+    Held,
+    /// Replaced by these events, each handed to the next processor in order; none is the same
+    /// as filtered
+    Split(Vec<PipelineEvent>),
 }
 
 /// Trait for event processors
@@ -26,4 +313,53 @@ pub enum ProcessorConfig {
 pub trait Processor: Send + Sync {
     /// Process an event, returning Some(event) if it should continue, None if filtered out
     async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>>;
+
+    /// Process an event, telling a deliberate stop apart from a filtered one
+    ///
+    /// Defaults to mapping `process`, where `None` means filtered.
+    async fn run(&self, event: PipelineEvent) -> Result<ProcessorOutput> {
+        Ok(match self.process(event).await? {
+            Some(event) => ProcessorOutput::Continue(Box::new(event)),
+            None => ProcessorOutput::Filtered,
+        })
+    }
+
+    /// Held events ready to go on to the next processor; every held event when `all` is set,
+    /// e.g. on shutdown
+    fn due(&self, _all: bool) -> Vec<PipelineEvent> {
+        Vec::new()
+    }
+
+    /// How often the pipeline checks `due`; `None` for processors that never hold events
+    fn poll_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Forget what was remembered of the event with `event_id`, which failed after passing the
+    /// processor, so its redelivery is processed as if it had never been seen
+    fn forget(&self, _event_id: &str) {}
+}
+
+/// A processor shared by the workers of a pipeline
+#[async_trait::async_trait]
+impl Processor for Arc<dyn Processor> {
+    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        (**self).process(event).await
+    }
+
+    async fn run(&self, event: PipelineEvent) -> Result<ProcessorOutput> {
+        (**self).run(event).await
+    }
+
+    fn due(&self, all: bool) -> Vec<PipelineEvent> {
+        (**self).due(all)
+    }
+
+    fn poll_interval(&self) -> Option<Duration> {
+        (**self).poll_interval()
+    }
+
+    fn forget(&self, event_id: &str) {
+        (**self).forget(event_id)
+    }
 }
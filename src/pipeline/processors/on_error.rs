@@ -0,0 +1,65 @@
+use crate::error::{AppError, Result};
+use crate::metrics;
+use crate::pipeline::event::PipelineEvent;
+use super::{Processor, ProcessorOutput};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// What a pipeline does with an event a processor fails on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessorErrorPolicy {
+    /// Fail the event
+    #[default]
+    Fail,
+    /// Pass the event on as the processor got it, as if the processor weren't there
+    Skip,
+}
+
+/// Runs a processor whose errors are logged and skipped instead of failing the event
+///
+/// The event is copied before each run, since the processor consumes it.
+pub struct SkipOnError {
+    inner: Box<dyn Processor>,
+    kind: &'static str,
+}
+
+impl SkipOnError {
+    pub fn new(inner: Box<dyn Processor>, kind: &'static str) -> Self {
+        Self { inner, kind }
+    }
+
+    /// The event as it was before the failed run, to go on with
+    fn skipped(&self, event: PipelineEvent, error: AppError) -> PipelineEvent {
+        tracing::warn!("Skipping {} processor that failed on event {}: {}", self.kind, event.id, error);
+        metrics::global().increment("connectcare_processor_errors_skipped_total", &[("processor", self.kind)]);
+        event
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for SkipOnError {
+    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let original = event.clone();
+        match self.inner.process(event).await {
+            Ok(processed) => Ok(processed),
+            Err(e) => Ok(Some(self.skipped(original, e))),
+        }
+    }
+
+    async fn run(&self, event: PipelineEvent) -> Result<ProcessorOutput> {
+        let original = event.clone();
+        match self.inner.run(event).await {
+            Ok(output) => Ok(output),
+            Err(e) => Ok(ProcessorOutput::Continue(Box::new(self.skipped(original, e)))),
+        }
+    }
+
+    fn due(&self, all: bool) -> Vec<PipelineEvent> {
+        self.inner.due(all)
+    }
+
+    fn poll_interval(&self) -> Option<Duration> {
+        self.inner.poll_interval()
+    }
+}
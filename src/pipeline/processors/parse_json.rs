@@ -0,0 +1,95 @@
+use crate::error::{AppError, Result};
+use crate::pipeline::event::PipelineEvent;
+use super::{path, Processor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// What to do with a string that is not valid JSON
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvalidJsonPolicy {
+    /// Leave the string as it is
+    #[default]
+    Skip,
+    /// Fail the event
+    Error,
+}
+
+/// Processor that replaces stringified JSON at the given paths with the parsed value
+pub struct ParseJsonProcessor {
+    paths: Vec<String>,
+    on_invalid: InvalidJsonPolicy,
+}
+
+impl ParseJsonProcessor {
+    pub fn new(paths: Vec<String>, on_invalid: InvalidJsonPolicy) -> Self {
+        Self { paths, on_invalid }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for ParseJsonProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        for field_path in &self.paths {
+            let Some(value) = path::get_mut(event.body_mut(), field_path) else {
+                continue;
+            };
+
+            let Value::String(raw) = value else {
+                continue;
+            };
+
+            match serde_json::from_str::<Value>(raw) {
+                Ok(parsed) => *value = parsed,
+                Err(e) => match self.on_invalid {
+                    InvalidJsonPolicy::Skip => {
+                        tracing::debug!("Leaving non-JSON string at {} untouched: {}", field_path, e);
+                    }
+                    InvalidJsonPolicy::Error => {
+                        return Err(AppError::Processing(format!("Field {} is not valid JSON: {}", field_path, e)));
+                    }
+                },
+            }
+        }
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::pipeline::event::Operation;
+
+    fn event(body: Value) -> PipelineEvent {
+        PipelineEvent::new(body, "test_event".to_string(), vec![], Operation::Write)
+    }
+
+    #[tokio::test]
+    async fn test_parses_nested_stringified_object() {
+        let processor = ParseJsonProcessor::new(
+            vec!["issue.fields.customfield_10001".to_string(), "issue.fields.summary".to_string()],
+            InvalidJsonPolicy::Skip,
+        );
+
+        let result = processor.process(event(json!({
+            "issue": {
+                "fields": {
+                    "customfield_10001": "{\"a\":1,\"b\":[true]}",
+                    "summary": "plain text"
+                }
+            }
+        }))).await.unwrap().unwrap();
+
+        assert_eq!(result.body["issue"]["fields"]["customfield_10001"], json!({"a": 1, "b": [true]}));
+        assert_eq!(result.body["issue"]["fields"]["summary"], "plain text");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_json_errors_under_error_policy() {
+        let processor = ParseJsonProcessor::new(vec!["payload".to_string()], InvalidJsonPolicy::Error);
+
+        assert!(processor.process(event(json!({"payload": "not json"}))).await.is_err());
+    }
+}
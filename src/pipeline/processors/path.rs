@@ -0,0 +1,97 @@
+use serde_json::{Map, Value};
+
+/// Value at a dotted path (`issue.fields.0.name`) or a JSON Pointer (`/issue/fields/0/name`)
+pub fn get<'a>(body: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.starts_with('/') {
+        return body.pointer(path);
+    }
+
+    let mut current = body;
+    for part in path.split('.') {
+        current = match current {
+            Value::Object(map) => map.get(part)?,
+            Value::Array(arr) => arr.get(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(current)
+}
+
+/// Mutable access to a dotted path (`issue.fields.0.name`) or a JSON Pointer (`/issue/fields/0/name`)
+pub fn get_mut<'a>(body: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    if path.starts_with('/') {
+        return body.pointer_mut(path);
+    }
+
+    let mut current = body;
+    for part in path.split('.') {
+        current = match current {
+            Value::Object(map) => map.get_mut(part)?,
+            Value::Array(arr) => arr.get_mut(part.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(current)
+}
+
+/// Write `value` at a dotted path or a JSON Pointer, creating intermediate objects as needed
+///
+/// Returns false when an intermediate segment exists but is not an object.
+pub fn set(body: &mut Value, path: &str, value: Value) -> bool {
+    let parts: Vec<String> = match path.strip_prefix('/') {
+        Some(pointer) => pointer
+            .split('/')
+            .map(|part| part.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+        None => path.split('.').map(str::to_string).collect(),
+    };
+    let (last, parents) = match parts.split_last() {
+        Some(split) => split,
+        None => return false,
+    };
+
+    let mut current = body;
+    for part in parents {
+        let Value::Object(map) = current else {
+            return false;
+        };
+        current = map
+            .entry(part.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+
+    match current {
+        Value::Object(map) => {
+            map.insert(last.clone(), value);
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_get_mut_and_set() {
+        let mut body = json!({"issue": {"labels": ["a", "b"]}});
+
+        *get_mut(&mut body, "issue.labels.1").unwrap() = json!("c");
+        *get_mut(&mut body, "/issue/labels/0").unwrap() = json!("z");
+        assert!(get_mut(&mut body, "issue.missing").is_none());
+
+        assert!(set(&mut body, "meta.source.name", json!("jira")));
+        assert!(!set(&mut body, "issue.labels.x", json!(1)));
+        assert!(set(&mut body, "/meta/source/a~1b", json!(1)));
+        assert!(!set(&mut body, "/issue/labels/x", json!(1)));
+
+        assert_eq!(body, json!({
+            "issue": {"labels": ["z", "c"]},
+            "meta": {"source": {"name": "jira", "a/b": 1}}
+        }));
+    }
+}
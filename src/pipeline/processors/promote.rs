@@ -0,0 +1,90 @@
+use crate::error::{AppError, Result};
+use crate::pipeline::event::PipelineEvent;
+use super::{path, Processor};
+
+/// Processor that copies a nested value to a key of the body, e.g. `issue.fields.fixVersions.0`
+/// to `version`
+///
+/// With `index`, the value at `from` must be an array and its element at that index is copied.
+/// Events without a value at `from` pass through unchanged.
+pub struct PromoteProcessor {
+    from: String,
+    to: String,
+    index: Option<usize>,
+}
+
+impl PromoteProcessor {
+    pub fn new(from: String, to: String, index: Option<usize>) -> Self {
+        Self { from, to, index }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for PromoteProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let value = match (path::get(&event.body, &self.from), self.index) {
+            (Some(value), None) => Some(value),
+            (Some(value), Some(index)) => value.as_array().and_then(|items| items.get(index)),
+            (None, _) => None,
+        };
+
+        let Some(value) = value.cloned() else {
+            tracing::debug!("Event {} has nothing to promote at {}", event.id, self.from);
+            return Ok(Some(event));
+        };
+
+        if !path::set(event.body_mut(), &self.to, value) {
+            return Err(AppError::Processing(format!(
+                "Cannot promote to '{}': parent is not an object",
+                self.to
+            )));
+        }
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use serde_json::{json, Value};
+    use crate::pipeline::event::Operation;
+
+    fn issue() -> Value {
+        json!({
+            "issue": {
+                "key": "PROJ-1",
+                "fields": {"fixVersions": [{"id": "10", "name": "1.0"}, {"id": "11", "name": "1.1"}]}
+            }
+        })
+    }
+
+    async fn promote(processor: PromoteProcessor, body: Value) -> Value {
+        let event = PipelineEvent::new(body, "test_event".to_string(), vec![], Operation::Write);
+        Arc::unwrap_or_clone(processor.process(event).await.unwrap().unwrap().body)
+    }
+
+    #[tokio::test]
+    async fn test_promotes_array_element_to_top_level() {
+        let processor = PromoteProcessor::new("issue.fields.fixVersions.0".to_string(), "version".to_string(), None);
+
+        let mut expected = issue();
+        expected["version"] = json!({"id": "10", "name": "1.0"});
+        assert_eq!(promote(processor, issue()).await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_index_picks_array_element() {
+        let processor = PromoteProcessor::new("issue.fields.fixVersions".to_string(), "version".to_string(), Some(1));
+
+        assert_eq!(promote(processor, issue()).await["version"], json!({"id": "11", "name": "1.1"}));
+    }
+
+    #[tokio::test]
+    async fn test_missing_value_leaves_body_intact() {
+        let processor = PromoteProcessor::new("issue.fields.fixVersions".to_string(), "version".to_string(), Some(5));
+
+        assert_eq!(promote(processor, issue()).await, issue());
+    }
+}
@@ -0,0 +1,107 @@
+use crate::error::{AppError, Result};
+use crate::pipeline::event::PipelineEvent;
+use super::{path, Processor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// What to do with an event missing a required field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissingFieldPolicy {
+    /// Fail the event
+    #[default]
+    Error,
+    /// Filter the event out
+    Drop,
+}
+
+/// Processor that checks body paths hold a value that is not null, an empty string, an empty
+/// array or an empty object
+pub struct RequireProcessor {
+    paths: Vec<String>,
+    on_missing: MissingFieldPolicy,
+}
+
+impl RequireProcessor {
+    pub fn new(paths: Vec<String>, on_missing: MissingFieldPolicy) -> Self {
+        Self { paths, on_missing }
+    }
+
+    fn is_empty(value: &Value) -> bool {
+        match value {
+            Value::Null => true,
+            Value::String(s) => s.is_empty(),
+            Value::Array(items) => items.is_empty(),
+            Value::Object(map) => map.is_empty(),
+            Value::Bool(_) | Value::Number(_) => false,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for RequireProcessor {
+    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let missing: Vec<&str> = self.paths
+            .iter()
+            .filter(|field_path| path::get(&event.body, field_path).is_none_or(Self::is_empty))
+            .map(String::as_str)
+            .collect();
+
+        if missing.is_empty() {
+            return Ok(Some(event));
+        }
+
+        match self.on_missing {
+            MissingFieldPolicy::Drop => {
+                tracing::debug!("Dropping event {} missing required fields {}", event.id, missing.join(", "));
+                Ok(None)
+            }
+            MissingFieldPolicy::Error => Err(AppError::Processing(format!(
+                "Required fields missing or empty: {}",
+                missing.join(", ")
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::pipeline::event::Operation;
+
+    fn event(body: Value) -> PipelineEvent {
+        PipelineEvent::new(body, "test_event".to_string(), vec![], Operation::Write)
+    }
+
+    fn require(on_missing: MissingFieldPolicy) -> RequireProcessor {
+        RequireProcessor::new(vec!["issue.key".to_string(), "/issue/fields/labels".to_string()], on_missing)
+    }
+
+    #[tokio::test]
+    async fn test_satisfied_requirements_pass() {
+        let body = json!({"issue": {"key": "PROJ-1", "fields": {"labels": ["backend"]}}});
+
+        let passed = require(MissingFieldPolicy::Error).process(event(body.clone())).await.unwrap();
+        assert_eq!(*passed.unwrap().body, body);
+    }
+
+    #[tokio::test]
+    async fn test_drop_policy_filters_event() {
+        let processor = require(MissingFieldPolicy::Drop);
+
+        let empty_labels = json!({"issue": {"key": "PROJ-1", "fields": {"labels": []}}});
+        assert!(processor.process(event(empty_labels)).await.unwrap().is_none());
+
+        let null_key = json!({"issue": {"key": null, "fields": {"labels": ["backend"]}}});
+        assert!(processor.process(event(null_key)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_error_policy_names_missing_fields() {
+        let body = json!({"issue": {"key": "", "fields": {}}});
+
+        let err = require(MissingFieldPolicy::Error).process(event(body)).await.unwrap_err();
+        assert!(err.to_string().contains("issue.key, /issue/fields/labels"), "unexpected error: {}", err);
+    }
+}
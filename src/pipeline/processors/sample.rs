@@ -0,0 +1,111 @@
+use crate::error::{AppError, Result};
+use crate::pipeline::event::PipelineEvent;
+use super::Processor;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Mutex;
+
+/// Sampling processor that passes a `rate` fraction of events and filters out the rest
+pub struct SampleProcessor {
+    rate: f64,
+    deterministic: bool,
+    rng: Mutex<StdRng>,
+}
+
+impl SampleProcessor {
+    pub fn new(rate: f64, deterministic: bool, seed: Option<u64>) -> Result<Self> {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(AppError::Config(format!("Sample rate must be between 0.0 and 1.0, got {}", rate)));
+        }
+        
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        
+        Ok(Self {
+            rate,
+            deterministic,
+            rng: Mutex::new(rng),
+        })
+    }
+    
+    /// Position of the event in [0, 1), derived from its id so the same event always lands in the same place
+    fn position_of(event: &PipelineEvent) -> f64 {
+        let prefix = event.id.get(..16).unwrap_or(&event.id);
+        let hash = u64::from_str_radix(prefix, 16).unwrap_or(0);
+        
+        (hash >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for SampleProcessor {
+    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        let position = if self.deterministic {
+            Self::position_of(&event)
+        } else {
+            self.rng.lock().unwrap().gen::<f64>()
+        };
+        
+        if position < self.rate {
+            Ok(Some(event))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::pipeline::event::{Operation, PkField};
+    
+    fn event(id: usize) -> PipelineEvent {
+        PipelineEvent::new(
+            json!({}),
+            "jira:issue_updated".to_string(),
+            vec![PkField { key: "issue.id".to_string(), value: id.to_string() }],
+            Operation::Write,
+        )
+    }
+    
+    async fn passed(processor: &SampleProcessor, count: usize) -> usize {
+        let mut passed = 0;
+        for id in 0..count {
+            if processor.process(event(id)).await.unwrap().is_some() {
+                passed += 1;
+            }
+        }
+        passed
+    }
+    
+    #[tokio::test]
+    async fn test_random_sampling_keeps_roughly_rate() {
+        let processor = SampleProcessor::new(0.3, false, Some(42)).unwrap();
+        
+        let passed = passed(&processor, 10_000).await;
+        
+        assert!((2_700..=3_300).contains(&passed), "passed {} of 10000", passed);
+    }
+    
+    #[tokio::test]
+    async fn test_deterministic_sampling_is_stable_per_event() {
+        let processor = SampleProcessor::new(0.5, true, None).unwrap();
+        
+        for id in 0..100 {
+            let first = processor.process(event(id)).await.unwrap().is_some();
+            let second = processor.process(event(id)).await.unwrap().is_some();
+            assert_eq!(first, second);
+        }
+        
+        let passed = passed(&processor, 10_000).await;
+        assert!((4_500..=5_500).contains(&passed), "passed {} of 10000", passed);
+    }
+    
+    #[test]
+    fn test_rate_out_of_range_is_rejected() {
+        assert!(SampleProcessor::new(1.5, false, None).is_err());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::error::Result;
+use crate::pipeline::event::PipelineEvent;
+use super::filter::FilterProcessor;
+use super::{Processor, ProcessorOutput};
+
+/// Processor that halts the pipeline for events matching a CEL expression
+///
+/// Unlike `filter`, which drops events that do *not* match, a stop is reported as its own
+/// outcome so it is logged and counted separately from filtered events.
+pub struct StopProcessor {
+    condition: FilterProcessor,
+}
+
+impl StopProcessor {
+    pub fn new(when: &str) -> Result<Self> {
+        Ok(Self { condition: FilterProcessor::new(when)? })
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for StopProcessor {
+    async fn process(&self, event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        match self.run(event).await? {
+            ProcessorOutput::Continue(event) => Ok(Some(*event)),
+            ProcessorOutput::Filtered | ProcessorOutput::Stopped | ProcessorOutput::Held | ProcessorOutput::Split(_) => Ok(None),
+        }
+    }
+
+    async fn run(&self, event: PipelineEvent) -> Result<ProcessorOutput> {
+        if self.condition.matches(&event)? {
+            Ok(ProcessorOutput::Stopped)
+        } else {
+            Ok(ProcessorOutput::Continue(Box::new(event)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::pipeline::event::Operation;
+
+    fn event(body: serde_json::Value) -> PipelineEvent {
+        PipelineEvent::new(body, "test_event".to_string(), vec![], Operation::Write)
+    }
+
+    #[tokio::test]
+    async fn test_stops_matching_event() {
+        let stop = StopProcessor::new("status == 'archived'").unwrap();
+
+        let result = stop.run(event(json!({"status": "archived"}))).await.unwrap();
+        assert!(matches!(result, ProcessorOutput::Stopped));
+    }
+
+    #[tokio::test]
+    async fn test_passes_other_events_through() {
+        let stop = StopProcessor::new("status == 'archived'").unwrap();
+
+        let result = stop.run(event(json!({"status": "open"}))).await.unwrap();
+        match result {
+            ProcessorOutput::Continue(event) => assert_eq!(event.body["status"], "open"),
+            _ => panic!("expected the event to continue"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_non_boolean_condition_is_an_error() {
+        let stop = StopProcessor::new("status").unwrap();
+
+        assert!(stop.run(event(json!({"status": "open"}))).await.is_err());
+    }
+}
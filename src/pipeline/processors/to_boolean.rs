@@ -0,0 +1,152 @@
+use crate::error::{AppError, Result};
+use crate::pipeline::event::PipelineEvent;
+use super::{path, Processor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Words read as `true` unless the processor configures its own
+pub const DEFAULT_TRUTHY: &[&str] = &["true", "yes", "1", "on"];
+
+/// Words read as `false` unless the processor configures its own
+pub const DEFAULT_FALSY: &[&str] = &["false", "no", "0", "off"];
+
+/// What to do with a value that is in neither vocabulary
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnrecognizedBooleanPolicy {
+    /// Leave the value as it is
+    #[default]
+    Skip,
+    /// Fail the event
+    Error,
+}
+
+/// Processor that turns strings and numbers at `paths` into JSON booleans
+///
+/// Strings are matched against the vocabularies ignoring case and surrounding whitespace, and
+/// numbers by their JSON text, so `1` matches `"1"`. Booleans, nulls and missing paths are left
+/// alone.
+pub struct ToBooleanProcessor {
+    paths: Vec<String>,
+    truthy: Vec<String>,
+    falsy: Vec<String>,
+    on_unrecognized: UnrecognizedBooleanPolicy,
+}
+
+impl ToBooleanProcessor {
+    pub fn new(paths: Vec<String>, truthy: Vec<String>, falsy: Vec<String>, on_unrecognized: UnrecognizedBooleanPolicy) -> Self {
+        let lowercase = |words: Vec<String>| words.into_iter().map(|w| w.trim().to_lowercase()).collect();
+        Self {
+            paths,
+            truthy: lowercase(truthy),
+            falsy: lowercase(falsy),
+            on_unrecognized,
+        }
+    }
+
+    fn coerce(&self, value: &Value) -> Option<bool> {
+        let word = match value {
+            Value::String(s) => s.trim().to_lowercase(),
+            Value::Number(n) => n.to_string(),
+            _ => return None,
+        };
+
+        if self.truthy.contains(&word) {
+            Some(true)
+        } else if self.falsy.contains(&word) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Processor for ToBooleanProcessor {
+    async fn process(&self, mut event: PipelineEvent) -> Result<Option<PipelineEvent>> {
+        for field_path in &self.paths {
+            let Some(value) = path::get_mut(event.body_mut(), field_path) else {
+                continue;
+            };
+            if matches!(value, Value::Bool(_) | Value::Null) {
+                continue;
+            }
+
+            match self.coerce(value) {
+                Some(flag) => *value = Value::Bool(flag),
+                None => match self.on_unrecognized {
+                    UnrecognizedBooleanPolicy::Skip => {
+                        tracing::debug!("Leaving unrecognized boolean at {}: {}", field_path, value);
+                    }
+                    UnrecognizedBooleanPolicy::Error => {
+                        return Err(AppError::Processing(format!("Field {} is not a recognized boolean: {}", field_path, value)));
+                    }
+                },
+            }
+        }
+
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use serde_json::json;
+    use crate::pipeline::event::Operation;
+
+    fn defaults(paths: &[&str], on_unrecognized: UnrecognizedBooleanPolicy) -> ToBooleanProcessor {
+        let words = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+        ToBooleanProcessor::new(words(paths), words(DEFAULT_TRUTHY), words(DEFAULT_FALSY), on_unrecognized)
+    }
+
+    async fn run(processor: ToBooleanProcessor, body: Value) -> Result<Value> {
+        let event = PipelineEvent::new(body, "test_event".to_string(), vec![], Operation::Write);
+        Ok(Arc::unwrap_or_clone(processor.process(event).await?.unwrap().body))
+    }
+
+    #[tokio::test]
+    async fn test_coerces_truthy_and_falsy_representations() {
+        let paths = ["a.yes", "a.on", "a.one", "a.true", "b.no", "b.off", "b.zero", "b.false", "b.bool", "c.items.0.flag", "missing.path"];
+        let body = json!({
+            "a": { "yes": "Yes", "on": " ON ", "one": 1, "true": "TRUE" },
+            "b": { "no": "no", "off": "Off", "zero": "0", "false": "False", "bool": false },
+            "c": { "items": [{ "flag": 0 }] }
+        });
+
+        let body = run(defaults(&paths, UnrecognizedBooleanPolicy::Error), body).await.unwrap();
+
+        assert_eq!(body, json!({
+            "a": { "yes": true, "on": true, "one": true, "true": true },
+            "b": { "no": false, "off": false, "zero": false, "false": false, "bool": false },
+            "c": { "items": [{ "flag": false }] }
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_unrecognized_values_follow_policy() {
+        let body = json!({ "fields": { "approved": "maybe", "count": 2 } });
+
+        let skipped = run(defaults(&["fields.approved", "fields.count"], UnrecognizedBooleanPolicy::Skip), body.clone()).await.unwrap();
+        assert_eq!(skipped, body);
+
+        assert!(run(defaults(&["fields.approved"], UnrecognizedBooleanPolicy::Error), body.clone()).await.is_err());
+        assert!(run(defaults(&["fields.count"], UnrecognizedBooleanPolicy::Error), body).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_vocabulary() {
+        let processor = ToBooleanProcessor::new(
+            vec!["issue.fields.flagged".to_string(), "issue.fields.resolved".to_string()],
+            vec!["Impediment".to_string()],
+            vec!["none".to_string()],
+            UnrecognizedBooleanPolicy::Error,
+        );
+        let body = json!({ "issue": { "fields": { "flagged": "impediment", "resolved": "None" } } });
+
+        let body = run(processor, body).await.unwrap();
+
+        assert_eq!(body["issue"]["fields"], json!({ "flagged": true, "resolved": false }));
+    }
+}
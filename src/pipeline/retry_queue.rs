@@ -0,0 +1,557 @@
+use crate::config::secret::SecretSource;
+use crate::error::{AppError, Result};
+use crate::metrics;
+use crate::pipeline::dead_letter::DeadLetterQueue;
+use crate::pipeline::event::{Operation, PipelineEvent, PkFields};
+use crate::pipeline::sinks::Sink;
+use crate::pipeline::sinks::database::DatabaseSink;
+use mongodb::bson::{self, doc, oid::ObjectId};
+use mongodb::options::ReturnDocument;
+use mongodb::{Client, Collection, IndexModel};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, warn};
+
+/// How long a claimed entry stays hidden from other workers while it is being retried
+const CLAIM_LEASE: Duration = Duration::from_secs(60);
+
+/// Where failed sink writes are persisted, and how they are retried
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RetryQueueConfig {
+    /// MongoDB URL including the database, e.g. `mongodb://localhost:27017/connectcare`
+    pub url: SecretSource,
+    #[serde(default = "default_collection")]
+    pub collection: String,
+    /// Attempts per write, the failed original included, before the event is dead-lettered
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// How often the worker looks for entries that are due
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+fn default_collection() -> String {
+    "connectcare_retry_queue".to_string()
+}
+
+fn default_max_attempts() -> u32 {
+    10
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    1_000
+}
+
+fn default_max_backoff_ms() -> u64 {
+    300_000
+}
+
+fn default_poll_interval_ms() -> u64 {
+    1_000
+}
+
+/// A failed sink write waiting for its next attempt, stored as one document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryEntry {
+    #[serde(rename = "_id")]
+    pub key: ObjectId,
+    /// Name of the pipeline, or its index if it has none
+    pub pipeline: String,
+    /// Name of the sink within the pipeline
+    pub sink: String,
+    /// Index of the pipeline when the write was queued, for metrics and dead letters
+    pub pipeline_index: u32,
+    pub event_id: String,
+    pub event_type: String,
+    pub pk_fields: PkFields,
+    /// Unix time in milliseconds the event was received at, so an older write never replaces a
+    /// newer one
+    pub received_at: u64,
+    pub operation: Operation,
+    pub insert_only: bool,
+    pub delivery_id: String,
+    pub body: serde_json::Value,
+    /// Body as the source created the event; absent from entries queued by older versions
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_body: Option<serde_json::Value>,
+    /// Attempts made so far, the original write included
+    pub attempts: u32,
+    pub next_attempt_at: bson::DateTime,
+    pub last_error: String,
+}
+
+impl RetryEntry {
+    fn event(&self) -> PipelineEvent {
+        let mut event = PipelineEvent::new(self.body.clone(), self.event_type.clone(), self.pk_fields.clone(), self.operation.clone())
+            .with_delivery_id(self.delivery_id.clone());
+        event.id = self.event_id.clone();
+        event.insert_only = self.insert_only;
+        event.received_at = self.received_at;
+        if let Some(raw_body) = &self.raw_body {
+            event.raw_body = Arc::new(raw_body.clone());
+        }
+        event
+    }
+}
+
+/// Persistence of the retry queue
+#[async_trait::async_trait]
+pub trait RetryStore: Send + Sync {
+    async fn push(&self, entry: RetryEntry) -> Result<()>;
+
+    /// Take the earliest entry due at `now`, hiding it from other workers until `lease_until`
+    async fn claim(&self, now: bson::DateTime, lease_until: bson::DateTime) -> Result<Option<RetryEntry>>;
+
+    /// Store the entry's new attempt count, next attempt time and error
+    async fn reschedule(&self, entry: &RetryEntry) -> Result<()>;
+
+    async fn remove(&self, entry: &RetryEntry) -> Result<()>;
+
+    /// Remove the entries of `event_id` for `sink` of `pipeline` received before `received_at`,
+    /// returning how many there were
+    async fn supersede(&self, pipeline: &str, sink: &str, event_id: &str, received_at: u64) -> Result<u64>;
+}
+
+/// Retry queue kept in a MongoDB collection, shared by every replica of the service
+pub struct MongoRetryStore {
+    collection: Collection<RetryEntry>,
+}
+
+impl MongoRetryStore {
+    pub async fn connect(mongo_url: &str, collection: &str) -> Result<Self> {
+        let (base_url, database) = DatabaseSink::split_database_url(mongo_url)?;
+        let client = Client::with_uri_str(&base_url)
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to connect to MongoDB: {}", e)))?;
+        let collection = client.database(&database).collection::<RetryEntry>(collection);
+
+        collection
+            .create_index(IndexModel::builder().keys(doc! { "next_attempt_at": 1 }).build())
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to index the retry queue: {}", e)))?;
+        collection
+            .create_index(IndexModel::builder().keys(doc! { "pipeline": 1, "sink": 1, "event_id": 1 }).build())
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to index the retry queue: {}", e)))?;
+
+        Ok(Self { collection })
+    }
+}
+
+#[async_trait::async_trait]
+impl RetryStore for MongoRetryStore {
+    async fn push(&self, entry: RetryEntry) -> Result<()> {
+        self.collection
+            .insert_one(entry)
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to queue retry in MongoDB: {}", e)))?;
+        Ok(())
+    }
+
+    async fn claim(&self, now: bson::DateTime, lease_until: bson::DateTime) -> Result<Option<RetryEntry>> {
+        self.collection
+            .find_one_and_update(
+                doc! { "next_attempt_at": { "$lte": now } },
+                doc! { "$set": { "next_attempt_at": lease_until } },
+            )
+            .sort(doc! { "next_attempt_at": 1 })
+            .return_document(ReturnDocument::Before)
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to claim retry from MongoDB: {}", e)))
+    }
+
+    async fn reschedule(&self, entry: &RetryEntry) -> Result<()> {
+        self.collection
+            .update_one(
+                doc! { "_id": entry.key },
+                doc! { "$set": {
+                    "attempts": entry.attempts,
+                    "next_attempt_at": entry.next_attempt_at,
+                    "last_error": &entry.last_error,
+                } },
+            )
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to reschedule retry in MongoDB: {}", e)))?;
+        Ok(())
+    }
+
+    async fn remove(&self, entry: &RetryEntry) -> Result<()> {
+        self.collection
+            .delete_one(doc! { "_id": entry.key })
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to remove retry from MongoDB: {}", e)))?;
+        Ok(())
+    }
+
+    async fn supersede(&self, pipeline: &str, sink: &str, event_id: &str, received_at: u64) -> Result<u64> {
+        let removed = self.collection
+            .delete_many(doc! {
+                "pipeline": pipeline,
+                "sink": sink,
+                "event_id": event_id,
+                "received_at": { "$lt": received_at as i64 },
+            })
+            .await
+            .map_err(|e| AppError::Database(format!("Failed to remove superseded retries from MongoDB: {}", e)))?;
+        Ok(removed.deleted_count)
+    }
+}
+
+/// A sink the worker can retry writes on
+pub struct RetryTarget {
+    pub sink: Arc<dyn Sink>,
+    pub timeout: Duration,
+}
+
+/// Retry targets by pipeline name (or index) and sink name
+pub type RetryTargets = HashMap<(String, String), RetryTarget>;
+
+/// Failed sink writes, retried with exponential backoff until they succeed or run out of attempts
+pub struct RetryQueue {
+    store: Arc<dyn RetryStore>,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    poll_interval: Duration,
+}
+
+impl RetryQueue {
+    pub async fn connect(config: &RetryQueueConfig) -> Result<Self> {
+        let store = MongoRetryStore::connect(&config.url.resolve()?, &config.collection).await?;
+        Ok(Self::with_store(Arc::new(store), config))
+    }
+
+    pub fn with_store(store: Arc<dyn RetryStore>, config: &RetryQueueConfig) -> Self {
+        Self {
+            store,
+            max_attempts: config.max_attempts.max(1),
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            max_backoff: Duration::from_millis(config.max_backoff_ms),
+            poll_interval: Duration::from_millis(config.poll_interval_ms),
+        }
+    }
+
+    /// Delay before the attempt following `attempts` failed ones
+    fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    fn at(delay: Duration) -> bson::DateTime {
+        let now = bson::DateTime::now().timestamp_millis();
+        bson::DateTime::from_millis(now.saturating_add(delay.as_millis() as i64))
+    }
+
+    /// Persist a write that just failed on sink `sink` of pipeline `pipeline`, which has index
+    /// `pipeline_index`
+    pub async fn enqueue(&self, pipeline: &str, pipeline_index: usize, sink: &str, event: &PipelineEvent, error: &str) -> Result<()> {
+        self.store.push(RetryEntry {
+            key: ObjectId::new(),
+            pipeline: pipeline.to_string(),
+            sink: sink.to_string(),
+            pipeline_index: pipeline_index as u32,
+            event_id: event.id.clone(),
+            event_type: event.event_type.clone(),
+            pk_fields: event.pk_fields.clone(),
+            received_at: event.received_at,
+            operation: event.operation.clone(),
+            insert_only: event.insert_only,
+            delivery_id: event.delivery_id.clone(),
+            body: (*event.body).clone(),
+            raw_body: Some((*event.raw_body).clone()),
+            attempts: 1,
+            next_attempt_at: Self::at(self.backoff(1)),
+            last_error: error.to_string(),
+        }).await?;
+
+        metrics::global().increment(
+            "connectcare_retry_enqueued_total",
+            &[("pipeline", &pipeline_index.to_string()), ("sink", sink)],
+        );
+        Ok(())
+    }
+
+    /// Note that `event` was written to sink `sink` of pipeline `pipeline`, dropping the queued
+    /// writes of its id received before it so a retry never replaces it with an older version
+    ///
+    /// The store is always asked, since the writes may have been queued by another replica or
+    /// before a restart.
+    pub async fn written(&self, pipeline: &str, sink: &str, event: &PipelineEvent) -> Result<()> {
+        let superseded = self.store.supersede(pipeline, sink, &event.id, event.received_at).await?;
+        if superseded > 0 {
+            debug!("Dropped {} queued writes of event {} on sink {} in pipeline {}, superseded", superseded, event.id, sink, pipeline);
+        }
+        Ok(())
+    }
+
+    /// Retry every entry that is due, returning how many were attempted
+    ///
+    /// A successful retry drops the entries of the same event and sink received before it, so
+    /// a write is never followed by an older one.
+    pub async fn retry_due(&self, sinks: &RetryTargets, dead_letter: Option<&DeadLetterQueue>) -> Result<usize> {
+        let mut attempted = 0;
+
+        while let Some(mut entry) = self.store.claim(bson::DateTime::now(), Self::at(CLAIM_LEASE)).await? {
+            attempted += 1;
+            let labels = [("pipeline", entry.pipeline_index.to_string()), ("sink", entry.sink.clone())];
+            let labels: Vec<(&str, &str)> = labels.iter().map(|(k, v)| (*k, v.as_str())).collect();
+
+            let result = match sinks.get(&(entry.pipeline.clone(), entry.sink.clone())) {
+                Some(target) => match tokio::time::timeout(target.timeout, target.sink.write(&entry.event())).await {
+                    Ok(result) => result.map(|_| ()),
+                    Err(_) => Err(AppError::Sink(format!("timed out after {:?}", target.timeout))),
+                },
+                // The configuration changed since the write was queued
+                None => Err(AppError::Config("sink is no longer configured".to_string())),
+            };
+
+            match result {
+                Ok(()) => {
+                    debug!("Retried event {} on sink {} in pipeline {}", entry.event_id, entry.sink, entry.pipeline);
+                    metrics::global().increment("connectcare_retry_succeeded_total", &labels);
+                    self.store.remove(&entry).await?;
+                    let superseded = self.store.supersede(&entry.pipeline, &entry.sink, &entry.event_id, entry.received_at).await?;
+                    if superseded > 0 {
+                        debug!("Dropped {} older queued writes of event {} on sink {} in pipeline {}", superseded, entry.event_id, entry.sink, entry.pipeline);
+                    }
+                }
+                Err(e) if entry.attempts + 1 >= self.max_attempts || matches!(e, AppError::Config(_)) => {
+                    error!("Giving up on event {} for sink {} in pipeline {}: {}", entry.event_id, entry.sink, entry.pipeline, e);
+                    metrics::global().increment("connectcare_retry_exhausted_total", &labels);
+                    if let Some(dead_letter) = dead_letter {
+                        let raw_body = Arc::new(entry.raw_body.clone().unwrap_or_else(|| entry.body.clone()));
+                        dead_letter.push(entry.pipeline_index as usize, entry.event_id.clone(), entry.event_type.clone(), entry.body.clone(), raw_body, e.to_string());
+                    }
+                    self.store.remove(&entry).await?;
+                }
+                Err(e) => {
+                    entry.attempts += 1;
+                    entry.next_attempt_at = Self::at(self.backoff(entry.attempts));
+                    entry.last_error = e.to_string();
+                    warn!("Retry {} of event {} failed on sink {} in pipeline {}: {}", entry.attempts, entry.event_id, entry.sink, entry.pipeline, e);
+                    self.store.reschedule(&entry).await?;
+                }
+            }
+        }
+
+        Ok(attempted)
+    }
+
+    /// Worker loop, retrying due entries every poll interval
+    pub async fn run(self: Arc<Self>, sinks: RetryTargets, dead_letter: Option<Arc<DeadLetterQueue>>) {
+        loop {
+            if let Err(e) = self.retry_due(&sinks, dead_letter.as_deref()).await {
+                error!("Retry queue worker failed: {}", e);
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::event::PkField;
+    use crate::pipeline::sinks::SinkOutcome;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// Store keeping entries in memory, in place of MongoDB
+    #[derive(Default)]
+    struct MemoryStore {
+        entries: Mutex<Vec<RetryEntry>>,
+    }
+
+    #[async_trait::async_trait]
+    impl RetryStore for MemoryStore {
+        async fn push(&self, entry: RetryEntry) -> Result<()> {
+            self.entries.lock().unwrap().push(entry);
+            Ok(())
+        }
+
+        async fn claim(&self, now: bson::DateTime, lease_until: bson::DateTime) -> Result<Option<RetryEntry>> {
+            let mut entries = self.entries.lock().unwrap();
+            let due = entries
+                .iter_mut()
+                .filter(|entry| entry.next_attempt_at <= now)
+                .min_by_key(|entry| entry.next_attempt_at);
+
+            Ok(due.map(|entry| {
+                let claimed = entry.clone();
+                entry.next_attempt_at = lease_until;
+                claimed
+            }))
+        }
+
+        async fn reschedule(&self, entry: &RetryEntry) -> Result<()> {
+            let mut entries = self.entries.lock().unwrap();
+            if let Some(stored) = entries.iter_mut().find(|stored| stored.key == entry.key) {
+                *stored = entry.clone();
+            }
+            Ok(())
+        }
+
+        async fn remove(&self, entry: &RetryEntry) -> Result<()> {
+            self.entries.lock().unwrap().retain(|stored| stored.key != entry.key);
+            Ok(())
+        }
+
+        async fn supersede(&self, pipeline: &str, sink: &str, event_id: &str, received_at: u64) -> Result<u64> {
+            let mut entries = self.entries.lock().unwrap();
+            let before = entries.len();
+            entries.retain(|stored| {
+                stored.pipeline != pipeline || stored.sink != sink || stored.event_id != event_id || stored.received_at >= received_at
+            });
+            Ok((before - entries.len()) as u64)
+        }
+    }
+
+    /// Sink failing its first `failures` writes
+    struct FlakySink {
+        failures: usize,
+        calls: AtomicUsize,
+        written: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Sink for FlakySink {
+        async fn write(&self, event: &PipelineEvent) -> Result<SinkOutcome> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(AppError::Sink("downstream unavailable".to_string()));
+            }
+            self.written.lock().unwrap().push(event.id.clone());
+            Ok(SinkOutcome::default())
+        }
+    }
+
+    fn config(max_attempts: u32) -> RetryQueueConfig {
+        RetryQueueConfig {
+            url: SecretSource::Plain("mongodb://localhost:27017/test".to_string()),
+            collection: default_collection(),
+            max_attempts,
+            initial_backoff_ms: 20,
+            max_backoff_ms: 1_000,
+            poll_interval_ms: 10,
+        }
+    }
+
+    fn event() -> PipelineEvent {
+        PipelineEvent::new(
+            json!({"id": "PROJ-1"}),
+            "jira:issue_created".to_string(),
+            vec![PkField { key: "id".to_string(), value: "PROJ-1".to_string() }],
+            Operation::Write,
+        )
+    }
+
+    fn targets(sink: Arc<dyn Sink>) -> RetryTargets {
+        HashMap::from([(("issues".to_string(), "mongo".to_string()), RetryTarget { sink, timeout: Duration::from_secs(1) })])
+    }
+
+    #[tokio::test]
+    async fn test_failed_write_succeeds_on_scheduled_retry() {
+        let store = Arc::new(MemoryStore::default());
+        let queue = RetryQueue::with_store(store.clone(), &config(5));
+        let sink = Arc::new(FlakySink { failures: 1, calls: AtomicUsize::new(0), written: Mutex::default() });
+        let sinks = targets(sink.clone());
+
+        let event = event();
+        queue.enqueue("issues", 0, "mongo", &event, "downstream unavailable").await.unwrap();
+
+        // Not due before the backoff has passed
+        assert_eq!(queue.retry_due(&sinks, None).await.unwrap(), 0);
+
+        // The first retry fails too and is rescheduled with a longer backoff
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(queue.retry_due(&sinks, None).await.unwrap(), 1);
+        assert_eq!(store.entries.lock().unwrap()[0].attempts, 2);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(queue.retry_due(&sinks, None).await.unwrap(), 1);
+
+        assert_eq!(*sink.written.lock().unwrap(), vec![event.id]);
+        assert!(store.entries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_retries_are_dead_lettered() {
+        let store = Arc::new(MemoryStore::default());
+        let queue = RetryQueue::with_store(store.clone(), &config(2));
+        let sink = Arc::new(FlakySink { failures: usize::MAX, calls: AtomicUsize::new(0), written: Mutex::default() });
+        let sinks = targets(sink);
+        let dead_letter = DeadLetterQueue::new(&crate::pipeline::dead_letter::DeadLetterConfig { max_entries: 10, max_age_secs: None });
+
+        queue.enqueue("issues", 0, "mongo", &event(), "downstream unavailable").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        queue.retry_due(&sinks, Some(&dead_letter)).await.unwrap();
+
+        assert!(store.entries.lock().unwrap().is_empty());
+        assert_eq!(dead_letter.entries()[0].error, "Sink error: downstream unavailable");
+    }
+
+    #[tokio::test]
+    async fn test_retry_keeps_primary_keys_and_never_replaces_newer_write() {
+        let store = Arc::new(MemoryStore::default());
+        let queue = RetryQueue::with_store(store.clone(), &config(5));
+
+        let older = event();
+        queue.enqueue("issues", 0, "mongo", &older, "downstream unavailable").await.unwrap();
+        assert_eq!(store.entries.lock().unwrap()[0].event().pk_fields[0].value, "PROJ-1");
+
+        // A newer version of the document was written since, so the queued write is dropped
+        let mut newer = event();
+        newer.received_at = older.received_at + 1;
+        queue.written("issues", "mongo", &newer).await.unwrap();
+        assert!(store.entries.lock().unwrap().is_empty());
+
+        // Of two queued writes, the one retried second is dropped if it is the older
+        queue.enqueue("issues", 0, "mongo", &newer, "downstream unavailable").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        queue.enqueue("issues", 0, "mongo", &older, "downstream unavailable").await.unwrap();
+        let sink = Arc::new(FlakySink { failures: 0, calls: AtomicUsize::new(0), written: Mutex::default() });
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(queue.retry_due(&targets(sink.clone()), None).await.unwrap(), 1);
+        assert_eq!(sink.calls.load(Ordering::SeqCst), 1);
+        assert!(store.entries.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_newer_write_supersedes_entries_queued_before_a_restart() {
+        let store = Arc::new(MemoryStore::default());
+        let older = event();
+        RetryQueue::with_store(store.clone(), &config(5))
+            .enqueue("issues", 0, "mongo", &older, "downstream unavailable")
+            .await
+            .unwrap();
+
+        // A fresh queue over the same store, as after a restart or on another replica
+        let queue = RetryQueue::with_store(store.clone(), &config(5));
+        let mut newer = event();
+        newer.received_at = older.received_at + 1;
+        queue.written("issues", "mongo", &newer).await.unwrap();
+        assert!(store.entries.lock().unwrap().is_empty());
+
+        // Nothing is left to write the older body over the newer one
+        let sink = Arc::new(FlakySink { failures: 0, calls: AtomicUsize::new(0), written: Mutex::default() });
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(queue.retry_due(&targets(sink.clone()), None).await.unwrap(), 0);
+        assert_eq!(sink.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_cap() {
+        let queue = RetryQueue::with_store(Arc::new(MemoryStore::default()), &config(10));
+
+        assert_eq!(queue.backoff(1), Duration::from_millis(20));
+        assert_eq!(queue.backoff(3), Duration::from_millis(80));
+        assert_eq!(queue.backoff(20), Duration::from_millis(1_000));
+    }
+}
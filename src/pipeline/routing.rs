@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use crate::config::Integration;
+use crate::error::{AppError, Result};
+
+/// Which of an integration's pipelines receive each event type
+///
+/// Pipelines are referred to by their index in the executor, which numbers the pipelines of
+/// all integrations consecutively.
+#[derive(Debug, Clone)]
+pub struct RoutingTable {
+    routes: HashMap<String, Vec<usize>>,
+    default: Vec<usize>,
+}
+
+impl RoutingTable {
+    /// Every event goes to every pipeline in `pipelines`
+    pub fn all(pipelines: Range<usize>) -> Self {
+        Self {
+            routes: HashMap::new(),
+            default: pipelines.collect(),
+        }
+    }
+
+    /// Resolve the integration's `routes` and `default_route` to pipeline indices, the
+    /// integration's first pipeline having index `first_pipeline`
+    pub fn build(integration: &Integration, first_pipeline: usize) -> Result<Self> {
+        if integration.routes.is_empty() && integration.default_route.is_none() {
+            return Ok(Self::all(first_pipeline..first_pipeline + integration.pipelines.len()));
+        }
+
+        let mut by_name = HashMap::new();
+        for (offset, pipeline) in integration.pipelines.iter().enumerate() {
+            let name = pipeline.name.as_deref().ok_or_else(|| AppError::Config(format!(
+                "Pipeline {} needs a name when its integration has routes",
+                first_pipeline + offset
+            )))?;
+
+            if by_name.insert(name, first_pipeline + offset).is_some() {
+                return Err(AppError::Config(format!("Duplicate pipeline name: {}", name)));
+            }
+        }
+
+        let resolve = |names: &[String]| -> Result<Vec<usize>> {
+            names
+                .iter()
+                .map(|name| by_name.get(name.as_str()).copied().ok_or_else(|| {
+                    AppError::Config(format!("Route references unknown pipeline: {}", name))
+                }))
+                .collect()
+        };
+
+        let routes = integration.routes
+            .iter()
+            .map(|(event_type, names)| Ok((event_type.clone(), resolve(names)?)))
+            .collect::<Result<_>>()?;
+        let default = resolve(integration.default_route.as_deref().unwrap_or_default())?;
+
+        Ok(Self { routes, default })
+    }
+
+    /// Pipelines an event of this type is dispatched to
+    pub fn targets(&self, event_type: &str) -> &[usize] {
+        self.routes.get(event_type).unwrap_or(&self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn integration(routing: serde_json::Value) -> Integration {
+        let mut integration = json!({
+            "source": {
+                "type": "jira",
+                "authentication": { "secret": "test_secret" }
+            },
+            "pipelines": [
+                { "name": "issues", "sinks": [] },
+                { "name": "versions", "sinks": [] },
+                { "name": "audit", "sinks": [] }
+            ]
+        });
+        integration.as_object_mut().unwrap().extend(routing.as_object().unwrap().clone());
+
+        serde_json::from_value(integration).unwrap()
+    }
+
+    #[test]
+    fn test_without_routes_every_pipeline_receives_every_event() {
+        let table = RoutingTable::build(&integration(json!({})), 2).unwrap();
+
+        assert_eq!(table.targets("jira:issue_created"), &[2, 3, 4]);
+    }
+
+    #[test]
+    fn test_routes_resolve_names_and_default() {
+        let table = RoutingTable::build(&integration(json!({
+            "routes": {
+                "jira:issue_created": ["issues", "audit"],
+                "jira:version_released": ["versions"]
+            },
+            "default_route": ["audit"]
+        })), 0).unwrap();
+
+        assert_eq!(table.targets("jira:issue_created"), &[0, 2]);
+        assert_eq!(table.targets("jira:version_released"), &[1]);
+        assert_eq!(table.targets("jira:sprint_started"), &[2]);
+    }
+
+    #[test]
+    fn test_unlisted_events_are_not_routed_without_default() {
+        let table = RoutingTable::build(&integration(json!({
+            "routes": { "jira:issue_created": ["issues"] }
+        })), 0).unwrap();
+
+        assert!(table.targets("jira:sprint_started").is_empty());
+    }
+
+    #[test]
+    fn test_unknown_pipeline_name_is_rejected() {
+        let error = RoutingTable::build(&integration(json!({
+            "routes": { "jira:issue_created": ["isues"] }
+        })), 0).unwrap_err();
+
+        assert!(error.to_string().contains("isues"));
+    }
+}
@@ -1,14 +1,22 @@
 use crate::error::{AppError, Result};
 use crate::pipeline::event::{PipelineEvent, Operation};
-use super::Sink;
-use mongodb::{Client, Collection, bson::{self, doc}};
+use super::{CollectionBy, MongoSinkOptions, NumberMode, Sink, SinkOperation, SinkOutcome};
+use crate::sources::webhook::types::{extract_value_by_path, HEADER_PK_PREFIX};
+use mongodb::{Client, Collection, IndexModel, bson::{self, doc}};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::{ClientOptions, Credential, IndexOptions, ServerAddress};
 use serde_json::Value;
 
+/// Fields maintained when the `timestamps` option is enabled
+const CREATED_AT: &str = "createdAt";
+const UPDATED_AT: &str = "updatedAt";
+
 pub struct DatabaseSink {
     client: Client,
     database: String,
     collection: String,
     insert_only: bool,
+    options: MongoSinkOptions,
 }
 
 impl DatabaseSink {
@@ -24,6 +32,7 @@ impl DatabaseSink {
             database,
             collection,
             insert_only,
+            options: MongoSinkOptions::default(),
         })
     }
     
@@ -37,9 +46,55 @@ impl DatabaseSink {
             database: database.to_string(),
             collection: collection.to_string(),
             insert_only,
+            options: MongoSinkOptions::default(),
         })
     }
     
+    /// Connect from separately configured parts so credentials never appear in a connection string
+    pub fn with_client_options(client_options: ClientOptions, database: &str, collection: &str, insert_only: bool) -> Result<Self> {
+        let client = Client::with_options(client_options)
+            .map_err(|e| AppError::Database(format!("Failed to create MongoDB client: {}", e)))?;
+        
+        Ok(Self {
+            client,
+            database: database.to_string(),
+            collection: collection.to_string(),
+            insert_only,
+            options: MongoSinkOptions::default(),
+        })
+    }
+    
+    pub fn client_options(host: &str, username: Option<String>, password: Option<String>) -> Result<ClientOptions> {
+        let hosts = host
+            .split(',')
+            .map(|h| ServerAddress::parse(h.trim()))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Config(format!("Invalid MongoDB host '{}': {}", host, e)))?;
+        
+        let credential = match (username, password) {
+            (None, None) => None,
+            (Some(username), password) => Some(
+                Credential::builder()
+                    .username(username)
+                    .password(password)
+                    .build()
+            ),
+            (None, Some(_)) => {
+                return Err(AppError::Config("MongoDB password configured without a username".to_string()));
+            }
+        };
+        
+        Ok(ClientOptions::builder()
+            .hosts(hosts)
+            .credential(credential)
+            .build())
+    }
+    
+    pub fn with_options(mut self, options: MongoSinkOptions) -> Self {
+        self.options = options;
+        self
+    }
+    
     fn parse_mongo_url(url: &str) -> Result<(String, String)> {
         let url_without_protocol = url.strip_prefix("mongodb://")
             .or_else(|| url.strip_prefix("mongodb+srv://"))
@@ -74,62 +129,324 @@ impl DatabaseSink {
         Ok((database, collection))
     }
     
-    fn get_collection(&self) -> Collection<bson::Document> {
+    /// Connection string up to its path, and the database the path names, for URLs configured
+    /// alongside a separate collection
+    pub(crate) fn split_database_url(url: &str) -> Result<(String, String)> {
+        let url_without_protocol = url.strip_prefix("mongodb://")
+            .or_else(|| url.strip_prefix("mongodb+srv://"))
+            .ok_or_else(|| AppError::Config(
+                "Invalid MongoDB URL: must start with mongodb:// or mongodb+srv://".to_string()
+            ))?;
+        
+        if let Some(slash_pos) = url_without_protocol.find('/') {
+            let base = &url[..url.len() - url_without_protocol.len() + slash_pos];
+            let path = &url_without_protocol[slash_pos + 1..];
+            
+            let database = path.split('?').next().unwrap_or(path).split('/').next().unwrap_or("");
+            
+            if database.is_empty() {
+                return Err(AppError::Config(
+                    "MongoDB URL must include database name (format: mongodb://host:port/database)".to_string()
+                ));
+            }
+            
+            Ok((base.to_string(), database.to_string()))
+        } else {
+            Err(AppError::Config(
+                "MongoDB URL must include database name (format: mongodb://host:port/database)".to_string()
+            ))
+        }
+    }
+    
+    /// Collection for this event: the `collections` entry matching it, or the default collection
+    fn get_collection(&self, event: &PipelineEvent) -> Collection<bson::Document> {
+        let key = match &self.options.collection_by {
+            CollectionBy::EventType => Some(event.event_type.as_str()),
+            CollectionBy::Path(path) => extract_value_by_path(&event.body, path)
+                .ok()
+                .and_then(Value::as_str),
+        };
+        
+        let collection = key
+            .and_then(|key| self.options.collections.get(key))
+            .unwrap_or(&self.collection);
+        
         self.client
             .database(&self.database)
-            .collection(&self.collection)
+            .collection(collection)
     }
     
     /// Convert serde_json::Value to bson::Document
     fn json_to_bson(&self, value: &Value) -> Result<bson::Document> {
-        let bson_value = bson::to_bson(value)
-            .map_err(|e| AppError::Processing(format!("Failed to convert JSON to BSON: {}", e)))?;
+        let bson_value = match self.options.number_mode {
+            NumberMode::Native => bson::to_bson(value)
+                .map_err(|e| AppError::Processing(format!("Failed to convert JSON to BSON: {}", e)))?,
+            mode => Self::convert_value(value, mode),
+        };
         
         match bson_value {
             bson::Bson::Document(doc) => Ok(doc),
             _ => Err(AppError::Processing("Expected JSON object for BSON conversion".to_string())),
         }
     }
+    
+    /// `bson::to_bson`, storing integers beyond int64 the way `mode` says instead of failing
+    fn convert_value(value: &Value, mode: NumberMode) -> bson::Bson {
+        match value {
+            Value::Null => bson::Bson::Null,
+            Value::Bool(b) => bson::Bson::Boolean(*b),
+            Value::String(s) => bson::Bson::String(s.clone()),
+            Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+                (Some(i), _) => bson::Bson::Int64(i),
+                (None, Some(u)) if mode == NumberMode::String => bson::Bson::String(u.to_string()),
+                (None, Some(u)) => match u.to_string().parse::<bson::Decimal128>() {
+                    Ok(decimal) => bson::Bson::Decimal128(decimal),
+                    Err(_) => bson::Bson::String(u.to_string()),
+                },
+                (None, None) => bson::Bson::Double(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::Array(items) => bson::Bson::Array(items.iter().map(|item| Self::convert_value(item, mode)).collect()),
+            Value::Object(map) => bson::Bson::Document(
+                map.iter().map(|(key, value)| (key.clone(), Self::convert_value(value, mode))).collect(),
+            ),
+        }
+    }
+    
+    /// Coerce a numeric `id` to a string when `string_id` is enabled
+    fn normalize_id(&self, document: &mut bson::Document) {
+        if !self.options.string_id {
+            return;
+        }
+        
+        let id = match document.get("id") {
+            Some(bson::Bson::Int32(n)) => n.to_string(),
+            Some(bson::Bson::Int64(n)) => n.to_string(),
+            Some(bson::Bson::Double(n)) => n.to_string(),
+            _ => return,
+        };
+        
+        document.insert("id", id);
+    }
+    
+    /// Value used to key the document: the body `id` if present, otherwise the event id
+    fn id_value(document: &bson::Document, event: &PipelineEvent) -> bson::Bson {
+        document.get("id")
+            .cloned()
+            .unwrap_or_else(|| bson::Bson::String(event.id.clone()))
+    }
+    
+    /// Filter matching the stored version of a document: its `id`, or with `filter_by_pk_fields`
+    /// every primary key field of the event
+    ///
+    /// Keys are used as dotted field paths, JSON Pointer keys converted to one. Values are read from
+    /// the document so they keep their type, falling back to the key's string value when the document
+    /// doesn't have the path. Keys read from a header name no field of the document and are refused.
+    fn key_filter(&self, document: &bson::Document, event: &PipelineEvent, id_value: &bson::Bson) -> Result<bson::Document> {
+        if !self.options.filter_by_pk_fields || event.pk_fields.is_empty() {
+            return Ok(doc! { "id": id_value.clone() });
+        }
+        
+        event.pk_fields
+            .iter()
+            .map(|pk| {
+                let field = Self::field_path(&pk.key)?;
+                let value = Self::value_at(document, &field)
+                    .cloned()
+                    .unwrap_or_else(|| bson::Bson::String(pk.value.clone()));
+                Ok((field, value))
+            })
+            .collect()
+    }
+    
+    /// Dotted field path of a primary key, which may be a dotted path or a JSON Pointer
+    fn field_path(key: &str) -> Result<String> {
+        if key.starts_with(HEADER_PK_PREFIX) {
+            return Err(AppError::Config(format!(
+                "filter_by_pk_fields needs primary keys read from the body, '{}' is read from a header",
+                key
+            )));
+        }
+        
+        match key.strip_prefix('/') {
+            Some(pointer) => Ok(pointer
+                .split('/')
+                .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+                .collect::<Vec<_>>()
+                .join(".")),
+            None => Ok(key.to_string()),
+        }
+    }
+    
+    fn value_at<'a>(document: &'a bson::Document, path: &str) -> Option<&'a bson::Bson> {
+        let (parents, last) = match path.rsplit_once('.') {
+            Some((parents, last)) => (Some(parents), last),
+            None => (None, path),
+        };
+        
+        let mut current = document;
+        for segment in parents.into_iter().flat_map(|parents| parents.split('.')) {
+            current = current.get_document(segment).ok()?;
+        }
+        current.get(last)
+    }
+    
+    /// JSON form of a document id reported back to sources; ObjectIds become their hex string
+    fn id_to_json(id: bson::Bson) -> Value {
+        match id {
+            bson::Bson::ObjectId(oid) => Value::String(oid.to_hex()),
+            other => other.into_relaxed_extjson(),
+        }
+    }
+    
+    /// Set `createdAt` and `updatedAt` on a document about to be inserted
+    fn stamp_insert(&self, document: &mut bson::Document, now: bson::DateTime) {
+        if self.options.timestamps {
+            document.insert(CREATED_AT, now);
+            document.insert(UPDATED_AT, now);
+        }
+    }
+    
+    /// Set `updatedAt` on a replacement, carrying `createdAt` over from the stored document
+    fn stamp_replace(&self, document: &mut bson::Document, existing: &bson::Document, now: bson::DateTime) {
+        if !self.options.timestamps {
+            return;
+        }
+        
+        match existing.get(CREATED_AT) {
+            Some(created_at) => document.insert(CREATED_AT, created_at.clone()),
+            None => document.remove(CREATED_AT),
+        };
+        document.insert(UPDATED_AT, now);
+    }
+    
+    /// Insert a document, keyed on its `id` when `insert_id_as_mongo_id` is set
+    async fn insert(&self, collection: &Collection<bson::Document>, mut document: bson::Document, id_value: bson::Bson) -> Result<SinkOutcome> {
+        if self.options.insert_id_as_mongo_id {
+            document.insert("_id", id_value.clone());
+        }
+        self.stamp_insert(&mut document, bson::DateTime::now());
+        
+        match collection.insert_one(document).await {
+            Ok(result) => Ok(Self::outcome(SinkOperation::Inserted, Some(result.inserted_id))),
+            Err(e) if is_duplicate_key(&e) => self.duplicate(id_value),
+            Err(e) => Err(AppError::Database(format!("Failed to insert to MongoDB: {}", e))),
+        }
+    }
+    
+    fn duplicate(&self, id_value: bson::Bson) -> Result<SinkOutcome> {
+        if !self.options.ignore_duplicate_inserts {
+            return Err(AppError::DuplicateKey(id_value.to_string()));
+        }
+        
+        tracing::debug!("Document {} already stored, skipping duplicate insert", id_value);
+        Ok(SinkOutcome {
+            operation: None,
+            id: Some(Self::id_to_json(id_value)),
+        })
+    }
+    
+    /// Collections written to and the `unique_index` to ensure in them, if any
+    fn unique_indexes(&self) -> Vec<(&str, IndexModel)> {
+        if self.options.unique_index.is_empty() {
+            return Vec::new();
+        }
+        
+        let keys: bson::Document = self.options.unique_index.iter().map(|field| (field.clone(), bson::Bson::Int32(1))).collect();
+        let mut collections: Vec<&str> = std::iter::once(self.collection.as_str())
+            .chain(self.options.collections.values().map(String::as_str))
+            .collect();
+        collections.sort_unstable();
+        collections.dedup();
+        
+        collections
+            .into_iter()
+            .map(|collection| {
+                let index = IndexModel::builder()
+                    .keys(keys.clone())
+                    .options(IndexOptions::builder().unique(true).build())
+                    .build();
+                (collection, index)
+            })
+            .collect()
+    }
+    
+    fn outcome(operation: SinkOperation, id: Option<bson::Bson>) -> SinkOutcome {
+        SinkOutcome {
+            operation: Some(operation),
+            id: id.map(Self::id_to_json),
+        }
+    }
+}
+
+/// MongoDB's `E11000 duplicate key error`
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    const DUPLICATE_KEY: i32 = 11000;
+    
+    matches!(error.kind.as_ref(), ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == DUPLICATE_KEY)
 }
 
 #[async_trait::async_trait]
 impl Sink for DatabaseSink {
-    async fn write(&self, event: &PipelineEvent) -> Result<()> {
-        let collection = self.get_collection();
+    async fn ping(&self) -> Result<()> {
+        // The driver only connects on the first operation
+        self.client
+            .database(&self.database)
+            .run_command(doc! { "ping": 1 })
+            .await
+            .map_err(|e| AppError::Database(format!("MongoDB ping failed: {}", e)))?;
+        Ok(())
+    }
+    
+    async fn prepare(&self) -> Result<()> {
+        // Creating an index that already exists with the same keys and options is a no-op
+        for (collection, index) in self.unique_indexes() {
+            self.client
+                .database(&self.database)
+                .collection::<bson::Document>(collection)
+                .create_index(index)
+                .await
+                .map_err(|e| AppError::Database(format!("Failed to create the unique index on {}: {}", collection, e)))?;
+        }
+        Ok(())
+    }
+    
+    async fn write(&self, event: &PipelineEvent) -> Result<SinkOutcome> {
+        let collection = self.get_collection(event);
         
-        match event.operation {
+        let outcome = match event.operation {
             Operation::Write => {
-                let document = self.json_to_bson(&event.body)?;
+                let mut document = self.json_to_bson(&event.body)?;
+                self.normalize_id(&mut document);
                 
-                if self.insert_only {
-                    collection
-                        .insert_one(document)
-                        .await
-                        .map_err(|e| AppError::Database(format!("Failed to insert to MongoDB: {}", e)))?;
+                if self.insert_only || event.insert_only {
+                    let id_value = Self::id_value(&document, event);
+                    self.insert(&collection, document, id_value).await?
                 } else {
-                    let id_value = document.get("id")
-                        .cloned()
-                        .unwrap_or_else(|| bson::Bson::String(event.id.clone()));
+                    let id_value = Self::id_value(&document, event);
                     
-                    let filter = doc! { "id": id_value.clone() };
+                    let filter = self.key_filter(&document, event, &id_value)?;
                     let existing = collection.find_one(filter.clone()).await
                         .map_err(|e| AppError::Database(format!("Failed to query MongoDB: {}", e)))?;
                     
                     if let Some(existing_doc) = existing {
 
                         let mut update_doc = document;
-                        if let Some(mongo_id) = existing_doc.get("_id") {
+                        let mongo_id = existing_doc.get("_id").cloned();
+                        if let Some(mongo_id) = &mongo_id {
                             update_doc.insert("_id", mongo_id.clone());
                         }
 
                         if !update_doc.contains_key("id") {
                             update_doc.insert("id", id_value.clone());
                         }
+                        self.stamp_replace(&mut update_doc, &existing_doc, bson::DateTime::now());
 
                         collection
                             .replace_one(filter, update_doc)
                             .await
                             .map_err(|e| AppError::Database(format!("Failed to update MongoDB: {}", e)))?;
+                        
+                        Self::outcome(SinkOperation::Updated, mongo_id)
                     } else {
                         let mut insert_doc = document;
 
@@ -137,26 +454,317 @@ impl Sink for DatabaseSink {
                             insert_doc.insert("id", id_value.clone());
                         }
 
-                        collection
-                            .insert_one(insert_doc)
-                            .await
-                            .map_err(|e| AppError::Database(format!("Failed to insert to MongoDB: {}", e)))?;
+                        self.insert(&collection, insert_doc, id_value).await?
                     }
                 }
             }
             Operation::Delete => {
-                let document = self.json_to_bson(&event.body)?;
-                let id_value = document.get("id")
-                    .cloned()
-                    .unwrap_or_else(|| bson::Bson::String(event.id.clone()));
+                let mut document = self.json_to_bson(&event.body)?;
+                self.normalize_id(&mut document);
+                let id_value = Self::id_value(&document, event);
+                let filter = self.key_filter(&document, event, &id_value)?;
                 
                 collection
-                    .delete_one(doc! { "id": id_value.clone() })
+                    .delete_one(filter)
                     .await
                     .map_err(|e| AppError::Database(format!("Failed to delete from MongoDB: {}", e)))?;
+                
+                Self::outcome(SinkOperation::Deleted, Some(id_value))
             }
+        };
+        
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::event::PkField;
+    use serde_json::json;
+    use std::collections::HashMap;
+    
+    async fn test_sink(options: MongoSinkOptions) -> DatabaseSink {
+        DatabaseSink::with_collection("mongodb://localhost:27017", "test", "events", false)
+            .await
+            .unwrap()
+            .with_options(options)
+    }
+    
+    fn key_for(sink: &DatabaseSink, body: serde_json::Value) -> bson::Bson {
+        let event = PipelineEvent::new(body, "test_event".to_string(), vec![], Operation::Write);
+        let mut document = sink.json_to_bson(&event.body).unwrap();
+        sink.normalize_id(&mut document);
+        DatabaseSink::id_value(&document, &event)
+    }
+    
+    #[tokio::test]
+    async fn test_string_id_collapses_numeric_and_string_ids() {
+        let sink = test_sink(MongoSinkOptions { string_id: true, ..Default::default() }).await;
+        
+        let from_string = key_for(&sink, json!({"id": "123"}));
+        let from_number = key_for(&sink, json!({"id": 123}));
+        
+        assert_eq!(from_string, bson::Bson::String("123".to_string()));
+        assert_eq!(from_string, from_number);
+    }
+    
+    #[tokio::test]
+    async fn test_ids_keep_their_type_by_default() {
+        let sink = test_sink(MongoSinkOptions::default()).await;
+        
+        assert_ne!(key_for(&sink, json!({"id": "123"})), key_for(&sink, json!({"id": 123})));
+    }
+    
+    #[tokio::test]
+    async fn test_64_bit_ids_round_trip_without_precision_loss() {
+        let sink = test_sink(MongoSinkOptions::default()).await;
+        
+        // Above 2^53, where a double would round it to 9007199254740992
+        let body = json!({"id": 9007199254740993_i64, "issue": {"id": -9007199254740993_i64}});
+        let document = sink.json_to_bson(&body).unwrap();
+        
+        assert_eq!(document.get("id"), Some(&bson::Bson::Int64(9007199254740993)));
+        assert_eq!(bson::Bson::Document(document).into_relaxed_extjson(), body);
+    }
+    
+    #[tokio::test]
+    async fn test_number_mode_stores_integers_beyond_int64() {
+        let body = json!({"id": u64::MAX, "ratio": 0.5, "count": 3});
+        
+        let native = test_sink(MongoSinkOptions::default()).await;
+        assert!(native.json_to_bson(&body).is_err());
+        
+        let decimal = test_sink(MongoSinkOptions { number_mode: NumberMode::Decimal128, ..Default::default() }).await;
+        let document = decimal.json_to_bson(&body).unwrap();
+        match document.get("id") {
+            Some(bson::Bson::Decimal128(id)) => assert_eq!(id.to_string(), u64::MAX.to_string()),
+            other => panic!("expected a Decimal128 id, got {:?}", other),
         }
+        assert_eq!(document.get("ratio"), Some(&bson::Bson::Double(0.5)));
+        assert_eq!(document.get("count"), Some(&bson::Bson::Int64(3)));
         
-        Ok(())
+        let string = test_sink(MongoSinkOptions { number_mode: NumberMode::String, ..Default::default() }).await;
+        let document = string.json_to_bson(&body).unwrap();
+        assert_eq!(document.get("id"), Some(&bson::Bson::String(u64::MAX.to_string())));
+    }
+    
+    fn write_error(code: i32) -> mongodb::error::Error {
+        let write_error = bson::from_document(doc! { "code": code, "errmsg": "E11000 duplicate key error" }).unwrap();
+        ErrorKind::Write(WriteFailure::WriteError(write_error)).into()
+    }
+    
+    #[tokio::test]
+    async fn test_composite_pk_filter_matches_on_every_field() {
+        let sink = test_sink(MongoSinkOptions { filter_by_pk_fields: true, ..Default::default() }).await;
+        
+        let filter_for = |source: i64, dest: i64, kind: &str| {
+            let event = PipelineEvent::new(
+                json!({"issueLink": {"sourceId": source, "destId": dest, "type": kind}}),
+                "jira:issuelink_created".to_string(),
+                vec![
+                    PkField { key: "issueLink.sourceId".to_string(), value: source.to_string() },
+                    PkField { key: "issueLink.destId".to_string(), value: dest.to_string() },
+                ],
+                Operation::Write,
+            );
+            let document = sink.json_to_bson(&event.body).unwrap();
+            let id_value = DatabaseSink::id_value(&document, &event);
+            sink.key_filter(&document, &event, &id_value).unwrap()
+        };
+        
+        let stored = filter_for(10, 20, "Blocks");
+        assert_eq!(stored, doc! { "issueLink.sourceId": 10_i64, "issueLink.destId": 20_i64 });
+        
+        // Same key with a new body replaces the stored document, another destination is a new one
+        assert_eq!(filter_for(10, 20, "Relates"), stored);
+        assert_ne!(filter_for(10, 21, "Blocks"), stored);
+    }
+    
+    #[tokio::test]
+    async fn test_pk_filter_fallbacks() {
+        let event = PipelineEvent::new(
+            json!({"id": "1", "key": "PROJ-1"}),
+            "jira:issue_created".to_string(),
+            vec![PkField { key: "issue.key".to_string(), value: "PROJ-1".to_string() }],
+            Operation::Write,
+        );
+        
+        let composite = test_sink(MongoSinkOptions { filter_by_pk_fields: true, ..Default::default() }).await;
+        let document = composite.json_to_bson(&event.body).unwrap();
+        let id_value = DatabaseSink::id_value(&document, &event);
+        
+        // The mapper dropped the path, so the key's string value is used
+        assert_eq!(composite.key_filter(&document, &event, &id_value).unwrap(), doc! { "issue.key": "PROJ-1" });
+        
+        let default = test_sink(MongoSinkOptions::default()).await;
+        assert_eq!(default.key_filter(&document, &event, &id_value).unwrap(), doc! { "id": "1" });
+    }
+    
+    #[tokio::test]
+    async fn test_pk_filter_keys_from_pointers_and_headers() {
+        let sink = test_sink(MongoSinkOptions { filter_by_pk_fields: true, ..Default::default() }).await;
+        let filter_for = |key: &str| {
+            let event = PipelineEvent::new(
+                json!({"issue": {"id": 10}}),
+                "jira:issue_created".to_string(),
+                vec![PkField { key: key.to_string(), value: "10".to_string() }],
+                Operation::Write,
+            );
+            let document = sink.json_to_bson(&event.body).unwrap();
+            let id_value = DatabaseSink::id_value(&document, &event);
+            sink.key_filter(&document, &event, &id_value)
+        };
+        
+        assert_eq!(filter_for("/issue/id").unwrap(), doc! { "issue.id": 10_i64 });
+        assert!(matches!(filter_for("header:X-Resource-Id"), Err(AppError::Config(_))));
+    }
+    
+    #[test]
+    fn test_duplicate_key_errors_are_recognized() {
+        assert!(is_duplicate_key(&write_error(11000)));
+        assert!(!is_duplicate_key(&write_error(121)));
+    }
+    
+    #[tokio::test]
+    async fn test_duplicate_insert_is_deduplicated() {
+        let strict = test_sink(MongoSinkOptions { insert_id_as_mongo_id: true, ..Default::default() }).await;
+        let lenient = test_sink(MongoSinkOptions {
+            insert_id_as_mongo_id: true,
+            ignore_duplicate_inserts: true,
+            ..Default::default()
+        }).await;
+        
+        // A redelivery keys the same `_id`, so the second insert is the duplicate
+        let id = key_for(&strict, json!({"id": "PROJ-1"}));
+        assert_eq!(id, key_for(&strict, json!({"id": "PROJ-1"})));
+        
+        assert!(matches!(strict.duplicate(id.clone()), Err(AppError::DuplicateKey(_))));
+        assert_eq!(lenient.duplicate(id).unwrap(), SinkOutcome { operation: None, id: Some(json!("PROJ-1")) });
+    }
+    
+    #[tokio::test]
+    async fn test_unique_index_dedupes_inserts_in_every_collection() {
+        let sink = test_sink(MongoSinkOptions {
+            unique_index: vec!["issue.id".to_string(), "version".to_string()],
+            ignore_duplicate_inserts: true,
+            collections: HashMap::from([
+                ("jira:issue_created".to_string(), "created".to_string()),
+                ("jira:issue_updated".to_string(), "events".to_string()),
+            ]),
+            ..Default::default()
+        }).await;
+        
+        let indexes = sink.unique_indexes();
+        let collections: Vec<&str> = indexes.iter().map(|(collection, _)| *collection).collect();
+        assert_eq!(collections, vec!["created", "events"]);
+        for (_, index) in &indexes {
+            assert_eq!(index.keys, doc! { "issue.id": 1, "version": 1 });
+            assert_eq!(index.options.as_ref().and_then(|options| options.unique), Some(true));
+        }
+        
+        // The index rejects the second copy with a duplicate key error, which is then a success
+        let id = key_for(&sink, json!({"id": "PROJ-1"}));
+        assert_eq!(sink.duplicate(id).unwrap(), SinkOutcome { operation: None, id: Some(json!("PROJ-1")) });
+        
+        assert!(test_sink(MongoSinkOptions::default()).await.unique_indexes().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_timestamps_keep_created_at_across_updates() {
+        let sink = test_sink(MongoSinkOptions { timestamps: true, ..Default::default() }).await;
+        let inserted_at = bson::DateTime::from_millis(1_700_000_000_000);
+        let updated_at = bson::DateTime::from_millis(1_700_000_060_000);
+        
+        let mut stored = doc! { "id": "1", "status": "Open" };
+        sink.stamp_insert(&mut stored, inserted_at);
+        assert_eq!(stored.get_datetime(CREATED_AT).unwrap(), &inserted_at);
+        assert_eq!(stored.get_datetime(UPDATED_AT).unwrap(), &inserted_at);
+        
+        // The replacement comes from the event body, which knows nothing of the stored timestamps
+        let mut replacement = doc! { "id": "1", "status": "Done" };
+        sink.stamp_replace(&mut replacement, &stored, updated_at);
+        assert_eq!(replacement.get_datetime(CREATED_AT).unwrap(), &inserted_at);
+        assert_eq!(replacement.get_datetime(UPDATED_AT).unwrap(), &updated_at);
+    }
+    
+    #[tokio::test]
+    async fn test_timestamps_are_off_by_default() {
+        let sink = test_sink(MongoSinkOptions::default()).await;
+        
+        let mut document = doc! { "id": "1" };
+        sink.stamp_insert(&mut document, bson::DateTime::now());
+        sink.stamp_replace(&mut document, &doc! {}, bson::DateTime::now());
+        
+        assert_eq!(document, doc! { "id": "1" });
+    }
+    
+    #[test]
+    fn test_outcome_ids_are_plain_json() {
+        let oid = bson::oid::ObjectId::new();
+        
+        assert_eq!(DatabaseSink::id_to_json(bson::Bson::ObjectId(oid)), json!(oid.to_hex()));
+        assert_eq!(DatabaseSink::id_to_json(bson::Bson::Int64(42)), json!(42));
+        assert_eq!(DatabaseSink::id_to_json(bson::Bson::String("PROJ-1".to_string())), json!("PROJ-1"));
+    }
+    
+    #[tokio::test]
+    async fn test_collection_by_event_type() {
+        let sink = test_sink(MongoSinkOptions {
+            collections: std::collections::HashMap::from([
+                ("jira:issue_created".to_string(), "issues".to_string()),
+                ("jira:sprint_started".to_string(), "sprints".to_string()),
+            ]),
+            ..Default::default()
+        }).await;
+        
+        let collection_for = |event_type: &str| {
+            let event = PipelineEvent::new(json!({}), event_type.to_string(), vec![], Operation::Write);
+            sink.get_collection(&event).name().to_string()
+        };
+        
+        assert_eq!(collection_for("jira:issue_created"), "issues");
+        assert_eq!(collection_for("jira:sprint_started"), "sprints");
+        assert_eq!(collection_for("jira:board_created"), "events");
+    }
+    
+    #[tokio::test]
+    async fn test_collection_by_body_path() {
+        let sink = test_sink(MongoSinkOptions {
+            collections: std::collections::HashMap::from([("PROJ".to_string(), "proj_issues".to_string())]),
+            collection_by: CollectionBy::Path("issue.fields.project.key".to_string()),
+            ..Default::default()
+        }).await;
+        
+        let event = PipelineEvent::new(
+            json!({"issue": {"fields": {"project": {"key": "PROJ"}}}}),
+            "jira:issue_created".to_string(),
+            vec![],
+            Operation::Write,
+        );
+        
+        assert_eq!(sink.get_collection(&event).name(), "proj_issues");
+    }
+    
+    #[tokio::test]
+    async fn test_client_options_from_components() {
+        let options = DatabaseSink::client_options(
+            "mongo-0:27017, mongo-1:27018",
+            Some("connectcare".to_string()),
+            Some("s3cret".to_string()),
+        ).unwrap();
+        
+        assert_eq!(options.hosts.len(), 2);
+        assert_eq!(options.hosts[1].to_string(), "mongo-1:27018");
+        
+        let credential = options.credential.as_ref().unwrap();
+        assert_eq!(credential.username.as_deref(), Some("connectcare"));
+        assert_eq!(credential.password.as_deref(), Some("s3cret"));
+        
+        assert!(DatabaseSink::client_options("mongo-0", None, Some("s3cret".to_string())).is_err());
+        assert!(DatabaseSink::client_options("mongo-0", None, None).unwrap().credential.is_none());
+        
+        let sink = DatabaseSink::with_client_options(options, "test", "events", false).unwrap();
+        assert_eq!(sink.database, "test");
     }
 }
@@ -1,23 +1,337 @@
 pub mod database;
+#[cfg(feature = "nats")]
+pub mod nats;
 
-use crate::error::Result;
-use crate::pipeline::event::PipelineEvent;
+use crate::error::{AppError, Result};
+use crate::pipeline::circuit_breaker::CircuitBreakerConfig;
+use crate::pipeline::event::{Operation, PipelineEvent};
 use crate::config::secret::SecretSource;
+use database::DatabaseSink;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
 
+/// A sink entry in a pipeline: the sink itself plus options common to every sink type
 #[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(tag = "type", rename_all = "lowercase")]
+pub struct PipelineSink {
+    #[serde(flatten)]
+    pub config: SinkConfig,
+    
+    /// Identifies the sink in metrics and queued retries; defaults to its `type`, numbered when
+    /// several sinks of the pipeline share it (`mongo-1`, `mongo-2`)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    
+    /// Upper bound for a single write; falls back to `SINK_TIMEOUT_MS`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    
+    /// Best-effort mirror: failures are logged as warnings and never fail the event
+    #[serde(default)]
+    pub tap: bool,
+    
+    /// Written before the other sinks; sources with `require_sink_ack` answer once one of these succeeds
+    #[serde(default)]
+    pub required: bool,
+    
+    /// Operations written to this sink, e.g. `["delete"]` for an audit of deletions; all if empty
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub operations: Vec<Operation>,
+    
+    /// Skip writes for a while after repeated failures instead of trying every event
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    
+    /// Write the event body, or the body wrapped with the event's metadata
+    #[serde(default)]
+    pub payload: SinkPayload,
+}
+
+/// What a sink is given to write
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkPayload {
+    /// The event body as the processors left it
+    #[default]
+    Body,
+    /// `PipelineEvent::envelope`: id, event type, operation, primary keys, delivery id,
+    /// reception time and the body
+    Envelope,
+}
+
+impl From<SinkConfig> for PipelineSink {
+    fn from(config: SinkConfig) -> Self {
+        Self {
+            config,
+            name: None,
+            timeout_ms: None,
+            tap: false,
+            required: false,
+            operations: vec![],
+            circuit_breaker: None,
+            payload: SinkPayload::Body,
+        }
+    }
+}
+
+impl PipelineSink {
+    /// Name of each of a pipeline's sinks, failing if two end up with the same one
+    pub fn names(sinks: &[PipelineSink]) -> Result<Vec<String>> {
+        let unnamed = |kind: &str| sinks.iter().filter(|sink| sink.name.is_none() && sink.config.kind() == kind).count();
+        
+        let mut names: Vec<String> = Vec::new();
+        for (idx, sink) in sinks.iter().enumerate() {
+            let kind = sink.config.kind();
+            let name = match &sink.name {
+                Some(name) => name.clone(),
+                None if unnamed(kind) > 1 => {
+                    let position = sinks[..=idx].iter().filter(|other| other.name.is_none() && other.config.kind() == kind).count();
+                    format!("{}-{}", kind, position)
+                }
+                None => kind.to_string(),
+            };
+            if names.contains(&name) {
+                return Err(AppError::Config(format!("two sinks of the pipeline are named {}", name)));
+            }
+            names.push(name);
+        }
+        
+        Ok(names)
+    }
+}
+
+/// Retries of sink connections at startup, for sinks that start alongside the service
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConnectRetry {
+    /// Total connection attempts per sink; `1` fails startup on the first error
+    #[serde(default = "default_connect_attempts")]
+    pub attempts: u32,
+    /// Wait after the first failure, doubled after each further one
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+}
+
+impl Default for ConnectRetry {
+    fn default() -> Self {
+        Self {
+            attempts: default_connect_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+        }
+    }
+}
+
+fn default_connect_attempts() -> u32 {
+    5
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_backoff_ms() -> u64 {
+    10_000
+}
+
+/// Sink of a pipeline, selected by its `type` tag
+///
+/// Types other than the built-in ones keep their settings as JSON, to be handled by a factory
+/// registered in the [`SinkRegistry`].
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "serde_json::Value", into = "serde_json::Value")]
 pub enum SinkConfig {
+    Builtin(Box<BuiltinSinkConfig>),
+    Custom {
+        sink_type: String,
+        /// The sink object without its `type`
+        settings: serde_json::Map<String, serde_json::Value>,
+    },
+}
+
+impl SinkConfig {
+    /// The `type` tag of this sink, used to look up its factory and in logs and error messages
+    pub fn kind(&self) -> &str {
+        match self {
+            SinkConfig::Builtin(config) => config.kind(),
+            SinkConfig::Custom { sink_type, .. } => sink_type,
+        }
+    }
+}
+
+impl From<BuiltinSinkConfig> for SinkConfig {
+    fn from(config: BuiltinSinkConfig) -> Self {
+        SinkConfig::Builtin(Box::new(config))
+    }
+}
+
+impl TryFrom<serde_json::Value> for SinkConfig {
+    type Error = AppError;
+    
+    fn try_from(value: serde_json::Value) -> Result<Self> {
+        let serde_json::Value::Object(mut settings) = value else {
+            return Err(AppError::Config("sink must be an object".to_string()));
+        };
+        
+        let sink_type = match settings.get("type") {
+            Some(serde_json::Value::String(sink_type)) => sink_type.clone(),
+            _ => return Err(AppError::Config("sink is missing its type".to_string())),
+        };
+        
+        match sink_type.as_str() {
+            "mongo" | "database" | "nats" => Ok(SinkConfig::Builtin(serde_json::from_value(settings.into())?)),
+            _ => {
+                settings.remove("type");
+                Ok(SinkConfig::Custom { sink_type, settings })
+            }
+        }
+    }
+}
+
+impl From<SinkConfig> for serde_json::Value {
+    fn from(config: SinkConfig) -> Self {
+        match config {
+            // Settings are plain structs with string keys, which always serialize
+            SinkConfig::Builtin(builtin) => serde_json::to_value(builtin).expect("builtin sink settings serialize to JSON"),
+            SinkConfig::Custom { sink_type, mut settings } => {
+                settings.insert("type".to_string(), serde_json::Value::String(sink_type));
+                serde_json::Value::Object(settings)
+            }
+        }
+    }
+}
+
+/// Settings of the sinks built into the service
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BuiltinSinkConfig {
     Mongo {
-        url: SecretSource,
+        /// Full connection string including the database; alternative to `host`
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        url: Option<SecretSource>,
+        /// `host:port`, comma separated for replica sets; used with `database` and the credentials below
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        host: Option<String>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        username: Option<SecretSource>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        password: Option<SecretSource>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        database: Option<String>,
         collection: String,
         #[serde(default)]
         insert_only: bool,
+        #[serde(flatten)]
+        options: Box<MongoSinkOptions>,
     },
     #[serde(rename = "database")]
     Database { 
         provider: DatabaseProvider 
     },
+    /// Publishes the event body to NATS; requires the `nats` feature
+    Nats {
+        servers: Vec<String>,
+        /// Handlebars template rendered against the event body, e.g. `jira.issue.{{ project.key }}`
+        subject: String,
+        /// Contents of a NATS `.creds` file
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        credentials: Option<SecretSource>,
+        /// Publish through JetStream and wait for the stream's ack
+        #[serde(default)]
+        jetstream: bool,
+    },
+}
+
+impl BuiltinSinkConfig {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BuiltinSinkConfig::Mongo { .. } => "mongo",
+            BuiltinSinkConfig::Database { .. } => "database",
+            BuiltinSinkConfig::Nats { .. } => "nats",
+        }
+    }
+}
+
+/// Optional behaviours of the Mongo sink
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MongoSinkOptions {
+    /// Store and match the `id` field as a string so `"123"` and `123` key the same document
+    #[serde(default)]
+    pub string_id: bool,
+    
+    /// Target collection per `collection_by` value; unmapped values go to `collection`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub collections: HashMap<String, String>,
+    
+    /// What the `collections` keys are matched against
+    #[serde(default)]
+    pub collection_by: CollectionBy,
+    
+    /// Use the document's `id` as its `_id` on inserts, so a redelivered event fails with a
+    /// duplicate key instead of being stored twice
+    #[serde(default)]
+    pub insert_id_as_mongo_id: bool,
+    
+    /// Count a duplicate-key insert as a successful write instead of a failure
+    #[serde(default)]
+    pub ignore_duplicate_inserts: bool,
+    
+    /// Maintain `createdAt` (set on insert, kept on replace) and `updatedAt` (set on every write)
+    #[serde(default)]
+    pub timestamps: bool,
+    
+    /// How integers too large for a BSON int64 are stored
+    #[serde(default)]
+    pub number_mode: NumberMode,
+    
+    /// Match stored documents on all the event's primary key fields instead of `id`, for
+    /// collections keyed on several fields
+    #[serde(default)]
+    pub filter_by_pk_fields: bool,
+    
+    /// Fields of a unique index ensured at startup in the default and the `collections`
+    /// collections, e.g. `["id"]`; with `ignore_duplicate_inserts` redelivered inserts are no-ops
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unique_index: Vec<String>,
+}
+
+/// Storage of JSON numbers in BSON
+///
+/// Integers that fit in 64 bits are always stored as int64 and other numbers as doubles; the
+/// modes only differ for unsigned integers above `i64::MAX`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberMode {
+    /// Fail the write
+    #[default]
+    Native,
+    /// Store them as Decimal128, which holds them exactly
+    Decimal128,
+    /// Store them as their decimal string
+    String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionBy {
+    /// The event type, e.g. `jira:issue_created`
+    #[default]
+    EventType,
+    /// The string value at a body path (dotted or JSON Pointer)
+    Path(String),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -26,7 +340,154 @@ pub enum DatabaseProvider {
     Mongo,
 }
 
+/// What a sink did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SinkOperation {
+    Inserted,
+    Updated,
+    Deleted,
+}
+
+/// Result of a successful write, returned to sources that wait for a required sink
+///
+/// Both fields are optional: sinks that can't tell what they wrote return the default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SinkOutcome {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<SinkOperation>,
+    /// Id of the written document in the sink, e.g. MongoDB's `_id`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<serde_json::Value>,
+}
+
 #[async_trait::async_trait]
 pub trait Sink: Send + Sync {
-    async fn write(&self, event: &PipelineEvent) -> Result<()>;
+    async fn write(&self, event: &PipelineEvent) -> Result<SinkOutcome>;
+    
+    /// Check that the sink's dependency answers, for sinks whose connections are made lazily
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+    
+    /// Set up what the sink needs before its first write, e.g. indexes; run at startup but not
+    /// by connectivity checks
+    async fn prepare(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Builds the sinks of one `type` tag from their configuration
+#[async_trait::async_trait]
+pub trait SinkFactory: Send + Sync {
+    /// Connect a sink; `AppError::Config` errors are not retried at startup
+    async fn connect(&self, config: &SinkConfig) -> Result<Arc<dyn Sink>>;
+}
+
+/// Sink factories by `type` tag
+///
+/// The default registry knows the built-in sinks; more can be added with `register` before
+/// building the executor.
+#[derive(Clone)]
+pub struct SinkRegistry {
+    factories: HashMap<String, Arc<dyn SinkFactory>>,
+}
+
+impl SinkRegistry {
+    /// A registry without any sink
+    pub fn empty() -> Self {
+        Self { factories: HashMap::new() }
+    }
+    
+    /// Build sinks with this `type` tag with `factory`, replacing any previous registration
+    pub fn register(&mut self, sink_type: impl Into<String>, factory: impl SinkFactory + 'static) -> &mut Self {
+        self.factories.insert(sink_type.into(), Arc::new(factory));
+        self
+    }
+    
+    pub async fn connect(&self, config: &SinkConfig) -> Result<Arc<dyn Sink>> {
+        let factory = self.factories
+            .get(config.kind())
+            .ok_or_else(|| AppError::Config(format!("Unknown sink type: {}", config.kind())))?;
+        
+        factory.connect(config).await
+    }
+}
+
+impl Default for SinkRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("mongo", BuiltinSinkFactory);
+        registry.register("database", BuiltinSinkFactory);
+        registry.register("nats", BuiltinSinkFactory);
+        registry
+    }
+}
+
+/// Factory of the sinks in `BuiltinSinkConfig`
+pub struct BuiltinSinkFactory;
+
+#[async_trait::async_trait]
+impl SinkFactory for BuiltinSinkFactory {
+    async fn connect(&self, config: &SinkConfig) -> Result<Arc<dyn Sink>> {
+        let SinkConfig::Builtin(config) = config else {
+            return Err(AppError::Config(format!("{} is not a built-in sink type", config.kind())));
+        };
+        
+        let sink: Arc<dyn Sink> = match config.as_ref() {
+            BuiltinSinkConfig::Mongo { url, host, username, password, database, collection, insert_only, options } => {
+                let sink = match (url, host) {
+                    (Some(url), None) => {
+                        let mongo_url = url.resolve()?;
+                        
+                        let (base_url, database) = DatabaseSink::split_database_url(&mongo_url)?;
+                        DatabaseSink::with_collection(&base_url, &database, collection, *insert_only).await?
+                    }
+                    (None, Some(host)) => {
+                        let database = database.as_deref().ok_or_else(|| AppError::Config(
+                            "Mongo sink configured with host requires a database".to_string()
+                        ))?;
+                        let username = username.as_ref().map(|s| s.resolve()).transpose()?;
+                        let password = password.as_ref().map(|s| s.resolve()).transpose()?;
+                        
+                        let client_options = DatabaseSink::client_options(host, username, password)?;
+                        DatabaseSink::with_client_options(client_options, database, collection, *insert_only)?
+                    }
+                    _ => {
+                        return Err(AppError::Config(
+                            "Mongo sink requires exactly one of url or host".to_string()
+                        ));
+                    }
+                };
+                
+                Arc::new(sink.with_options(options.as_ref().clone()))
+            }
+            BuiltinSinkConfig::Database { provider } => {
+                match provider {
+                    DatabaseProvider::Mongo => {
+                        let mongo_url = crate::config::AppConfig::mongodb_url()?;
+                        
+                        Arc::new(DatabaseSink::new(&mongo_url, false).await?)
+                    }
+                }
+            }
+            #[cfg(feature = "nats")]
+            BuiltinSinkConfig::Nats { servers, subject, credentials, jetstream } => {
+                Arc::new(nats::NatsSink::connect(
+                    servers,
+                    subject,
+                    credentials.as_ref(),
+                    *jetstream,
+                ).await?)
+            }
+            #[cfg(not(feature = "nats"))]
+            BuiltinSinkConfig::Nats { .. } => {
+                return Err(AppError::Config(
+                    "NATS sink requires building with the `nats` feature".to_string()
+                ));
+            }
+        };
+        
+        Ok(sink)
+    }
 }
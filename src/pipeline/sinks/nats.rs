@@ -0,0 +1,196 @@
+use crate::config::secret::SecretSource;
+use crate::error::{AppError, Result};
+use crate::pipeline::event::{Operation, PipelineEvent};
+use super::{Sink, SinkOutcome};
+use async_nats::HeaderMap;
+use bytes::Bytes;
+use handlebars::Handlebars;
+
+/// Destination for published messages, split out so the sink can be tested without a server
+#[async_trait::async_trait]
+pub trait NatsPublisher: Send + Sync {
+    async fn publish(&self, subject: String, headers: HeaderMap, payload: Bytes) -> Result<()>;
+}
+
+/// Core NATS publishing (fire-and-forget)
+pub struct CorePublisher {
+    client: async_nats::Client,
+}
+
+#[async_trait::async_trait]
+impl NatsPublisher for CorePublisher {
+    async fn publish(&self, subject: String, headers: HeaderMap, payload: Bytes) -> Result<()> {
+        self.client
+            .publish_with_headers(subject, headers, payload)
+            .await
+            .map_err(|e| AppError::Sink(format!("Failed to publish to NATS: {}", e)))
+    }
+}
+
+/// JetStream publishing, waiting for the stream to acknowledge persistence
+pub struct JetStreamPublisher {
+    context: async_nats::jetstream::Context,
+}
+
+#[async_trait::async_trait]
+impl NatsPublisher for JetStreamPublisher {
+    async fn publish(&self, subject: String, headers: HeaderMap, payload: Bytes) -> Result<()> {
+        self.context
+            .publish_with_headers(subject, headers, payload)
+            .await
+            .map_err(|e| AppError::Sink(format!("Failed to publish to JetStream: {}", e)))?
+            .await
+            .map_err(|e| AppError::Sink(format!("JetStream did not acknowledge message: {}", e)))?;
+
+        Ok(())
+    }
+}
+
+/// Sink that publishes the event body to a NATS subject rendered from a template
+pub struct NatsSink {
+    publisher: Box<dyn NatsPublisher>,
+    handlebars: Handlebars<'static>,
+    subject: String,
+}
+
+impl NatsSink {
+    pub async fn connect(
+        servers: &[String],
+        subject: &str,
+        credentials: Option<&SecretSource>,
+        jetstream: bool,
+    ) -> Result<Self> {
+        let mut options = async_nats::ConnectOptions::new();
+
+        if let Some(credentials) = credentials {
+            options = options
+                .credentials(&credentials.resolve()?)
+                .map_err(|e| AppError::Config(format!("Invalid NATS credentials: {}", e)))?;
+        }
+
+        let client = options
+            .connect(servers)
+            .await
+            .map_err(|e| AppError::Sink(format!("Failed to connect to NATS: {}", e)))?;
+
+        let publisher: Box<dyn NatsPublisher> = if jetstream {
+            Box::new(JetStreamPublisher { context: async_nats::jetstream::new(client) })
+        } else {
+            Box::new(CorePublisher { client })
+        };
+
+        Self::with_publisher(publisher, subject)
+    }
+
+    pub fn with_publisher(publisher: Box<dyn NatsPublisher>, subject: &str) -> Result<Self> {
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string("subject", subject)
+            .map_err(|e| AppError::Config(format!("Invalid NATS subject template: {}", e)))?;
+
+        Ok(Self {
+            publisher,
+            handlebars,
+            subject: subject.to_string(),
+        })
+    }
+
+    fn render_subject(&self, event: &PipelineEvent) -> Result<String> {
+        let subject = self.handlebars
+            .render("subject", &event.body)
+            .map_err(|e| AppError::Processing(format!("Failed to render NATS subject '{}': {}", self.subject, e)))?;
+
+        if subject.is_empty() || subject.contains(char::is_whitespace) || subject.split('.').any(str::is_empty) {
+            return Err(AppError::Processing(format!("Rendered NATS subject is invalid: '{}'", subject)));
+        }
+
+        Ok(subject)
+    }
+}
+
+#[async_trait::async_trait]
+impl Sink for NatsSink {
+    async fn write(&self, event: &PipelineEvent) -> Result<SinkOutcome> {
+        let subject = self.render_subject(event)?;
+        let payload = serde_json::to_vec(&event.body)?;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Event-Id", event.id.as_str());
+        headers.insert("Event-Type", event.event_type.as_str());
+        headers.insert("Operation", match event.operation {
+            Operation::Write => "write",
+            Operation::Delete => "delete",
+        });
+
+        self.publisher.publish(subject, headers, Bytes::from(payload)).await?;
+        
+        Ok(SinkOutcome::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    type Published = Arc<Mutex<Vec<(String, HeaderMap, Bytes)>>>;
+
+    struct MockPublisher {
+        published: Published,
+    }
+
+    #[async_trait::async_trait]
+    impl NatsPublisher for MockPublisher {
+        async fn publish(&self, subject: String, headers: HeaderMap, payload: Bytes) -> Result<()> {
+            self.published.lock().unwrap().push((subject, headers, payload));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_publishes_body_to_rendered_subject() {
+        let published = Published::default();
+        let sink = NatsSink::with_publisher(
+            Box::new(MockPublisher { published: published.clone() }),
+            "jira.issue.{{ project.key }}",
+        ).unwrap();
+
+        let event = PipelineEvent::new(
+            json!({"project": {"key": "PROJ"}, "summary": "Test"}),
+            "jira:issue_created".to_string(),
+            vec![],
+            Operation::Write,
+        );
+
+        sink.write(&event).await.unwrap();
+
+        let published = published.lock().unwrap();
+        assert_eq!(published.len(), 1);
+
+        let (subject, headers, payload) = &published[0];
+        assert_eq!(subject, "jira.issue.PROJ");
+        assert_eq!(headers.get("Event-Type").unwrap().as_str(), "jira:issue_created");
+        assert_eq!(headers.get("Operation").unwrap().as_str(), "write");
+
+        let body: serde_json::Value = serde_json::from_slice(payload).unwrap();
+        assert_eq!(body, *event.body);
+    }
+
+    #[tokio::test]
+    async fn test_missing_subject_field_is_rejected() {
+        let sink = NatsSink::with_publisher(
+            Box::new(MockPublisher { published: Published::default() }),
+            "jira.issue.{{ project.key }}",
+        ).unwrap();
+
+        let event = PipelineEvent::new(
+            json!({"summary": "no project"}),
+            "jira:issue_created".to_string(),
+            vec![],
+            Operation::Write,
+        );
+
+        assert!(sink.write(&event).await.is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::pipeline::PipelineSender;
+use crate::pipeline::event::PipelineEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Liveness settings of the `/-/healthz` route
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HealthConfig {
+    /// Report unhealthy once the executor hasn't sent a heartbeat, or a pipeline worker has been
+    /// on a single event, for this long
+    #[serde(default = "default_heartbeat_timeout_ms")]
+    pub heartbeat_timeout_ms: u64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_timeout_ms: default_heartbeat_timeout_ms(),
+        }
+    }
+}
+
+fn default_heartbeat_timeout_ms() -> u64 {
+    30_000
+}
+
+/// Executor counters shared with the `/-/status` route
+#[derive(Debug, Default)]
+pub struct PipelineStatus {
+    processed: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+    sink_errors: Mutex<BTreeMap<(usize, usize), u64>>,
+    /// Last time the executor's dispatch loop was seen running; `None` until it starts
+    last_heartbeat: Mutex<Option<Instant>>,
+    /// Pipeline of each worker and since when it has been on its current event; `None` while it
+    /// waits for one
+    workers: Mutex<Vec<(usize, Option<Instant>)>>,
+    /// Queues of each pipeline's workers, held weakly so they still close when the executor stops
+    pipeline_queues: Mutex<Vec<Vec<mpsc::WeakSender<PipelineEvent>>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BacklogReport {
+    /// Events queued between the sources and the executor
+    pub depth: usize,
+    pub capacity: usize,
+}
+
+/// Events dispatched to a pipeline that its workers haven't picked up yet
+#[derive(Debug, Clone, Serialize)]
+pub struct PipelineQueueReport {
+    pub pipeline: usize,
+    /// Summed over the pipeline's workers
+    pub depth: usize,
+    pub capacity: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SinkErrorReport {
+    pub pipeline: usize,
+    pub sink: usize,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusReport {
+    pub backlog: BacklogReport,
+    pub pipeline_queues: Vec<PipelineQueueReport>,
+    /// Pipeline runs that completed, including events filtered out
+    pub processed: u64,
+    pub failed: u64,
+    /// Events a pipeline never saw because its queue was full or its worker had stopped
+    pub dropped: u64,
+    pub sink_errors: Vec<SinkErrorReport>,
+}
+
+impl PipelineStatus {
+    pub fn record_processed(&self) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn record_failed(&self) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    pub fn record_sink_error(&self, pipeline_idx: usize, sink_idx: usize) {
+        *self.sink_errors.lock().unwrap().entry((pipeline_idx, sink_idx)).or_insert(0) += 1;
+    }
+    
+    pub fn record_heartbeat(&self) {
+        *self.last_heartbeat.lock().unwrap() = Some(Instant::now());
+    }
+    
+    /// Time since the executor's last heartbeat, `None` if it hasn't started yet
+    pub fn heartbeat_age(&self) -> Option<Duration> {
+        self.last_heartbeat.lock().unwrap().map(|at| at.elapsed())
+    }
+    
+    /// Track a new worker of a pipeline, returning the number it records its progress under
+    pub fn register_worker(&self, pipeline_idx: usize) -> usize {
+        let mut workers = self.workers.lock().unwrap();
+        workers.push((pipeline_idx, None));
+        workers.len() - 1
+    }
+    
+    pub fn record_worker_busy(&self, worker: usize) {
+        self.workers.lock().unwrap()[worker].1 = Some(Instant::now());
+    }
+    
+    pub fn record_worker_idle(&self, worker: usize) {
+        self.workers.lock().unwrap()[worker].1 = None;
+    }
+    
+    /// Pipeline of the worker that has been on a single event the longest, and for how long;
+    /// `None` when every worker is waiting for events
+    pub fn slowest_worker(&self) -> Option<(usize, Duration)> {
+        self.workers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|&(pipeline, since)| since.map(|since| (pipeline, since.elapsed())))
+            .max_by_key(|&(_, busy)| busy)
+    }
+    
+    /// Report the depth of these worker queues, one list per pipeline
+    pub fn watch_queues(&self, queues: &[Vec<mpsc::Sender<PipelineEvent>>]) {
+        *self.pipeline_queues.lock().unwrap() = queues
+            .iter()
+            .map(|workers| workers.iter().map(mpsc::Sender::downgrade).collect())
+            .collect();
+    }
+    
+    fn pipeline_queues(&self) -> Vec<PipelineQueueReport> {
+        self.pipeline_queues
+            .lock()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(pipeline, workers)| {
+                let mut report = PipelineQueueReport { pipeline, depth: 0, capacity: 0 };
+                for queue in workers.iter().filter_map(mpsc::WeakSender::upgrade) {
+                    report.depth += queue.max_capacity() - queue.capacity();
+                    report.capacity += queue.max_capacity();
+                }
+                report
+            })
+            .collect()
+    }
+    
+    pub fn report(&self, pipeline_tx: &PipelineSender) -> StatusReport {
+        let capacity = pipeline_tx.max_capacity();
+        
+        StatusReport {
+            backlog: BacklogReport {
+                depth: capacity - pipeline_tx.capacity(),
+                capacity,
+            },
+            pipeline_queues: self.pipeline_queues(),
+            processed: self.processed.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            sink_errors: self.sink_errors
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(&(pipeline, sink), &count)| SinkErrorReport { pipeline, sink, count })
+                .collect(),
+        }
+    }
+}
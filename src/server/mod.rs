@@ -1,23 +1,34 @@
 pub mod routes;
+pub mod tls;
 
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
 use crate::config::AppConfig;
 use crate::pipeline::PipelineSender;
+use crate::pipeline::status::PipelineStatus;
 use crate::error::Result;
 
-pub async fn run_server(config: AppConfig, pipeline_tx: PipelineSender) -> Result<()> {
-    let router = routes::create_router(config.clone(), pipeline_tx)?;
+pub async fn run_server(config: AppConfig, pipeline_tx: PipelineSender, status: Arc<PipelineStatus>) -> Result<()> {
+    let router = routes::create_router(config.clone(), pipeline_tx, status)?;
     
     let port = AppConfig::get_port();
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     let listener = TcpListener::bind(addr).await?;
     
+    if let Some(tls_config) = &config.tls {
+        let acceptor = tls::acceptor(tls_config)?;
+        let mode = if tls_config.client_ca_file.is_some() { "mutual TLS" } else { "TLS" };
+        tracing::info!("Server listening on {} ({})", addr, mode);
+        
+        return tls::serve(listener, router, acceptor, tls_config).await;
+    }
+    
     tracing::info!("Server listening on {}", addr);
     
     axum::serve(listener, router)
         .await
-        .map_err(|e| crate::error::AppError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+        .map_err(|e| crate::error::AppError::Io(std::io::Error::other(e)))?;
     
     Ok(())
 }
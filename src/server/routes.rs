@@ -1,27 +1,299 @@
-use axum::{Router, routing::get, http::StatusCode};
+use axum::{Router, routing::get, http::{HeaderMap, StatusCode}, extract::{Query, State}, Json};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
 use crate::config::AppConfig;
-use crate::config::SourceConfig;
 use crate::pipeline::PipelineSender;
-use crate::sources::jira;
-use crate::error::Result;
+use crate::pipeline::in_flight::InFlightLimit;
+use crate::pipeline::status::{PipelineStatus, StatusReport};
+use crate::sources::{EventRoute, SourceContext, SourceRegistry};
+use crate::debug::{self, FailureRecord};
+use crate::error::{AppError, Result};
+use tokio::sync::broadcast::error::RecvError;
 
-async fn health_check() -> StatusCode {
-    StatusCode::OK
+/// How often gauges otherwise refreshed on scrape are pushed when metrics go to StatsD
+const GAUGE_PUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Operational routes, which no webhook path may take; the debug ones are reserved even when off
+const OPS_PATHS: [&str; 7] = ["/-/healthz", "/-/ready", "/-/metrics", "/-/status", "/-/debug/failures", "/-/events/stream", "/-/routes"];
+
+/// Shared state of the operational routes
+#[derive(Clone)]
+struct OpsState {
+    pipeline_tx: PipelineSender,
+    status: Arc<PipelineStatus>,
+    heartbeat_timeout: Duration,
+}
+
+impl OpsState {
+    fn report(&self) -> StatusReport {
+        self.executor_alive();
+        let report = self.status.report(&self.pipeline_tx);
+        crate::metrics::global().set_gauge("connectcare_pipeline_backlog", &[], report.backlog.depth as i64);
+        for queue in &report.pipeline_queues {
+            let pipeline = queue.pipeline.to_string();
+            crate::metrics::global().set_gauge("connectcare_pipeline_queue_depth", &[("pipeline", &pipeline)], queue.depth as i64);
+        }
+        report
+    }
+    
+    /// The executor owns the receiving end, so a closed channel means its task has ended or panicked
+    fn executor_alive(&self) -> bool {
+        let alive = !self.pipeline_tx.is_closed();
+        crate::metrics::global().set_gauge("connectcare_executor_alive", &[], alive as i64);
+        alive
+    }
+    
+    /// Alive, its dispatch loop has heartbeated recently and no pipeline worker is stuck on an
+    /// event; an executor that hasn't started yet counts as live
+    fn executor_live(&self) -> bool {
+        let stale = self.status
+            .heartbeat_age()
+            .filter(|age| *age > self.heartbeat_timeout);
+        if let Some(age) = stale {
+            tracing::warn!("Pipeline executor hasn't sent a heartbeat for {:?}", age);
+        }
+        
+        let stuck = self.status
+            .slowest_worker()
+            .filter(|(_, busy)| *busy > self.heartbeat_timeout);
+        if let Some((pipeline, busy)) = stuck {
+            tracing::warn!("A worker of pipeline {} has been on the same event for {:?}", pipeline, busy);
+        }
+        
+        self.executor_alive() && stale.is_none() && stuck.is_none()
+    }
+}
+
+/// Refresh the executor gauges every push interval, since nothing scrapes `/-/metrics` to do it
+///
+/// The pipeline channel is held weakly so the executor still stops once the server is gone.
+fn push_gauges(state: OpsState) {
+    let pipeline_tx = state.pipeline_tx.downgrade();
+    let OpsState { status, heartbeat_timeout, .. } = state;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(GAUGE_PUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(pipeline_tx) = pipeline_tx.upgrade() else {
+                break;
+            };
+            OpsState { pipeline_tx, status: status.clone(), heartbeat_timeout }.report();
+        }
+    });
+}
+
+async fn health_check(State(state): State<OpsState>) -> StatusCode {
+    if state.executor_live() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+async fn readiness_check(State(state): State<OpsState>) -> StatusCode {
+    if state.executor_alive() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+async fn metrics_handler(State(state): State<OpsState>) -> String {
+    // Refresh the backlog gauge on scrape
+    state.report();
+    crate::metrics::global().render()
 }
 
-pub fn create_router(config: AppConfig, pipeline_tx: PipelineSender) -> Result<Router> {
+async fn status_handler(State(state): State<OpsState>) -> Json<StatusReport> {
+    Json(state.report())
+}
+
+/// Prefix a webhook path with the base path, tolerating missing or doubled slashes
+pub fn join_path(base_path: &str, path: &str) -> String {
+    let base = base_path.trim_matches('/');
+    let path = path.trim_start_matches('/');
+    
+    if base.is_empty() {
+        format!("/{}", path)
+    } else {
+        format!("/{}/{}", base, path)
+    }
+}
+
+/// Webhook routes of one integration, as listed by `/-/routes`
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceRoutes {
+    pub source_type: String,
+    pub paths: Vec<String>,
+    pub events: Vec<EventRoute>,
+}
+
+/// Shared state of the endpoints behind the debug token
+#[derive(Clone)]
+struct DebugState {
+    token: Arc<String>,
+    routes: Arc<Vec<SourceRoutes>>,
+}
+
+impl DebugState {
+    fn authorize(&self, headers: &HeaderMap) -> std::result::Result<(), StatusCode> {
+        let provided = headers
+            .get("X-Debug-Token")
+            .map(|value| value.as_bytes())
+            .unwrap_or_default();
+        
+        if !bool::from(provided.ct_eq(self.token.as_bytes())) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        
+        Ok(())
+    }
+}
+
+async fn debug_failures_handler(
+    State(state): State<DebugState>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<FailureRecord>>, StatusCode> {
+    state.authorize(&headers)?;
+    
+    Ok(Json(debug::failures().records()))
+}
+
+#[derive(Deserialize)]
+struct EventStreamQuery {
+    /// Only stream events of this type
+    event_type: Option<String>,
+}
+
+async fn events_stream_handler(
+    State(state): State<DebugState>,
+    headers: HeaderMap,
+    Query(query): Query<EventStreamQuery>,
+) -> std::result::Result<Sse<impl Stream<Item = std::result::Result<Event, axum::Error>>>, StatusCode> {
+    state.authorize(&headers)?;
+    
+    let receiver = debug::live_events().subscribe();
+    let stream = futures_util::stream::unfold((receiver, query.event_type), |(mut receiver, event_type)| async move {
+        loop {
+            let message = match receiver.recv().await {
+                Ok(event) if event_type.as_ref().is_some_and(|wanted| *wanted != event.event_type) => continue,
+                Ok(event) => Event::default().event("event").json_data(&event),
+                // The client reads slower than events are processed
+                Err(RecvError::Lagged(skipped)) => Ok(Event::default().comment(format!("{} events skipped", skipped))),
+                Err(RecvError::Closed) => return None,
+            };
+            return Some((message, (receiver, event_type)));
+        }
+    });
+    
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn routes_handler(
+    State(state): State<DebugState>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<SourceRoutes>>, StatusCode> {
+    state.authorize(&headers)?;
+    
+    Ok(Json(state.routes.as_ref().clone()))
+}
+
+pub fn create_router(config: AppConfig, pipeline_tx: PipelineSender, status: Arc<PipelineStatus>) -> Result<Router> {
+    create_router_with_sources(config, pipeline_tx, status, &SourceRegistry::default())
+}
+
+/// Like `create_router`, with source types looked up in `sources` instead of the built-in ones only
+pub fn create_router_with_sources(
+    config: AppConfig,
+    pipeline_tx: PipelineSender,
+    status: Arc<PipelineStatus>,
+    sources: &SourceRegistry,
+) -> Result<Router> {
+    let context = SourceContext {
+        pipeline_tx: pipeline_tx.clone(),
+        base_path: config.get_base_path(),
+        in_flight: config.max_in_flight.map(InFlightLimit::new),
+        integration: 0,
+    };
+    
+    let ops_state = OpsState {
+        pipeline_tx: pipeline_tx.clone(),
+        status,
+        heartbeat_timeout: Duration::from_millis(config.health.heartbeat_timeout_ms),
+    };
+    
     let mut router = Router::new()
-        .route("/-/healthz", get(health_check))
-        .route("/-/ready", get(health_check));
-    
-    // Register source routes
-    for integration in config.integrations {
-        router = match integration.source {
-            SourceConfig::Jira(jira_config) => {
-                jira::register_jira_routes(router, jira_config, pipeline_tx.clone())?
+        .route("/-/healthz", get(health_check).with_state(ops_state.clone()))
+        .route("/-/ready", get(readiness_check).with_state(ops_state.clone()))
+        .route("/-/metrics", get(metrics_handler).with_state(ops_state.clone()))
+        .route("/-/status", get(status_handler).with_state(ops_state.clone()));
+    
+    if crate::metrics::global().pushes() {
+        push_gauges(ops_state);
+    }
+    
+    // axum panics on a path registered twice, so report clashes as a configuration error first
+    let mut paths: HashSet<String> = OPS_PATHS.iter().map(|path| path.to_string()).collect();
+    let mut routes = Vec::new();
+    for integration in &config.integrations {
+        let source_paths = sources.webhook_paths(&integration.source, &context)?;
+        for path in &source_paths {
+            if !paths.insert(path.clone()) {
+                return Err(AppError::Config(format!("duplicate webhook path: {}", path)));
             }
+        }
+        
+        routes.push(SourceRoutes {
+            source_type: integration.source.source_type().to_string(),
+            paths: source_paths,
+            events: sources.events(&integration.source)?,
+        });
+    }
+    
+    if let Some(debug_config) = &config.debug {
+        let token = debug_config.token.resolve()?;
+        if token.is_empty() {
+            return Err(AppError::Config("debug.token must not be empty".to_string()));
+        }
+        
+        let debug_state = DebugState {
+            token: Arc::new(token),
+            routes: Arc::new(routes),
         };
+        
+        debug::failures().configure(debug_config.failure_buffer_size, debug_config.redact_paths.clone());
+        debug::live_events().configure(debug_config.redact_paths.clone());
+        router = router
+            .route("/-/debug/failures", get(debug_failures_handler).with_state(debug_state.clone()))
+            .route("/-/events/stream", get(events_stream_handler).with_state(debug_state.clone()))
+            .route("/-/routes", get(routes_handler).with_state(debug_state));
+        
+        tracing::warn!("Debug failure buffer enabled, keeping up to {} failing payloads", debug_config.failure_buffer_size);
+    }
+    
+    // Register source routes, under the base path if any (health endpoints stay at `/-/*`)
+    for (idx, integration) in config.integrations.iter().enumerate() {
+        let context = SourceContext { integration: idx, ..context.clone() };
+        router = sources.register_routes(router, &integration.source, &context)?;
     }
     
     Ok(router)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_join_path() {
+        assert_eq!(join_path("/webhooks", "/jira/webhook"), "/webhooks/jira/webhook");
+        assert_eq!(join_path("webhooks/", "jira/webhook"), "/webhooks/jira/webhook");
+        assert_eq!(join_path("/webhooks/", "/jira/webhook"), "/webhooks/jira/webhook");
+        assert_eq!(join_path("/", "/jira/webhook"), "/jira/webhook");
+    }
+}
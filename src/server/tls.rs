@@ -0,0 +1,294 @@
+use axum::extract::Request;
+use axum::Router;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::server::{ServerConnection, WebPkiClientVerifier};
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+use crate::error::{AppError, Result};
+
+/// Pause after a failed accept, e.g. when the process is out of file descriptors, so the loop
+/// doesn't spin while the condition lasts
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// TLS settings of the HTTP server
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TlsConfig {
+    /// PEM certificate chain presented to clients
+    pub cert_file: String,
+    /// PEM private key of the certificate
+    pub key_file: String,
+    /// PEM bundle of the CAs client certificates must chain to; enables mutual TLS
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ca_file: Option<String>,
+    /// Client certificate subjects allowed to connect, e.g. `CN=partner.example.com, O=Partner`;
+    /// any certificate issued by the client CA if empty
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_client_subjects: Vec<String>,
+    /// Longest a client may take to complete the handshake before its connection is closed
+    #[serde(default = "default_handshake_timeout_ms")]
+    pub handshake_timeout_ms: u64,
+}
+
+fn default_handshake_timeout_ms() -> u64 {
+    10_000
+}
+
+/// Verified client certificate, in the extensions of every request received over mutual TLS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    /// Distinguished name of the certificate subject, e.g. `CN=partner.example.com, O=Partner`
+    pub subject: String,
+}
+
+pub fn acceptor(config: &TlsConfig) -> Result<TlsAcceptor> {
+    let provider = Arc::new(ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| AppError::Config(format!("Invalid TLS configuration: {}", e)))?;
+
+    let builder = match &config.client_ca_file {
+        Some(client_ca_file) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(client_ca_file)? {
+                roots.add(cert).map_err(|e| AppError::Config(format!("Invalid client CA in {}: {}", client_ca_file, e)))?;
+            }
+
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                .build()
+                .map_err(|e| AppError::Config(format!("Invalid client CA in {}: {}", client_ca_file, e)))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None if !config.allowed_client_subjects.is_empty() => {
+            return Err(AppError::Config("tls.allowed_client_subjects requires tls.client_ca_file".to_string()));
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let server_config = builder
+        .with_single_cert(load_certs(&config.cert_file)?, load_key(&config.key_file)?)
+        .map_err(|e| AppError::Config(format!("Invalid TLS certificate or key: {}", e)))?;
+
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>> {
+    let pem = std::fs::read(path)?;
+    let certs = rustls_pemfile::certs(&mut pem.as_slice())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| AppError::Config(format!("Invalid PEM certificate in {}: {}", path, e)))?;
+
+    if certs.is_empty() {
+        return Err(AppError::Config(format!("No certificate found in {}", path)));
+    }
+
+    Ok(certs)
+}
+
+fn load_key(path: &str) -> Result<PrivateKeyDer<'static>> {
+    let pem = std::fs::read(path)?;
+    rustls_pemfile::private_key(&mut pem.as_slice())
+        .map_err(|e| AppError::Config(format!("Invalid PEM private key in {}: {}", path, e)))?
+        .ok_or_else(|| AppError::Config(format!("No private key found in {}", path)))
+}
+
+/// Subject of the certificate the client presented, if any
+fn client_certificate(connection: &ServerConnection) -> Option<ClientCertificate> {
+    let der = connection.peer_certificates()?.first()?;
+    let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+
+    Some(ClientCertificate { subject: cert.subject().to_string() })
+}
+
+/// Serve the router over TLS, accepting connections until the task is dropped
+///
+/// Handshakes run in the connection's own task, so a slow or failing client never holds up
+/// the accept loop, and are abandoned after `config.handshake_timeout_ms`. A failed accept is
+/// logged and retried after a short pause rather than stopping the server.
+pub async fn serve(listener: TcpListener, router: Router, acceptor: TlsAcceptor, config: &TlsConfig) -> Result<()> {
+    let allowed_subjects = Arc::new(config.allowed_client_subjects.clone());
+    let handshake_timeout = Duration::from_millis(config.handshake_timeout_ms);
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::warn!("Failed to accept connection: {}, retrying in {:?}", e, ACCEPT_ERROR_BACKOFF);
+                tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        let allowed_subjects = allowed_subjects.clone();
+
+        tokio::spawn(async move {
+            let stream = match tokio::time::timeout(handshake_timeout, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    tracing::debug!("TLS handshake with {} failed: {}", peer, e);
+                    return;
+                }
+                Err(_) => {
+                    tracing::debug!("TLS handshake with {} timed out after {:?}", peer, handshake_timeout);
+                    return;
+                }
+            };
+
+            let client = client_certificate(stream.get_ref().1);
+            if !allowed_subjects.is_empty() {
+                let allowed = client.as_ref().is_some_and(|client| allowed_subjects.contains(&client.subject));
+                if !allowed {
+                    tracing::warn!("Rejected client certificate {:?} from {}: subject not allowed", client.map(|c| c.subject), peer);
+                    return;
+                }
+            }
+
+            let service = hyper::service::service_fn(move |mut request: Request<hyper::body::Incoming>| {
+                if let Some(client) = &client {
+                    request.extensions_mut().insert(client.clone());
+                }
+                router.clone().oneshot(request.map(axum::body::Body::new))
+            });
+
+            if let Err(e) = auto::Builder::new(TokioExecutor::new())
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!("Connection from {} closed with error: {}", peer, e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Extension};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::rustls::pki_types::ServerName;
+    use tokio_rustls::rustls::ClientConfig;
+    use tokio_rustls::TlsConnector;
+
+    fn fixture(name: &str) -> String {
+        format!("{}/tests/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn mtls_config(allowed_client_subjects: Vec<String>) -> TlsConfig {
+        TlsConfig {
+            cert_file: fixture("server.pem"),
+            key_file: fixture("server.key"),
+            client_ca_file: Some(fixture("ca.pem")),
+            allowed_client_subjects,
+            handshake_timeout_ms: 10_000,
+        }
+    }
+
+    /// Start a server echoing the client certificate subject, returning its address
+    async fn start(config: TlsConfig) -> std::net::SocketAddr {
+        let router = Router::new().route(
+            "/whoami",
+            get(|client: Option<Extension<ClientCertificate>>| async move {
+                client.map(|Extension(client)| client.subject).unwrap_or_default()
+            }),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let acceptor = acceptor(&config).unwrap();
+        tokio::spawn(async move { serve(listener, router, acceptor, &config).await });
+
+        addr
+    }
+
+    /// Send `GET /whoami`, returning the raw response or the error that ended the exchange
+    async fn get_whoami(addr: std::net::SocketAddr, with_client_cert: bool) -> std::io::Result<String> {
+        let mut roots = RootCertStore::empty();
+        roots.add(load_certs(&fixture("ca.pem")).unwrap().remove(0)).unwrap();
+
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots);
+        let client_config = if with_client_cert {
+            builder
+                .with_client_auth_cert(load_certs(&fixture("client.pem")).unwrap(), load_key(&fixture("client.key")).unwrap())
+                .unwrap()
+        } else {
+            builder.with_no_client_auth()
+        };
+
+        let stream = tokio::net::TcpStream::connect(addr).await?;
+        let mut stream = TlsConnector::from(Arc::new(client_config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await?;
+
+        stream.write_all(b"GET /whoami HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn test_verified_client_subject_reaches_handlers() {
+        let addr = start(mtls_config(vec![])).await;
+
+        let response = get_whoami(addr, true).await.unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200"), "unexpected response: {}", response);
+        assert!(response.ends_with("CN=partner.example.com, O=Partner"), "unexpected response: {}", response);
+    }
+
+    #[tokio::test]
+    async fn test_request_without_client_certificate_is_rejected() {
+        let addr = start(mtls_config(vec![])).await;
+
+        let response = get_whoami(addr, false).await;
+
+        // With TLS 1.3 the server's rejection arrives after the client considers the handshake done
+        assert!(response.as_ref().map_or(true, String::is_empty), "unexpected response: {:?}", response);
+    }
+
+    #[tokio::test]
+    async fn test_client_subject_outside_allowlist_is_rejected() {
+        let addr = start(mtls_config(vec!["CN=other.example.com".to_string()])).await;
+
+        let response = get_whoami(addr, true).await;
+
+        assert!(response.as_ref().map_or(true, String::is_empty), "unexpected response: {:?}", response);
+    }
+
+    #[tokio::test]
+    async fn test_silent_client_is_dropped_after_handshake_timeout() {
+        let addr = start(TlsConfig { handshake_timeout_ms: 50, ..mtls_config(vec![]) }).await;
+
+        // Connect without ever starting the handshake
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut buffer = [0u8; 16];
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buffer)).await;
+
+        assert!(matches!(read, Ok(Ok(0)) | Ok(Err(_))), "connection still open: {:?}", read);
+        // The server keeps accepting others
+        assert!(get_whoami(addr, true).await.unwrap().starts_with("HTTP/1.1 200"));
+    }
+
+    #[test]
+    fn test_allowlist_requires_client_ca() {
+        let config = TlsConfig {
+            client_ca_file: None,
+            ..mtls_config(vec!["CN=partner.example.com".to_string()])
+        };
+
+        assert!(acceptor(&config).is_err());
+    }
+}
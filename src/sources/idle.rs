@@ -0,0 +1,107 @@
+use crate::metrics;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+/// Notices a source going silent: warns once no event has arrived for `timeout`
+///
+/// Each silence is logged and counted in `connectcare_source_idle_total` once, and
+/// `connectcare_source_idle` is 1 while it lasts.
+pub struct IdleWatch {
+    source: String,
+    path: String,
+    timeout: Duration,
+    last_event: Mutex<Instant>,
+    idle: AtomicBool,
+}
+
+impl IdleWatch {
+    /// Start watching a source; the watch stops once the returned handle is dropped
+    pub fn spawn(source: &str, path: &str, timeout: Duration) -> Arc<Self> {
+        let watch = Arc::new(Self {
+            source: source.to_string(),
+            path: path.to_string(),
+            timeout,
+            last_event: Mutex::new(Instant::now()),
+            idle: AtomicBool::new(false),
+        });
+
+        tokio::spawn(Self::watch(Arc::downgrade(&watch), (timeout / 4).max(Duration::from_millis(10))));
+        watch
+    }
+
+    /// Note that an event arrived, ending any silence
+    pub fn record_event(&self) {
+        *self.last_event.lock().unwrap() = Instant::now();
+
+        if self.idle.swap(false, Ordering::Relaxed) {
+            tracing::info!("Source {} at {} is receiving events again", self.source, self.path);
+            metrics::global().set_gauge("connectcare_source_idle", &self.labels(), 0);
+        }
+    }
+
+    /// Whether the source has been silent for longer than the timeout
+    pub fn is_idle(&self) -> bool {
+        self.idle.load(Ordering::Relaxed)
+    }
+
+    fn labels(&self) -> [(&str, &str); 2] {
+        [("source", &self.source), ("path", &self.path)]
+    }
+
+    fn check(&self) {
+        let silent_for = self.last_event.lock().unwrap().elapsed();
+        if silent_for < self.timeout || self.idle.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        tracing::warn!("Source {} at {} has received no events for {:?}", self.source, self.path, silent_for);
+        metrics::global().increment("connectcare_source_idle_total", &self.labels());
+        metrics::global().set_gauge("connectcare_source_idle", &self.labels(), 1);
+    }
+
+    async fn watch(watch: Weak<Self>, period: Duration) {
+        let mut ticks = tokio::time::interval(period);
+        loop {
+            ticks.tick().await;
+            match watch.upgrade() {
+                Some(watch) => watch.check(),
+                None => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle_count(path: &str) -> u64 {
+        metrics::global().counter("connectcare_source_idle_total", &[("source", "jira"), ("path", path)])
+    }
+
+    #[tokio::test]
+    async fn test_warns_when_no_events_arrive() {
+        let watch = IdleWatch::spawn("jira", "/jira/silent", Duration::from_millis(40));
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        assert!(watch.is_idle());
+        // Warned once for the whole silence
+        assert_eq!(idle_count("/jira/silent"), 1);
+        assert_eq!(metrics::global().gauge("connectcare_source_idle", &[("source", "jira"), ("path", "/jira/silent")]), 1);
+    }
+
+    #[tokio::test]
+    async fn test_events_keep_the_source_active() {
+        let watch = IdleWatch::spawn("jira", "/jira/busy", Duration::from_millis(80));
+
+        for _ in 0..10 {
+            tokio::time::sleep(Duration::from_millis(15)).await;
+            watch.record_event();
+        }
+
+        assert!(!watch.is_idle());
+        assert_eq!(idle_count("/jira/busy"), 0);
+    }
+}
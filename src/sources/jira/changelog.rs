@@ -0,0 +1,39 @@
+use serde_json::{json, Map, Value};
+
+/// Reduce an `issue_updated` body to the issue identity and the new values from its changelog
+///
+/// Each `changelog.items` entry contributes `field -> toString` (falling back to the raw `to`
+/// value), so the result looks like:
+/// `{ "webhookEvent", "timestamp", "issue": { "id", "key" }, "changedFields": { ... } }`
+pub fn only_changed_fields(body: &Value) -> Value {
+    let mut changed = Map::new();
+
+    let items = body
+        .pointer("/changelog/items")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    for item in items {
+        let Some(field) = item.get("field").and_then(Value::as_str) else {
+            continue;
+        };
+
+        let new_value = match item.get("toString") {
+            Some(value) if !value.is_null() => value.clone(),
+            _ => item.get("to").cloned().unwrap_or(Value::Null),
+        };
+
+        changed.insert(field.to_string(), new_value);
+    }
+
+    json!({
+        "webhookEvent": body.get("webhookEvent").cloned().unwrap_or(Value::Null),
+        "timestamp": body.get("timestamp").cloned().unwrap_or(Value::Null),
+        "issue": {
+            "id": body.pointer("/issue/id").cloned().unwrap_or(Value::Null),
+            "key": body.pointer("/issue/key").cloned().unwrap_or(Value::Null),
+        },
+        "changedFields": changed,
+    })
+}
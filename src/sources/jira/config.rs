@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use crate::config::secret::SecretSource;
+use crate::pipeline::event::Operation;
+use crate::sources::webhook::hmac::{SecretEncoding, SignatureFormat};
+
+/// Upper bound of `auth_failure_min_ms`
+pub const MAX_AUTH_FAILURE_MIN_MS: u64 = 5_000;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct JiraSourceConfig {
@@ -8,6 +14,96 @@ pub struct JiraSourceConfig {
     pub webhook_path: Option<String>,
     
     pub authentication: JiraAuthentication,
+    
+    /// Ordered candidate pk paths per event type, overriding the built-in path
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub primary_key_paths: HashMap<String, Vec<String>>,
+    
+    /// Per event type changes to the built-in operation and sink hints
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub event_overrides: HashMap<String, EventOverride>,
+    
+    /// Reduce `jira:issue_updated` bodies to the fields listed in their changelog
+    #[serde(default)]
+    pub only_changed_fields: bool,
+    
+    /// Answer only after a `required` sink has written the event, failing the request otherwise
+    #[serde(default)]
+    pub require_sink_ack: bool,
+    
+    /// Answer `503` if the event can't be queued for the pipelines within this time, and `202`
+    /// as soon as it is queued; without it the request waits for queue space
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enqueue_deadline_ms: Option<u64>,
+    
+    /// Header carrying the provider's id for the delivery, logged and passed on with its events
+    #[serde(default = "default_delivery_id_header")]
+    pub delivery_id_header: String,
+    
+    /// Event types accepted by the source; others are rejected with `400`. Every supported
+    /// event type is accepted if empty
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allowed_events: Vec<String>,
+    
+    /// Deepest nesting of objects and arrays accepted in a body; deeper payloads get `400`
+    #[serde(default = "default_max_body_depth")]
+    pub max_body_depth: usize,
+    
+    /// Mapper template applied to authenticated bodies before the event type is read, e.g. to
+    /// unwrap a provider envelope
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body_transform: Option<serde_json::Value>,
+    
+    /// Give every event this type instead of reading it from the body, for bodies without one such
+    /// as Jira automation's "Send web request"; types other than the built-in ones need an entry
+    /// in `primary_key_paths`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed_event_type: Option<String>,
+    
+    /// Ordered candidate body paths of the event type; the first holding a string is used
+    #[serde(default = "default_event_type_paths")]
+    pub event_type_paths: Vec<String>,
+    
+    /// Build the event type by joining these header values and body strings instead of reading
+    /// `event_type_paths`; types other than the built-in ones need an entry in `primary_key_paths`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub event_type_parts: Vec<EventTypePart>,
+    
+    /// Joins the `event_type_parts` values
+    #[serde(default = "default_event_type_separator")]
+    pub event_type_separator: String,
+    
+    /// Read bodies as `application/x-www-form-urlencoded` and take the JSON from this field,
+    /// e.g. `payload`; the signature still covers the raw body
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub form_payload_field: Option<String>,
+    
+    /// Parse bodies selectively, keeping only these paths plus the event type and primary key
+    /// paths; the rest of the payload is skipped without being materialized. Processors and
+    /// sinks only see the kept paths
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extract_paths: Vec<String>,
+    
+    /// Headers every request must carry, checked before the signature as a cheap spam filter; a
+    /// `null` value only requires the header, a string requires it to equal that value
+    #[serde(default)]
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub required_headers: HashMap<String, Option<String>>,
+    
+    /// Warn, and count in `connectcare_source_idle_total`, once no authentic request has arrived
+    /// for this many seconds
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_secs: Option<u64>,
 }
 
 impl JiraSourceConfig {
@@ -16,12 +112,84 @@ impl JiraSourceConfig {
     }
 }
 
+/// One piece of a composite event type
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventTypePart {
+    /// Value of a request header
+    Header(String),
+    /// String at a body path, dotted or JSON Pointer
+    Path(String),
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct EventOverride {
+    /// Replaces the event's built-in `write`/`delete` operation
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation: Option<Operation>,
+    
+    /// Makes sinks insert the event instead of upserting it
+    #[serde(default)]
+    pub insert_only: bool,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct JiraAuthentication {
     pub secret: SecretSource,
     
     #[serde(default = "default_header_name")]
     pub header_name: String,
+    
+    /// Headers checked in order when the request has no `header_name` header, e.g. for Jira
+    /// versions signing under another name
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fallback_header_names: Vec<String>,
+    
+    /// Read the signature from this query parameter of the webhook URL instead of `header_name`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_param: Option<String>,
+    
+    #[serde(default)]
+    pub signature_format: SignatureFormat,
+    
+    /// Verify the signature against the body re-serialized with sorted keys
+    #[serde(default)]
+    pub canonicalize_body: bool,
+    
+    /// How the resolved secret is decoded into the HMAC key
+    #[serde(default)]
+    pub secret_encoding: SecretEncoding,
+    
+    /// Token that skips the signature check of requests carrying it in `X-Auth-Bypass`; for test
+    /// environments only, never set it in production
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_bypass_secret: Option<SecretSource>,
+    
+    /// Answer requests failing authentication no sooner than this after they arrive, so response
+    /// times don't tell which check failed; at most `MAX_AUTH_FAILURE_MIN_MS`, off if unset
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_failure_min_ms: Option<u64>,
+}
+
+fn default_event_type_paths() -> Vec<String> {
+    vec!["webhookEvent".to_string()]
+}
+
+fn default_event_type_separator() -> String {
+    ".".to_string()
+}
+
+fn default_max_body_depth() -> usize {
+    100
+}
+
+fn default_delivery_id_header() -> String {
+    "X-Atlassian-Webhook-Identifier".to_string()
 }
 
 fn default_webhook_path() -> String {
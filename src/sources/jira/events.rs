@@ -1,8 +1,10 @@
+use axum::http::HeaderMap;
 use std::collections::HashMap;
 use serde_json::Value;
 use crate::error::{AppError, Result};
 use crate::pipeline::event::{Operation, PkFields};
-use crate::sources::webhook::types::get_primary_key_by_path;
+use crate::sources::webhook::types::{extract_value_by_path, get_primary_key_by_path};
+use super::config::EventTypePart;
 
 pub mod event_types {
     // Issue events
@@ -27,11 +29,30 @@ pub mod event_types {
     pub const VERSION_CREATED: &str = "jira:version_created";
     pub const VERSION_UPDATED: &str = "jira:version_updated";
     pub const VERSION_DELETED: &str = "jira:version_deleted";
+    
+    // Sprint events
+    pub const SPRINT_CREATED: &str = "sprint_created";
+    pub const SPRINT_UPDATED: &str = "sprint_updated";
+    pub const SPRINT_STARTED: &str = "sprint_started";
+    pub const SPRINT_CLOSED: &str = "sprint_closed";
+    pub const SPRINT_DELETED: &str = "sprint_deleted";
+    
+    // Board events
+    pub const BOARD_CREATED: &str = "board_created";
+    pub const BOARD_UPDATED: &str = "board_updated";
+    pub const BOARD_DELETED: &str = "board_deleted";
 }
 
+/// Extracts an event's primary keys from its body and the request headers
+pub type PkExtractor = Box<dyn Fn(&Value, &HeaderMap) -> Result<PkFields> + Send + Sync>;
+
 pub struct EventConfig {
     pub operation: Operation,
-    pub get_field_id: Box<dyn Fn(&Value) -> Result<PkFields> + Send + Sync>,
+    pub get_field_id: PkExtractor,
+    /// Candidate paths `get_field_id` reads, for introspection
+    pub pk_paths: Vec<String>,
+    /// Passed on to sinks as a hint to insert rather than upsert
+    pub insert_only: bool,
 }
 
 pub fn get_supported_events() -> HashMap<String, EventConfig> {
@@ -43,6 +64,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Write,
             get_field_id: Box::new(get_primary_key_by_path("issue.id")),
+            pk_paths: vec!["issue.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -51,6 +74,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Write,
             get_field_id: Box::new(get_primary_key_by_path("issue.id")),
+            pk_paths: vec!["issue.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -59,6 +84,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Delete,
             get_field_id: Box::new(get_primary_key_by_path("issue.id")),
+            pk_paths: vec!["issue.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -68,6 +95,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Write,
             get_field_id: Box::new(get_primary_key_by_path("issueLink.id")),
+            pk_paths: vec!["issueLink.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -76,6 +105,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Delete,
             get_field_id: Box::new(get_primary_key_by_path("issueLink.id")),
+            pk_paths: vec!["issueLink.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -85,6 +116,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Write,
             get_field_id: Box::new(get_primary_key_by_path("project.id")),
+            pk_paths: vec!["project.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -93,6 +126,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Write,
             get_field_id: Box::new(get_primary_key_by_path("project.id")),
+            pk_paths: vec!["project.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -101,6 +136,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Delete,
             get_field_id: Box::new(get_primary_key_by_path("project.id")),
+            pk_paths: vec!["project.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -109,6 +146,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Delete,
             get_field_id: Box::new(get_primary_key_by_path("project.id")),
+            pk_paths: vec!["project.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -117,6 +156,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Write,
             get_field_id: Box::new(get_primary_key_by_path("project.id")),
+            pk_paths: vec!["project.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -126,6 +167,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Write,
             get_field_id: Box::new(get_primary_key_by_path("version.id")),
+            pk_paths: vec!["version.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -134,6 +177,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Write,
             get_field_id: Box::new(get_primary_key_by_path("version.id")),
+            pk_paths: vec!["version.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -142,6 +187,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Write,
             get_field_id: Box::new(get_primary_key_by_path("version.id")),
+            pk_paths: vec!["version.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -150,6 +197,8 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Write,
             get_field_id: Box::new(get_primary_key_by_path("version.id")),
+            pk_paths: vec!["version.id".to_string()],
+            insert_only: false,
         },
     );
     
@@ -158,15 +207,116 @@ pub fn get_supported_events() -> HashMap<String, EventConfig> {
         EventConfig {
             operation: Operation::Delete,
             get_field_id: Box::new(get_primary_key_by_path("version.id")),
+            pk_paths: vec!["version.id".to_string()],
+            insert_only: false,
+        },
+    );
+    
+    // Sprint events
+    events.insert(
+        event_types::SPRINT_CREATED.to_string(),
+        EventConfig {
+            operation: Operation::Write,
+            get_field_id: Box::new(get_primary_key_by_path("sprint.id")),
+            pk_paths: vec!["sprint.id".to_string()],
+            insert_only: false,
+        },
+    );
+    
+    events.insert(
+        event_types::SPRINT_UPDATED.to_string(),
+        EventConfig {
+            operation: Operation::Write,
+            get_field_id: Box::new(get_primary_key_by_path("sprint.id")),
+            pk_paths: vec!["sprint.id".to_string()],
+            insert_only: false,
+        },
+    );
+    
+    events.insert(
+        event_types::SPRINT_STARTED.to_string(),
+        EventConfig {
+            operation: Operation::Write,
+            get_field_id: Box::new(get_primary_key_by_path("sprint.id")),
+            pk_paths: vec!["sprint.id".to_string()],
+            insert_only: false,
+        },
+    );
+    
+    events.insert(
+        event_types::SPRINT_CLOSED.to_string(),
+        EventConfig {
+            operation: Operation::Write,
+            get_field_id: Box::new(get_primary_key_by_path("sprint.id")),
+            pk_paths: vec!["sprint.id".to_string()],
+            insert_only: false,
+        },
+    );
+    
+    events.insert(
+        event_types::SPRINT_DELETED.to_string(),
+        EventConfig {
+            operation: Operation::Delete,
+            get_field_id: Box::new(get_primary_key_by_path("sprint.id")),
+            pk_paths: vec!["sprint.id".to_string()],
+            insert_only: false,
+        },
+    );
+    
+    // Board events
+    events.insert(
+        event_types::BOARD_CREATED.to_string(),
+        EventConfig {
+            operation: Operation::Write,
+            get_field_id: Box::new(get_primary_key_by_path("board.id")),
+            pk_paths: vec!["board.id".to_string()],
+            insert_only: false,
+        },
+    );
+    
+    events.insert(
+        event_types::BOARD_UPDATED.to_string(),
+        EventConfig {
+            operation: Operation::Write,
+            get_field_id: Box::new(get_primary_key_by_path("board.id")),
+            pk_paths: vec!["board.id".to_string()],
+            insert_only: false,
+        },
+    );
+    
+    events.insert(
+        event_types::BOARD_DELETED.to_string(),
+        EventConfig {
+            operation: Operation::Delete,
+            get_field_id: Box::new(get_primary_key_by_path("board.id")),
+            pk_paths: vec!["board.id".to_string()],
+            insert_only: false,
         },
     );
     
     events
 }
 
-pub fn get_event_type(body: &Value) -> Result<String> {
-    body.get("webhookEvent")
-        .and_then(|v| v.as_str())
+/// Event type at the first of `paths` holding a string
+pub fn get_event_type(body: &Value, paths: &[String]) -> Result<String> {
+    paths
+        .iter()
+        .find_map(|path| extract_value_by_path(body, path).ok().and_then(|v| v.as_str()))
         .map(|s| s.to_string())
         .ok_or(AppError::EventTypeNotFound)
 }
+
+/// Event type joined from header values and body strings, e.g. `X-GitHub-Event` and `action`
+/// into `pull_request.opened`; every part must be present
+pub fn compose_event_type(parts: &[EventTypePart], separator: &str, headers: &HeaderMap, body: &Value) -> Result<String> {
+    let values = parts
+        .iter()
+        .map(|part| match part {
+            EventTypePart::Header(name) => headers.get(name).and_then(|v| v.to_str().ok()),
+            EventTypePart::Path(path) => extract_value_by_path(body, path).ok().and_then(Value::as_str),
+        })
+        .collect::<Option<Vec<&str>>>()
+        .ok_or(AppError::EventTypeNotFound)?;
+    
+    Ok(values.join(separator))
+}
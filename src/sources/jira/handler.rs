@@ -1,68 +1,425 @@
 use axum::{
-    extract::State,
-    http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    extract::{Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json,
 };
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use serde_json::Value;
+use crate::debug;
 use crate::error::{AppError, Result};
+use crate::metrics;
 use crate::pipeline::{PipelineSender, event::PipelineEvent};
+use crate::pipeline::ack::{ack_channel, AckOutcome};
+use crate::pipeline::handoff::handoff_channel;
+use crate::pipeline::in_flight::InFlightLimit;
+use crate::pipeline::processors::mapper::MapperProcessor;
+use crate::pipeline::sinks::SinkOutcome;
+use crate::sources::idle::IdleWatch;
+use crate::sources::webhook::batch::{BatchItemStatus, BatchReport};
+use crate::sources::webhook::extract::{self, PathSelection};
 use crate::sources::webhook::hmac::HmacValidator;
-use super::events::{EventConfig, get_event_type};
-use std::collections::HashMap;
+use crate::sources::webhook::types::exceeds_depth;
+use super::changelog;
+use super::config::EventTypePart;
+use super::events::{EventConfig, compose_event_type, event_types, get_event_type};
+use std::collections::{HashMap, HashSet};
+use subtle::ConstantTimeEq;
+use tracing::Instrument;
+
+/// Number of body bytes logged when a payload fails to parse
+const BODY_PREVIEW_BYTES: usize = 256;
+
+/// Response header echoing the delivery id, so callers can match responses to our logs
+pub const DELIVERY_ID_RESPONSE_HEADER: &str = "x-delivery-id";
+
+/// Request header carrying the token that skips the signature check, when one is configured
+pub const AUTH_BYPASS_HEADER: &str = "x-auth-bypass";
 
 pub struct JiraWebhookState {
     pub validator: HmacValidator,
+    /// Resolved `auth_bypass_secret`; requests whose bypass header equals it skip the signature check
+    pub auth_bypass_token: Option<String>,
+    /// Least time taken to answer a request failing authentication
+    pub auth_failure_floor: Option<Duration>,
+    /// Query parameter carrying the signature, instead of the validator's header
+    pub signature_query_param: Option<String>,
+    /// Type given to every event instead of the one in its body
+    pub fixed_event_type: Option<String>,
+    /// Candidate body paths of the event type, in order
+    pub event_type_paths: Vec<String>,
+    /// Header values and body strings joined into the event type, instead of `event_type_paths`
+    pub event_type_parts: Vec<EventTypePart>,
+    pub event_type_separator: String,
+    /// Form field holding the JSON of form-encoded bodies
+    pub form_payload_field: Option<String>,
+    /// Paths kept when bodies are parsed selectively; bodies are parsed whole if `None`
+    pub selection: Option<PathSelection>,
+    /// Headers that must be present, and equal the value if one is given
+    pub required_headers: HashMap<String, Option<String>>,
+    /// Tracks requests to warn when the source goes silent
+    pub idle: Option<Arc<IdleWatch>>,
     pub events: HashMap<String, EventConfig>,
     pub pipeline_tx: PipelineSender,
+    pub only_changed_fields: bool,
+    pub require_sink_ack: bool,
+    pub enqueue_deadline: Option<Duration>,
+    pub delivery_id_header: String,
+    /// Accept only these event types; empty accepts all
+    pub allowed_events: HashSet<String>,
+    pub max_body_depth: usize,
+    /// Applied to the whole body before events are extracted from it
+    pub body_transform: Option<MapperProcessor>,
+    pub in_flight: Option<InFlightLimit>,
+    /// Index of the integration the source belongs to, set on every event for routing
+    pub integration: Option<usize>,
+}
+
+/// Request-level details shared by every event of a delivery
+struct Delivery<'a> {
+    id: &'a str,
+    headers: &'a HeaderMap,
+    uri: &'a Uri,
+}
+
+/// What happened to a single event of a webhook
+enum Dispatched {
+    /// Its type is not configured
+    Ignored,
+    /// Queued for the pipelines
+    Queued,
+    /// Written by a required sink
+    Written(SinkOutcome),
 }
 
 pub async fn handle_jira_webhook(
     State(state): State<Arc<JiraWebhookState>>,
+    uri: Uri,
     headers: HeaderMap,
     body: axum::body::Bytes,
-) -> Result<impl IntoResponse> {
-    // Step 1: Validate HMAC signature
-    let signature = headers
-        .get(state.validator.header_name())
+) -> Response {
+    // Use the provider's delivery id when it sends one, to cross-reference its delivery logs
+    let delivery_id = headers
+        .get(&state.delivery_id_header)
         .and_then(|h| h.to_str().ok())
-        .ok_or(AppError::MissingSignature)?;
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    
+    let span = tracing::info_span!("jira_delivery", delivery_id = %delivery_id);
+    let delivery = Delivery { id: &delivery_id, headers: &headers, uri: &uri };
+    let mut response = handle_delivery(&state, &delivery, body)
+        .instrument(span)
+        .await
+        .into_response();
+    
+    if let Ok(value) = HeaderValue::from_str(&delivery_id) {
+        response.headers_mut().insert(DELIVERY_ID_RESPONSE_HEADER, value);
+    }
+    
+    response
+}
+
+async fn handle_delivery(state: &JiraWebhookState, delivery: &Delivery<'_>, body: axum::body::Bytes) -> Result<Response> {
+    let received = Instant::now();
+    
+    // Step 1: Check required headers and validate HMAC signature, holding failures until the floor
+    // so every failing check takes as long
+    if let Err(e) = authenticate(state, delivery, &body) {
+        if let Some(floor) = state.auth_failure_floor {
+            tokio::time::sleep(floor.saturating_sub(received.elapsed())).await;
+        }
+        return Err(e);
+    }
+    
+    if let Some(idle) = &state.idle {
+        idle.record_event();
+    }
+    
+    // Form-encoded deliveries carry the JSON in one field
+    let body = match &state.form_payload_field {
+        Some(field) => form_payload(&body, field)?,
+        None => body,
+    };
+    
+    // Step 2: Parse JSON body (only reached for authentic requests)
+    let parsed = match &state.selection {
+        Some(selection) => extract::from_slice(&body, selection),
+        None => serde_json::from_slice(&body),
+    };
+    let json_body: Value = parsed.map_err(|e| {
+        metrics::global().increment("connectcare_webhook_parse_failures_total", &[("source", "jira")]);
+        
+        let preview = String::from_utf8_lossy(&body[..body.len().min(BODY_PREVIEW_BYTES)]);
+        tracing::warn!("Malformed JSON in authenticated Jira webhook: {}", e);
+        tracing::debug!("Malformed body preview ({} of {} bytes): {}", preview.len(), body.len(), preview);
+        
+        AppError::JsonParse(e)
+    })?;
+    
+    // Keep pathologically nested payloads away from the recursive processors
+    if exceeds_depth(&json_body, state.max_body_depth) {
+        tracing::warn!("Rejecting Jira webhook nested deeper than {} levels", state.max_body_depth);
+        return Err(AppError::PayloadTooDeep(state.max_body_depth));
+    }
+    
+    // Normalize the provider's envelope before looking for events
+    let json_body = match &state.body_transform {
+        Some(transform) => transform.render(&json_body)?,
+        None => json_body,
+    };
+    
+    // A JSON array is a batch of events, each dispatched independently
+    if let Value::Array(items) = json_body {
+        return Ok(dispatch_batch(state, items, delivery).await.into_response());
+    }
+    
+    match dispatch_event(state, json_body, delivery).await? {
+        // Queued but not processed yet
+        Dispatched::Queued if state.enqueue_deadline.is_some() => Ok(StatusCode::ACCEPTED.into_response()),
+        Dispatched::Written(outcome) => Ok((StatusCode::OK, Json(outcome)).into_response()),
+        _ => Ok(StatusCode::OK.into_response()),
+    }
+}
+
+/// Check the request's required headers and its signature, unless a test environment's bypass
+/// token vouches for the signature
+fn authenticate(state: &JiraWebhookState, delivery: &Delivery<'_>, body: &[u8]) -> Result<()> {
+    check_required_headers(&state.required_headers, delivery.headers)?;
+    
+    if bypass_authorized(state.auth_bypass_token.as_deref(), delivery.headers) {
+        tracing::warn!("Skipping the signature check of a Jira webhook carrying a valid {} header", AUTH_BYPASS_HEADER);
+        metrics::global().increment("connectcare_webhook_auth_bypassed_total", &[("source", "jira")]);
+        return Ok(());
+    }
+    
+    let signature = match &state.signature_query_param {
+        Some(param) => query_signature(delivery.uri, param),
+        None => state.validator
+            .header_names()
+            .iter()
+            .find_map(|name| delivery.headers.get(name))
+            .and_then(|h| h.to_str().ok())
+            .map(str::to_string),
+    }
+    .ok_or(AppError::MissingSignature)?;
+    
+    state.validator.validate(body, &signature)
+}
+
+/// Whether the request carries the bypass token, compared in constant time
+fn bypass_authorized(token: Option<&str>, headers: &HeaderMap) -> bool {
+    let (Some(token), Some(provided)) = (token, headers.get(AUTH_BYPASS_HEADER)) else {
+        return false;
+    };
+    
+    bool::from(provided.as_bytes().ct_eq(token.as_bytes()))
+}
+
+/// Reject requests lacking a required header, or carrying it with another value
+fn check_required_headers(required: &HashMap<String, Option<String>>, headers: &HeaderMap) -> Result<()> {
+    for (name, expected) in required {
+        let value = headers.get(name).map(|v| v.to_str().unwrap_or_default());
+        
+        let accepted = match (value, expected) {
+            (Some(value), Some(expected)) => value == expected,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        
+        if !accepted {
+            tracing::warn!("Rejecting Jira webhook without the required {} header", name);
+            return Err(AppError::RequiredHeader(name.clone()));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Decoded value of a field of a form-encoded body
+fn form_payload(body: &[u8], field: &str) -> Result<axum::body::Bytes> {
+    let mut fields: HashMap<String, String> = serde_urlencoded::from_bytes(body)
+        .map_err(|_| AppError::MissingFormField(field.to_string()))?;
+    
+    fields
+        .remove(field)
+        .map(axum::body::Bytes::from)
+        .ok_or_else(|| AppError::MissingFormField(field.to_string()))
+}
+
+/// Percent-decoded value of a query parameter of the webhook URL
+fn query_signature(uri: &Uri, param: &str) -> Option<String> {
+    let Query(mut params) = Query::<HashMap<String, String>>::try_from_uri(uri).ok()?;
+    params.remove(param)
+}
+
+async fn dispatch_batch(state: &JiraWebhookState, items: Vec<Value>, delivery: &Delivery<'_>) -> BatchReport {
+    let mut report = BatchReport::default();
+    let mut busy = false;
     
-    state.validator.validate(&body, signature)?;
+    for item in items {
+        // Once the pipelines are busy or the service sheds load the later items aren't tried, so
+        // the sender resends them in order
+        if busy {
+            report.push(BatchItemStatus::Busy, Some("Not sent, an earlier event found the service busy".to_string()));
+            continue;
+        }
+        
+        match dispatch_event(state, item, delivery).await {
+            Ok(Dispatched::Queued) => report.push(BatchItemStatus::Accepted, None),
+            Ok(Dispatched::Written(outcome)) => report.push_written(outcome),
+            Ok(Dispatched::Ignored) => report.push(BatchItemStatus::Ignored, None),
+            // Shed by the in-flight limit, which is as much a "retry later" as a full queue
+            Err(e @ (AppError::PipelineBusy | AppError::Overloaded)) => {
+                busy = true;
+                report.push(BatchItemStatus::Busy, Some(e.to_string()));
+            }
+            // Server-side failures are answered 5xx like a single event, so the sender redelivers
+            Err(e) if e.status_code().is_server_error() => report.push(BatchItemStatus::Failed, Some(e.to_string())),
+            Err(e) => report.push(BatchItemStatus::Rejected, Some(e.to_string())),
+        }
+    }
+    
+    tracing::info!("Processed Jira batch of {} events", report.items.len());
     
-    // Step 2: Parse JSON body
-    let json_body: Value = serde_json::from_slice(&body)?;
+    report
+}
+
+/// Extract and send a single event
+async fn dispatch_event(state: &JiraWebhookState, json_body: Value, delivery: &Delivery<'_>) -> Result<Dispatched> {
+    // Keep the payload for the debug buffer only when it is enabled
+    let captured = debug::failures().is_enabled().then(|| json_body.clone());
     
     // Step 3: Extract event type
-    let event_type = get_event_type(&json_body)?;
+    let event_type = match &state.fixed_event_type {
+        Some(event_type) => Ok(event_type.clone()),
+        None if !state.event_type_parts.is_empty() => {
+            compose_event_type(&state.event_type_parts, &state.event_type_separator, delivery.headers, &json_body)
+        }
+        None => get_event_type(&json_body, &state.event_type_paths),
+    };
+    // Failures past type extraction are recorded under the resolved type
+    let recorded_type = captured.as_ref().and_then(|_| event_type.as_ref().ok().cloned());
+    let result = match event_type {
+        Ok(event_type) => {
+            let span = tracing::info_span!("jira_event", event_type = %event_type);
+            dispatch_typed_event(state, json_body, event_type, delivery).instrument(span).await
+        }
+        Err(e) => Err(e),
+    };
+    
+    if let (Err(e), Some(body)) = (&result, &captured) {
+        debug::failures().record("jira", recorded_type.as_deref(), body, &e.to_string());
+    }
+    
+    result
+}
+
+async fn dispatch_typed_event(
+    state: &JiraWebhookState,
+    json_body: Value,
+    event_type: String,
+    delivery: &Delivery<'_>,
+) -> Result<Dispatched> {
+    if !state.allowed_events.is_empty() && !state.allowed_events.contains(&event_type) {
+        tracing::warn!("Rejecting event type not in allowed_events: {}", event_type);
+        return Err(AppError::UnsupportedEvent(event_type));
+    }
     
     // Step 4: Get event configuration (skip if not configured)
     let event_config = match state.events.get(&event_type) {
         Some(config) => config,
         None => {
             tracing::debug!("Event type not configured, accepting but will be filtered: {}", event_type);
-            return Ok(StatusCode::OK);
+            return Ok(Dispatched::Ignored);
         }
     };
     
     // Step 5: Extract primary keys
-    let pk_fields = (event_config.get_field_id)(&json_body)?;
+    let pk_fields = (event_config.get_field_id)(&json_body, delivery.headers)?;
+    
+    let json_body = if state.only_changed_fields && event_type == event_types::ISSUE_UPDATED {
+        changelog::only_changed_fields(&json_body)
+    } else {
+        json_body
+    };
     
     // Step 6: Create pipeline event
-    let event = PipelineEvent::new(
+    let mut event = PipelineEvent::new(
         json_body,
         event_type.clone(),
         pk_fields,
         event_config.operation.clone(),
-    );
+    )
+    .with_delivery_id(delivery.id.to_string());
+    event.insert_only = event_config.insert_only;
     
-    // Step 7: Send to pipeline
-    state.pipeline_tx
-        .send(event)
-        .await
-        .map_err(|_| AppError::PipelineSend)?;
+    if let Some(in_flight) = &state.in_flight {
+        event = event.with_in_flight_permit(in_flight.try_acquire("jira")?);
+    }
+    
+    // Step 7: Send to pipeline, waiting for a required sink when configured
+    let dispatched = if state.require_sink_ack {
+        let (ack, waiter) = ack_channel();
+        
+        enqueue(state, event.with_ack(ack)).await?;
+        
+        match waiter.wait().await {
+            AckOutcome::Acknowledged(outcome) => Dispatched::Written(outcome),
+            AckOutcome::NotRequired => Dispatched::Queued,
+            AckOutcome::Failed => {
+                return Err(AppError::Sink("No required sink accepted the event".to_string()));
+            }
+        }
+    } else {
+        enqueue(state, event).await?;
+        Dispatched::Queued
+    };
     
     tracing::info!("Successfully processed Jira event: {}", event_type);
     
-    Ok(StatusCode::OK)
+    Ok(dispatched)
+}
+
+/// Sending only fails once the executor has dropped its receiver, i.e. its task is gone
+fn pipeline_unavailable() -> AppError {
+    metrics::global().increment("connectcare_pipeline_send_failures_total", &[("source", "jira")]);
+    tracing::error!("Pipeline executor is not receiving events");
+    AppError::PipelineSend
+}
+
+/// Queue an event in its pipelines, giving up after the enqueue deadline if one is configured
+///
+/// The deadline covers both the executor's channel and the pipelines' own queues. An event the
+/// executor refuses, or that isn't queued in time, is never processed, so a retry can't duplicate it.
+async fn enqueue(state: &JiraWebhookState, mut event: PipelineEvent) -> Result<()> {
+    if let Some(integration) = state.integration {
+        event = event.with_integration(integration);
+    }
+    let (handoff, mut waiter) = handoff_channel();
+    let handed_off = async {
+        state.pipeline_tx.send(event.with_handoff(handoff)).await.map_err(|_| pipeline_unavailable())?;
+        Ok::<_, AppError>(waiter.queued().await)
+    };
+    
+    let queued = match state.enqueue_deadline {
+        Some(deadline) => match tokio::time::timeout(deadline, handed_off).await {
+            Ok(queued) => queued?,
+            Err(_) => {
+                let queued = waiter.give_up();
+                if !queued {
+                    metrics::global().increment("connectcare_pipeline_enqueue_timeouts_total", &[("source", "jira")]);
+                    tracing::warn!("Pipeline queue stayed full for {:?}, rejecting event", deadline);
+                }
+                queued
+            }
+        },
+        None => handed_off.await?,
+    };
+    
+    if queued {
+        Ok(())
+    } else {
+        Err(AppError::PipelineBusy)
+    }
 }
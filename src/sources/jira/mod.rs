@@ -1,41 +1,178 @@
+pub mod changelog;
 pub mod config;
 pub mod events;
 pub mod handler;
 
 use axum::{Router, routing::post};
+use std::collections::HashMap;
 use std::sync::Arc;
-use crate::error::Result;
+use std::time::Duration;
+use crate::config::SourceConfig;
+use crate::error::{AppError, Result};
 use crate::pipeline::PipelineSender;
+use crate::pipeline::event::Operation;
+use crate::pipeline::in_flight::InFlightLimit;
+use crate::pipeline::processors::mapper::MapperProcessor;
+use crate::sources::idle::IdleWatch;
+use crate::sources::webhook::extract::PathSelection;
 use crate::sources::webhook::hmac::HmacValidator;
-use events::get_supported_events;
+use crate::sources::webhook::types::get_primary_key_by_paths;
+use config::{EventTypePart, MAX_AUTH_FAILURE_MIN_MS};
+use events::{get_supported_events, EventConfig};
 use handler::{handle_jira_webhook, JiraWebhookState};
+use super::{EventRoute, Source, SourceContext};
 
 pub use config::JiraSourceConfig;
 
+/// The built-in `jira` source type
+pub struct JiraSource;
+
+impl Source for JiraSource {
+    fn register_routes(&self, router: Router, config: &SourceConfig, context: &SourceContext) -> Result<Router> {
+        let SourceConfig::Jira(jira_config) = config else {
+            return Err(AppError::Config(format!("Jira source given a {} configuration", config.source_type())));
+        };
+        
+        let mut jira_config = jira_config.as_ref().clone();
+        jira_config.webhook_path = Some(context.path(&jira_config.get_webhook_path()));
+        
+        register(router, jira_config, context.pipeline_tx.clone(), context.in_flight.clone(), Some(context.integration))
+    }
+    
+    fn webhook_paths(&self, config: &SourceConfig, context: &SourceContext) -> Vec<String> {
+        match config {
+            SourceConfig::Jira(jira_config) => vec![context.path(&jira_config.get_webhook_path())],
+            _ => Vec::new(),
+        }
+    }
+    
+    fn events(&self, config: &SourceConfig) -> Result<Vec<EventRoute>> {
+        let SourceConfig::Jira(jira_config) = config else {
+            return Ok(Vec::new());
+        };
+        
+        let mut events: Vec<EventRoute> = configured_events(jira_config)?
+            .into_iter()
+            .filter(|(event_type, _)| jira_config.allowed_events.is_empty() || jira_config.allowed_events.contains(event_type))
+            .map(|(event_type, event_config)| EventRoute {
+                event_type,
+                pk_paths: event_config.pk_paths,
+                operation: event_config.operation,
+            })
+            .collect();
+        events.sort_by(|a, b| a.event_type.cmp(&b.event_type));
+        
+        Ok(events)
+    }
+}
+
 pub fn register_jira_routes(
     router: Router,
     config: JiraSourceConfig,
     pipeline_tx: PipelineSender,
+) -> Result<Router> {
+    register_jira_routes_with_limit(router, config, pipeline_tx, None)
+}
+
+/// Like `register_jira_routes`, shedding events with `429` while `in_flight` is exhausted
+pub fn register_jira_routes_with_limit(
+    router: Router,
+    config: JiraSourceConfig,
+    pipeline_tx: PipelineSender,
+    in_flight: Option<InFlightLimit>,
+) -> Result<Router> {
+    register(router, config, pipeline_tx, in_flight, None)
+}
+
+/// Register the webhook route, marking events as coming from `integration` if given
+fn register(
+    router: Router,
+    config: JiraSourceConfig,
+    pipeline_tx: PipelineSender,
+    in_flight: Option<InFlightLimit>,
+    integration: Option<usize>,
 ) -> Result<Router> {
     // Resolve secret
     let secret = config.authentication.secret.resolve()?;
     
     // Create HMAC validator
-    let validator = HmacValidator::new(
-        secret,
+    let validator = HmacValidator::from_key(
+        config.authentication.secret_encoding.decode(&secret)?,
         config.authentication.header_name.clone(),
-    );
+    )
+    .with_fallback_headers(config.authentication.fallback_header_names.clone())
+    .with_format(config.authentication.signature_format)
+    .with_canonical_body(config.authentication.canonicalize_body);
+    
+    let auth_bypass_token = match &config.authentication.auth_bypass_secret {
+        Some(source) => {
+            let token = source.resolve()?;
+            if token.is_empty() {
+                return Err(AppError::Config("auth_bypass_secret resolved to an empty token".to_string()));
+            }
+            tracing::warn!("Signature bypass enabled: requests with a valid X-Auth-Bypass header skip HMAC validation");
+            Some(token)
+        }
+        None => None,
+    };
+    
+    // Bounded, as every failing request holds a connection for the whole floor
+    let auth_failure_floor = match config.authentication.auth_failure_min_ms {
+        Some(ms) if ms > MAX_AUTH_FAILURE_MIN_MS => {
+            return Err(AppError::Config(format!(
+                "auth_failure_min_ms is {} but at most {} is allowed", ms, MAX_AUTH_FAILURE_MIN_MS
+            )));
+        }
+        ms => ms.map(Duration::from_millis),
+    };
     
-    // Get supported events
-    let events = get_supported_events();
+    let events = configured_events(&config)?;
+    
+    // Selective parsing must still find the event type and primary key of every event
+    let selection = (!config.extract_paths.is_empty()).then(|| {
+        PathSelection::new(
+            config.extract_paths.iter()
+                .chain(&config.event_type_paths)
+                .chain(config.event_type_parts.iter().filter_map(|part| match part {
+                    EventTypePart::Path(path) => Some(path),
+                    EventTypePart::Header(_) => None,
+                }))
+                .chain(events.values().flat_map(|event| &event.pk_paths))
+                .map(String::as_str),
+        )
+    });
+    
+    let body_transform = config.body_transform.clone().map(MapperProcessor::new).transpose()?;
+    
+    let webhook_path = config.get_webhook_path();
+    let idle = config.idle_timeout_secs.map(|secs| IdleWatch::spawn("jira", &webhook_path, Duration::from_secs(secs)));
     
     let state = Arc::new(JiraWebhookState {
         validator,
+        auth_bypass_token,
+        auth_failure_floor,
         events,
         pipeline_tx,
+        only_changed_fields: config.only_changed_fields,
+        require_sink_ack: config.require_sink_ack,
+        enqueue_deadline: config.enqueue_deadline_ms.map(Duration::from_millis),
+        signature_query_param: config.authentication.query_param.clone(),
+        fixed_event_type: config.fixed_event_type.clone(),
+        event_type_paths: config.event_type_paths.clone(),
+        event_type_parts: config.event_type_parts.clone(),
+        event_type_separator: config.event_type_separator.clone(),
+        form_payload_field: config.form_payload_field.clone(),
+        selection,
+        required_headers: config.required_headers.clone(),
+        idle,
+        delivery_id_header: config.delivery_id_header.clone(),
+        allowed_events: config.allowed_events.iter().cloned().collect(),
+        max_body_depth: config.max_body_depth,
+        body_transform,
+        in_flight,
+        integration,
     });
     
-    let webhook_path = config.get_webhook_path();
     let router = router.route(
         &webhook_path,
         post(handle_jira_webhook).with_state(state),
@@ -46,5 +183,72 @@ pub fn register_jira_routes(
     Ok(router)
 }
 
+/// Supported events with the source's pk paths and overrides applied
+fn configured_events(config: &JiraSourceConfig) -> Result<HashMap<String, EventConfig>> {
+    let mut events = get_supported_events();
+    
+    // A fixed event type of our own has no built-in pk path to fall back on
+    if let Some(event_type) = &config.fixed_event_type {
+        if !events.contains_key(event_type) {
+            let paths = config.primary_key_paths.get(event_type).ok_or_else(|| {
+                AppError::Config(format!("fixed_event_type {} requires primary_key_paths for it", event_type))
+            })?;
+            
+            events.insert(event_type.clone(), EventConfig {
+                operation: Operation::Write,
+                get_field_id: Box::new(get_primary_key_by_paths(paths.clone())),
+                pk_paths: paths.clone(),
+                insert_only: false,
+            });
+        }
+    }
+    
+    // Composite event types are usually our own, registered by their primary key paths
+    if !config.event_type_parts.is_empty() {
+        for (event_type, paths) in &config.primary_key_paths {
+            events.entry(event_type.clone()).or_insert_with(|| EventConfig {
+                operation: Operation::Write,
+                get_field_id: Box::new(get_primary_key_by_paths(paths.clone())),
+                pk_paths: paths.clone(),
+                insert_only: false,
+            });
+        }
+    }
+    
+    // Apply configured pk fallback chains
+    for (event_type, paths) in &config.primary_key_paths {
+        let event_config = events
+            .get_mut(event_type)
+            .ok_or_else(|| AppError::Config(format!("primary_key_paths references unsupported event: {}", event_type)))?;
+        
+        if paths.is_empty() {
+            return Err(AppError::Config(format!("primary_key_paths for {} must not be empty", event_type)));
+        }
+        
+        event_config.get_field_id = Box::new(get_primary_key_by_paths(paths.clone()));
+        event_config.pk_paths = paths.clone();
+    }
+    
+    // Apply configured operation overrides
+    for (event_type, event_override) in &config.event_overrides {
+        let event_config = events
+            .get_mut(event_type)
+            .ok_or_else(|| AppError::Config(format!("event_overrides references unsupported event: {}", event_type)))?;
+        
+        if let Some(operation) = &event_override.operation {
+            event_config.operation = operation.clone();
+        }
+        event_config.insert_only = event_override.insert_only;
+    }
+    
+    for event_type in &config.allowed_events {
+        if !events.contains_key(event_type) {
+            return Err(AppError::Config(format!("allowed_events references unsupported event: {}", event_type)));
+        }
+    }
+    
+    Ok(events)
+}
+
 #[cfg(test)]
 mod tests;
@@ -1,15 +1,21 @@
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use crate::config::secret::SecretSource;
-    use crate::pipeline::create_pipeline_channel;
-    use crate::sources::jira::config::{JiraSourceConfig, JiraAuthentication};
-    use crate::sources::jira::register_jira_routes;
+    use crate::pipeline::{handoff, PipelineReceiver, PipelineSender};
+    use crate::pipeline::event::{Operation, PipelineEvent, PkField};
+    use crate::pipeline::sinks::{SinkOperation, SinkOutcome};
+    use crate::sources::jira::config::{JiraSourceConfig, JiraAuthentication, EventOverride, EventTypePart};
+    use crate::pipeline::in_flight::InFlightLimit;
+    use crate::sources::jira::{register_jira_routes, register_jira_routes_with_limit};
+    use crate::sources::webhook::hmac::{SecretEncoding, SignatureFormat};
     use axum::http::{Request, StatusCode};
     use axum::body::Body;
     use axum::Router;
     use tower::ServiceExt;
     use hmac::{Hmac, Mac};
     use sha2::Sha256;
+    use std::collections::HashMap;
     
     type HmacSha256 = Hmac<Sha256>;
     
@@ -19,6 +25,67 @@ mod tests {
         hex::encode(mac.finalize().into_bytes())
     }
     
+    fn test_config() -> JiraSourceConfig {
+        JiraSourceConfig {
+            webhook_path: Some("/jira/webhook".to_string()),
+            authentication: JiraAuthentication {
+                secret: SecretSource::Plain("test_secret".to_string()),
+                header_name: "X-Hub-Signature".to_string(),
+                fallback_header_names: vec![],
+                query_param: None,
+                signature_format: SignatureFormat::Prefixed,
+                canonicalize_body: false,
+                secret_encoding: SecretEncoding::Utf8,
+                auth_bypass_secret: None,
+                auth_failure_min_ms: None,
+            },
+            primary_key_paths: HashMap::new(),
+            event_overrides: HashMap::new(),
+            only_changed_fields: false,
+            require_sink_ack: false,
+            enqueue_deadline_ms: None,
+            delivery_id_header: "X-Atlassian-Webhook-Identifier".to_string(),
+            allowed_events: vec![],
+            max_body_depth: 100,
+            body_transform: None,
+            fixed_event_type: None,
+            event_type_paths: vec!["webhookEvent".to_string()],
+            event_type_parts: vec![],
+            event_type_separator: ".".to_string(),
+            form_payload_field: None,
+            extract_paths: vec![],
+            required_headers: HashMap::new(),
+            idle_timeout_secs: None,
+        }
+    }
+    
+    /// A pipeline channel whose events are accepted as the executor would, once there is room
+    fn create_pipeline_channel(buffer_size: usize) -> (PipelineSender, PipelineReceiver) {
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(buffer_size);
+        (tx, handoff::accept_handoffs(rx, buffer_size))
+    }
+    
+    async fn post_signed(app: Router, body: &str) -> StatusCode {
+        post_signed_response(app, body).await.status()
+    }
+    
+    async fn post_signed_response(app: Router, body: &str) -> axum::response::Response {
+        let signature = generate_signature("test_secret", body.as_bytes());
+        
+        app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/jira/webhook")
+                    .header("X-Hub-Signature", format!("sha256={}", signature))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+    }
+    
     #[tokio::test]
     async fn test_jira_issue_created() {
         let (tx, mut rx) = create_pipeline_channel(100);
@@ -28,7 +95,9 @@ mod tests {
             authentication: JiraAuthentication {
                 secret: SecretSource::Plain("test_secret".to_string()),
                 header_name: "X-Hub-Signature".to_string(),
+                ..test_config().authentication
             },
+            ..test_config()
         };
         
         let app = Router::new();
@@ -67,7 +136,9 @@ mod tests {
             authentication: JiraAuthentication {
                 secret: SecretSource::Plain("test_secret".to_string()),
                 header_name: "X-Hub-Signature".to_string(),
+                ..test_config().authentication
             },
+            ..test_config()
         };
         
         let app = Router::new();
@@ -100,7 +171,9 @@ mod tests {
             authentication: JiraAuthentication {
                 secret: SecretSource::Plain("test_secret".to_string()),
                 header_name: "X-Hub-Signature".to_string(),
+                ..test_config().authentication
             },
+            ..test_config()
         };
         
         let app = Router::new();
@@ -122,4 +195,809 @@ mod tests {
         
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
+    
+    #[tokio::test]
+    async fn test_jira_signature_from_fallback_header() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.authentication.header_name = "X-Hub-Signature-256".to_string();
+        config.authentication.fallback_header_names = vec!["X-Signature".to_string(), "X-Hub-Signature".to_string()];
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"12345","key":"TEST-123"}}"#;
+        
+        // Signed under the last configured name only
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::OK);
+        assert_eq!(rx.recv().await.unwrap().pk_fields[0].value, "12345");
+        
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/jira/webhook")
+                    .header("X-Other-Signature", format!("sha256={}", generate_signature("test_secret", body.as_bytes())))
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_signature_from_query_param() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.authentication.query_param = Some("signature".to_string());
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"12345","key":"TEST-123"}}"#;
+        let signature = generate_signature("test_secret", body.as_bytes());
+        let post = |uri: String| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+        };
+        
+        let response = post(format!("/jira/webhook?tenant=acme&signature=sha256%3D{}", signature)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(rx.recv().await.unwrap().pk_fields[0].value, "12345");
+        
+        let response = post("/jira/webhook?signature=sha256%3Dinvalid".to_string()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        
+        // The header is not looked at when a query parameter is configured
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_sprint_closed() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        let app = register_jira_routes(Router::new(), test_config(), tx).unwrap();
+        
+        let body = r#"{"timestamp":1700000000000,"webhookEvent":"sprint_closed","sprint":{"id":37,"self":"https://example.atlassian.net/rest/agile/1.0/sprint/37","state":"closed","name":"Sprint 12","originBoardId":5}}"#;
+        
+        assert_eq!(post_signed(app, body).await, StatusCode::OK);
+        
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.event_type, "sprint_closed");
+        assert_eq!(event.operation, Operation::Write);
+        assert_eq!(event.pk_fields[0].key, "sprint.id");
+        assert_eq!(event.pk_fields[0].value, "37");
+    }
+    
+    #[tokio::test]
+    async fn test_jira_board_deleted() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        let app = register_jira_routes(Router::new(), test_config(), tx).unwrap();
+        
+        let body = r#"{"timestamp":1700000000000,"webhookEvent":"board_deleted","board":{"id":5,"self":"https://example.atlassian.net/rest/agile/1.0/board/5","name":"TEST board","type":"scrum"}}"#;
+        
+        assert_eq!(post_signed(app, body).await, StatusCode::OK);
+        
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.event_type, "board_deleted");
+        assert_eq!(event.operation, Operation::Delete);
+        assert_eq!(event.pk_fields[0].key, "board.id");
+        assert_eq!(event.pk_fields[0].value, "5");
+    }
+    
+    #[tokio::test]
+    async fn test_jira_primary_key_fallback_from_config() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.primary_key_paths.insert(
+            "jira:issue_created".to_string(),
+            vec!["issue.id".to_string(), "issue.key".to_string()],
+        );
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"key":"TEST-123"}}"#;
+        
+        assert_eq!(post_signed(app, body).await, StatusCode::OK);
+        
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.pk_fields[0].key, "issue.key");
+        assert_eq!(event.pk_fields[0].value, "TEST-123");
+    }
+    
+    #[tokio::test]
+    async fn test_jira_batch_partial_success_report() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        let app = register_jira_routes(Router::new(), test_config(), tx).unwrap();
+        
+        let body = r#"[{"webhookEvent":"jira:issue_created","issue":{"id":"1"}},{"issue":{"id":"2"}}]"#;
+        let signature = generate_signature("test_secret", body.as_bytes());
+        
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/jira/webhook")
+                    .header("X-Hub-Signature", format!("sha256={}", signature))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+        
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        
+        assert_eq!(report["items"][0]["index"], 0);
+        assert_eq!(report["items"][0]["status"], "accepted");
+        assert_eq!(report["items"][1]["index"], 1);
+        assert_eq!(report["items"][1]["status"], "rejected");
+        assert_eq!(report["items"][1]["reason"], "Event type not found in payload");
+        
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.pk_fields[0].value, "1");
+        assert!(rx.try_recv().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_jira_malformed_json_is_counted() {
+        let (tx, _rx) = create_pipeline_channel(100);
+        let app = register_jira_routes(Router::new(), test_config(), tx).unwrap();
+        
+        let labels = [("source", "jira")];
+        let before = crate::metrics::global().counter("connectcare_webhook_parse_failures_total", &labels);
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":"#;
+        
+        assert_eq!(post_signed(app, body).await, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            crate::metrics::global().counter("connectcare_webhook_parse_failures_total", &labels),
+            before + 1
+        );
+    }
+    
+    #[tokio::test]
+    async fn test_jira_only_changed_fields() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.only_changed_fields = true;
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        let body = r#"{
+            "webhookEvent": "jira:issue_updated",
+            "timestamp": 1700000000000,
+            "issue": {"id": "10001", "key": "TEST-1", "fields": {"summary": "Big issue", "description": "Long text"}},
+            "changelog": {
+                "id": "555",
+                "items": [
+                    {"field": "status", "fieldtype": "jira", "from": "1", "fromString": "Open", "to": "3", "toString": "In Progress"},
+                    {"field": "assignee", "fieldtype": "jira", "from": null, "fromString": null, "to": "abc123", "toString": "Jane Doe"}
+                ]
+            }
+        }"#;
+        
+        assert_eq!(post_signed(app, body).await, StatusCode::OK);
+        
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.pk_fields[0].value, "10001");
+        assert_eq!(*event.body, serde_json::json!({
+            "webhookEvent": "jira:issue_updated",
+            "timestamp": 1700000000000u64,
+            "issue": {"id": "10001", "key": "TEST-1"},
+            "changedFields": {"status": "In Progress", "assignee": "Jane Doe"}
+        }));
+    }
+    
+    #[tokio::test]
+    async fn test_jira_require_sink_ack() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.require_sink_ack = true;
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        // Stand-in for the executor: the first event is persisted by a required sink, the second is not
+        tokio::spawn(async move {
+            let first = rx.recv().await.unwrap();
+            first.ack.as_ref().unwrap().acknowledge(SinkOutcome::default());
+            
+            let second = rx.recv().await.unwrap();
+            second.ack.as_ref().unwrap().fail();
+        });
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}"#;
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::OK);
+        assert_eq!(post_signed(app, body).await, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_require_sink_ack_returns_written_id() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.require_sink_ack = true;
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        tokio::spawn(async move {
+            let event = rx.recv().await.unwrap();
+            event.ack.as_ref().unwrap().acknowledge(SinkOutcome {
+                operation: Some(SinkOperation::Updated),
+                id: Some(serde_json::json!("65f1c0ffee00000000000001")),
+            });
+        });
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}"#;
+        let response = post_signed_response(app, body).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let outcome: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(outcome, serde_json::json!({"operation": "updated", "id": "65f1c0ffee00000000000001"}));
+    }
+    
+    #[tokio::test]
+    async fn test_jira_delivery_id_is_propagated() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        let app = register_jira_routes(Router::new(), test_config(), tx).unwrap();
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}"#;
+        let signature = generate_signature("test_secret", body.as_bytes());
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/jira/webhook")
+                    .header("X-Hub-Signature", format!("sha256={}", signature))
+                    .header("X-Atlassian-Webhook-Identifier", "delivery-42")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["x-delivery-id"], "delivery-42");
+        assert_eq!(rx.recv().await.unwrap().delivery_id, "delivery-42");
+        
+        // Without the header an id is generated, and still returned
+        let response = post_signed_response(app, body).await;
+        let generated = response.headers()["x-delivery-id"].to_str().unwrap().to_string();
+        assert!(!generated.is_empty());
+        assert_eq!(rx.recv().await.unwrap().delivery_id, generated);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_allowed_events() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.allowed_events = vec!["jira:issue_created".to_string()];
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        let allowed = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}"#;
+        assert_eq!(post_signed(app.clone(), allowed).await, StatusCode::OK);
+        assert_eq!(rx.recv().await.unwrap().event_type, "jira:issue_created");
+        
+        let disallowed = r#"{"webhookEvent":"jira:issue_deleted","issue":{"id":"1","key":"TEST-1"}}"#;
+        assert_eq!(post_signed(app, disallowed).await, StatusCode::BAD_REQUEST);
+        assert!(rx.try_recv().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_jira_allowed_events_must_be_supported() {
+        let (tx, _rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.allowed_events = vec!["jira:issue_creatd".to_string()];
+        
+        assert!(register_jira_routes(Router::new(), config, tx).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_jira_primary_key_from_header() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.primary_key_paths.insert(
+            "jira:issue_created".to_string(),
+            vec!["header:X-Resource-Id".to_string(), "issue.id".to_string()],
+        );
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}"#;
+        let signature = generate_signature("test_secret", body.as_bytes());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/jira/webhook")
+                    .header("X-Hub-Signature", format!("sha256={}", signature))
+                    .header("X-Resource-Id", "res-42")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        
+        let event = rx.recv().await.unwrap();
+        let pk = vec![PkField { key: "header:X-Resource-Id".to_string(), value: "res-42".to_string() }];
+        assert_eq!(event.pk_fields[0].value, "res-42");
+        assert_eq!(event.id, PipelineEvent::new(serde_json::json!({}), String::new(), pk, Operation::Write).id);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_rejects_over_deep_payload() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        let app = register_jira_routes(Router::new(), test_config(), tx).unwrap();
+        
+        // 110 nested objects: within serde_json's own limit, past the default max depth
+        let nested = format!("{}1{}", r#"{"a":"#.repeat(110), "}".repeat(110));
+        let body = format!(r#"{{"webhookEvent":"jira:issue_created","issue":{{"id":"1"}},"payload":{}}}"#, nested);
+        
+        assert_eq!(post_signed(app, &body).await, StatusCode::BAD_REQUEST);
+        assert!(rx.try_recv().is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_jira_body_transform_unwraps_envelope() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+    
+        let mut config = test_config();
+        config.body_transform = Some(serde_json::json!("{{ payload }}"));
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+    
+        let body = r#"{"provider":"relay","payload":{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}}"#;
+        assert_eq!(post_signed(app, body).await, StatusCode::OK);
+    
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.event_type, "jira:issue_created");
+        assert_eq!(event.pk_fields[0].value, "1");
+        assert_eq!(*event.body, serde_json::json!({"webhookEvent": "jira:issue_created", "issue": {"id": "1", "key": "TEST-1"}}));
+    }
+    
+    #[tokio::test]
+    async fn test_jira_fixed_event_type_for_automation_payloads() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+    
+        let mut config = test_config();
+        config.fixed_event_type = Some("automation:release_approved".to_string());
+        config.primary_key_paths.insert("automation:release_approved".to_string(), vec!["release.id".to_string()]);
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+    
+        // "Send web request" bodies are whatever the rule author wrote, with no webhookEvent
+        let body = r#"{"release":{"id":"42","approver":"jdoe"}}"#;
+        assert_eq!(post_signed(app, body).await, StatusCode::OK);
+    
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.event_type, "automation:release_approved");
+        assert_eq!(event.operation, Operation::Write);
+        assert_eq!(event.pk_fields[0].value, "42");
+    }
+    
+    #[tokio::test]
+    async fn test_jira_failure_is_recorded_under_the_resolved_event_type() {
+        let failures = crate::debug::FailureBuffer::scoped();
+        failures.configure(10, vec![]);
+        let (tx, _rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.fixed_event_type = Some("automation:release_approved".to_string());
+        config.primary_key_paths.insert("automation:release_approved".to_string(), vec!["release.id".to_string()]);
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        // The body claims another type, but the failure happened to the fixed one
+        let body = r#"{"webhookEvent":"jira:issue_created","release":{}}"#;
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::BAD_REQUEST);
+        
+        let records = failures.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].event_type.as_deref(), Some("automation:release_approved"));
+    }
+    
+    #[tokio::test]
+    async fn test_jira_event_type_falls_back_to_later_path() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+    
+        let mut config = test_config();
+        config.event_type_paths = vec!["webhookEvent".to_string(), "meta.eventType".to_string()];
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+    
+        let body = r#"{"meta":{"eventType":"jira:issue_created"},"issue":{"id":"1","key":"TEST-1"}}"#;
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::OK);
+        assert_eq!(rx.recv().await.unwrap().event_type, "jira:issue_created");
+    
+        // A non-string value doesn't count
+        let body = r#"{"webhookEvent":7,"meta":{"eventType":"jira:issue_updated"},"issue":{"id":"1","key":"TEST-1"}}"#;
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::OK);
+        assert_eq!(rx.recv().await.unwrap().event_type, "jira:issue_updated");
+    
+        let body = r#"{"meta":{},"issue":{"id":"1","key":"TEST-1"}}"#;
+        assert_eq!(post_signed(app, body).await, StatusCode::BAD_REQUEST);
+    }
+    
+    #[test]
+    fn test_jira_fixed_event_type_requires_pk_paths() {
+        let (tx, _rx) = crate::pipeline::create_pipeline_channel(100);
+    
+        let mut config = test_config();
+        config.fixed_event_type = Some("automation:release_approved".to_string());
+    
+        let err = register_jira_routes(Router::new(), config, tx).unwrap_err();
+        assert!(err.to_string().contains("automation:release_approved"));
+    }
+    
+    #[tokio::test]
+    async fn test_jira_form_encoded_payload() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+    
+        let mut config = test_config();
+        config.form_payload_field = Some("payload".to_string());
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+    
+        let payload = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1","fields":{"summary":"a & b = c"}}}"#;
+        let body = serde_urlencoded::to_string([("token", "ignored"), ("payload", payload)]).unwrap();
+        // The signature covers the form body as sent
+        let signature = generate_signature("test_secret", body.as_bytes());
+        let post = |body: String, signature: String| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/jira/webhook")
+                    .header("X-Hub-Signature", format!("sha256={}", signature))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+        };
+    
+        assert_eq!(post(body, signature).await.unwrap().status(), StatusCode::OK);
+    
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.event_type, "jira:issue_created");
+        assert_eq!(*event.body, serde_json::from_str::<serde_json::Value>(payload).unwrap());
+    
+        let without_payload = "token=ignored".to_string();
+        let signature = generate_signature("test_secret", without_payload.as_bytes());
+        assert_eq!(post(without_payload, signature).await.unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_selective_extraction_keeps_needed_paths() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+    
+        let mut config = test_config();
+        config.extract_paths = vec!["issue.fields.summary".to_string()];
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+    
+        let body = r#"{"webhookEvent":"jira:issue_created","user":{"name":"admin"},"issue":{"id":"1","key":"TEST-1","fields":{"summary":"s","description":"long"}}}"#;
+        assert_eq!(post_signed(app, body).await, StatusCode::OK);
+    
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.event_type, "jira:issue_created");
+        assert_eq!(event.pk_fields[0].value, "1");
+        assert_eq!(*event.body, serde_json::json!({
+            "webhookEvent": "jira:issue_created",
+            "issue": { "id": "1", "fields": { "summary": "s" } }
+        }));
+    }
+    
+    /// Post a signed issue event with the given extra headers
+    async fn post_with_headers(app: Router, extra: &[(&str, &str)]) -> StatusCode {
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}"#;
+        post_with_body_and_headers(app, body, extra).await
+    }
+    
+    /// Post a signed `body` with the given extra headers
+    async fn post_with_body_and_headers(app: Router, body: &'static str, extra: &[(&str, &str)]) -> StatusCode {
+        let signature = generate_signature("test_secret", body.as_bytes());
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/jira/webhook")
+            .header("X-Hub-Signature", format!("sha256={}", signature));
+        for (name, value) in extra {
+            request = request.header(*name, *value);
+        }
+        
+        app.oneshot(request.body(Body::from(body)).unwrap()).await.unwrap().status()
+    }
+    
+    #[tokio::test]
+    async fn test_jira_required_header_must_be_present() {
+        let (tx, _rx) = create_pipeline_channel(100);
+    
+        let mut config = test_config();
+        config.required_headers.insert("User-Agent".to_string(), None);
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+    
+        assert_eq!(post_with_headers(app.clone(), &[]).await, StatusCode::BAD_REQUEST);
+        assert_eq!(post_with_headers(app, &[("user-agent", "Atlassian Webhook HTTP Client")]).await, StatusCode::OK);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_required_header_must_match_value() {
+        let (tx, _rx) = create_pipeline_channel(100);
+    
+        let mut config = test_config();
+        config.required_headers.insert("X-Event-Key".to_string(), Some("jira:issue_created".to_string()));
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+    
+        assert_eq!(post_with_headers(app.clone(), &[]).await, StatusCode::BAD_REQUEST);
+        assert_eq!(post_with_headers(app.clone(), &[("X-Event-Key", "jira:issue_deleted")]).await, StatusCode::BAD_REQUEST);
+        assert_eq!(post_with_headers(app, &[("X-Event-Key", "jira:issue_created")]).await, StatusCode::OK);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_auth_bypass_skips_signature_with_correct_token() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+    
+        let mut config = test_config();
+        config.authentication.auth_bypass_secret = Some(SecretSource::Plain("staging-bypass".to_string()));
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+    
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}"#;
+        let post = |bypass: Option<&'static str>| {
+            let mut request = Request::builder().method("POST").uri("/jira/webhook");
+            if let Some(token) = bypass {
+                request = request.header("X-Auth-Bypass", token);
+            }
+            app.clone().oneshot(request.body(Body::from(body)).unwrap())
+        };
+    
+        assert_eq!(post(Some("staging-bypass")).await.unwrap().status(), StatusCode::OK);
+        assert_eq!(rx.recv().await.unwrap().event_type, "jira:issue_created");
+    
+        // Without the right token the signature is still required
+        assert_eq!(post(Some("wrong")).await.unwrap().status(), StatusCode::BAD_REQUEST);
+        assert_eq!(post(None).await.unwrap().status(), StatusCode::BAD_REQUEST);
+        assert_eq!(post_with_headers(app.clone(), &[("X-Auth-Bypass", "wrong")]).await, StatusCode::OK);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_auth_bypass_is_off_by_default() {
+        let (tx, _rx) = create_pipeline_channel(100);
+        let app = register_jira_routes(Router::new(), test_config(), tx).unwrap();
+    
+        let request = Request::builder()
+            .method("POST")
+            .uri("/jira/webhook")
+            .header("X-Auth-Bypass", "")
+            .body(Body::from(r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1"}}"#))
+            .unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_auth_failures_take_at_least_the_floor() {
+        let (tx, _rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.authentication.auth_failure_min_ms = Some(150);
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}"#;
+        let post = |signature: Option<&'static str>| {
+            let mut request = Request::builder().method("POST").uri("/jira/webhook");
+            if let Some(signature) = signature {
+                request = request.header("X-Hub-Signature", signature);
+            }
+            app.clone().oneshot(request.body(Body::from(body)).unwrap())
+        };
+        
+        // Missing, malformed and wrong signatures all wait out the floor
+        for (signature, status) in [
+            (None, StatusCode::BAD_REQUEST),
+            (Some("md5=abc"), StatusCode::BAD_REQUEST),
+            (Some("sha256=0000000000000000000000000000000000000000000000000000000000000000"), StatusCode::UNAUTHORIZED),
+        ] {
+            let started = std::time::Instant::now();
+            assert_eq!(post(signature).await.unwrap().status(), status);
+            assert!(started.elapsed() >= std::time::Duration::from_millis(150), "{:?} answered after {:?}", signature, started.elapsed());
+        }
+        
+        // Authentic requests aren't held
+        let started = std::time::Instant::now();
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::OK);
+        assert!(started.elapsed() < std::time::Duration::from_millis(150));
+        
+        // A missing required header waits it out too
+        let mut config = test_config();
+        config.authentication.auth_failure_min_ms = Some(150);
+        config.required_headers.insert("X-Event-Key".to_string(), None);
+        let (tx, _rx) = create_pipeline_channel(100);
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        let started = std::time::Instant::now();
+        assert_eq!(post_signed(app, body).await, StatusCode::BAD_REQUEST);
+        assert!(started.elapsed() >= std::time::Duration::from_millis(150));
+        
+        let mut config = test_config();
+        config.authentication.auth_failure_min_ms = Some(60_000);
+        let (tx, _rx) = create_pipeline_channel(100);
+        assert!(register_jira_routes(Router::new(), config, tx).is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_jira_composite_event_type_from_header_and_body() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+    
+        let mut config = test_config();
+        config.event_type_parts = vec![
+            EventTypePart::Header("X-GitHub-Event".to_string()),
+            EventTypePart::Path("action".to_string()),
+        ];
+        config.primary_key_paths.insert("pull_request.opened".to_string(), vec!["pull_request.id".to_string()]);
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+    
+        let body = r#"{"action":"opened","pull_request":{"id":42,"title":"Fix typo"}}"#;
+        assert_eq!(post_with_body_and_headers(app.clone(), body, &[("X-GitHub-Event", "pull_request")]).await, StatusCode::OK);
+    
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.event_type, "pull_request.opened");
+        assert_eq!(event.pk_fields[0].value, "42");
+    
+        // Unregistered combinations are ignored like other unsupported events, missing parts rejected
+        assert_eq!(post_with_body_and_headers(app.clone(), body, &[("X-GitHub-Event", "issues")]).await, StatusCode::OK);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(post_with_body_and_headers(app, body, &[]).await, StatusCode::BAD_REQUEST);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_sheds_events_beyond_in_flight_limit() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        let app = register_jira_routes_with_limit(Router::new(), test_config(), tx, Some(InFlightLimit::new(1))).unwrap();
+        let shed = || crate::metrics::global().counter("connectcare_events_shed_total", &[("source", "jira")]);
+        let before = shed();
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}"#;
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::OK);
+        
+        // The queued event still holds the only slot
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::TOO_MANY_REQUESTS);
+        assert!(shed() > before);
+        
+        // Processing the event frees it
+        drop(rx.recv().await.unwrap());
+        assert_eq!(post_signed(app, body).await, StatusCode::OK);
+        assert!(rx.recv().await.is_some());
+    }
+    
+    #[tokio::test]
+    async fn test_jira_batch_stops_when_events_are_shed() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        let app = register_jira_routes_with_limit(Router::new(), test_config(), tx, Some(InFlightLimit::new(1))).unwrap();
+        
+        let body = r#"[
+            {"webhookEvent":"jira:issue_created","issue":{"id":"1"}},
+            {"webhookEvent":"jira:issue_created","issue":{"id":"2"}},
+            {"webhookEvent":"jira:issue_created","issue":{"id":"3"}}
+        ]"#;
+        let response = post_signed_response(app, body).await;
+        
+        // The first event holds the only slot, the rest are to be sent again
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let statuses: Vec<_> = report["items"].as_array().unwrap().iter().map(|item| item["status"].clone()).collect();
+        assert_eq!(statuses, vec!["accepted", "busy", "busy"]);
+        
+        assert_eq!(rx.recv().await.unwrap().pk_fields[0].value, "1");
+    }
+    
+    #[tokio::test]
+    async fn test_jira_enqueue_deadline() {
+        let (tx, mut rx) = create_pipeline_channel(1);
+        
+        let mut config = test_config();
+        config.enqueue_deadline_ms = Some(50);
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"TEST-1"}}"#;
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::ACCEPTED);
+        
+        // Nothing drains the queue, so the second event can't be queued before the deadline
+        let started = std::time::Instant::now();
+        assert_eq!(post_signed(app.clone(), body).await, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        
+        rx.recv().await.unwrap();
+        assert_eq!(post_signed(app, body).await, StatusCode::ACCEPTED);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_batch_reports_items_left_unqueued() {
+        let (tx, mut rx) = create_pipeline_channel(1);
+        
+        let mut config = test_config();
+        config.enqueue_deadline_ms = Some(50);
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        let body = r#"[
+            {"webhookEvent":"jira:issue_created","issue":{"id":"1"}},
+            {"webhookEvent":"jira:issue_created","issue":{"id":"2"}},
+            {"webhookEvent":"jira:issue_created","issue":{"id":"3"}}
+        ]"#;
+        let response = post_signed_response(app.clone(), body).await;
+        
+        // Only the first fits in the queue, and only the others need resending
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let statuses: Vec<_> = report["items"].as_array().unwrap().iter().map(|item| item["status"].clone()).collect();
+        assert_eq!(statuses, vec!["accepted", "busy", "busy"]);
+        
+        assert_eq!(rx.recv().await.unwrap().pk_fields[0].value, "1");
+        let next = tokio::time::timeout(std::time::Duration::from_millis(100), rx.recv()).await;
+        assert!(next.is_err(), "an unqueued item was processed");
+    }
+    
+    #[tokio::test]
+    async fn test_jira_batch_fails_items_when_the_executor_is_gone() {
+        let (tx, rx) = crate::pipeline::create_pipeline_channel(10);
+        let app = register_jira_routes(Router::new(), test_config(), tx).unwrap();
+        drop(rx);
+        
+        let body = r#"[
+            {"webhookEvent":"jira:issue_created","issue":{"id":"1"}},
+            {"issue":{"id":"2"}}
+        ]"#;
+        let response = post_signed_response(app, body).await;
+        
+        // Lost events must be redelivered, so the batch isn't answered as a client error
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let statuses: Vec<_> = report["items"].as_array().unwrap().iter().map(|item| item["status"].clone()).collect();
+        assert_eq!(statuses, vec!["failed", "rejected"]);
+    }
+    
+    #[tokio::test]
+    async fn test_jira_operation_override() {
+        let (tx, mut rx) = create_pipeline_channel(100);
+        
+        let mut config = test_config();
+        config.event_overrides.insert(
+            "jira:issue_updated".to_string(),
+            EventOverride { operation: Some(Operation::Delete), insert_only: false },
+        );
+        config.event_overrides.insert(
+            "jira:issue_created".to_string(),
+            EventOverride { operation: None, insert_only: true },
+        );
+        let app = register_jira_routes(Router::new(), config, tx).unwrap();
+        
+        let updated = r#"{"webhookEvent":"jira:issue_updated","issue":{"id":"1","key":"TEST-1"}}"#;
+        assert_eq!(post_signed(app.clone(), updated).await, StatusCode::OK);
+        
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.operation, Operation::Delete);
+        assert!(!event.insert_only);
+        
+        let created = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"2","key":"TEST-2"}}"#;
+        assert_eq!(post_signed(app, created).await, StatusCode::OK);
+        
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.operation, Operation::Write);
+        assert!(event.insert_only);
+    }
+    
+    #[test]
+    fn test_jira_operation_override_rejects_unknown_event() {
+        let (tx, _rx) = crate::pipeline::create_pipeline_channel(1);
+        
+        let mut config = test_config();
+        config.event_overrides.insert("jira:unknown".to_string(), EventOverride::default());
+        
+        assert!(register_jira_routes(Router::new(), config, tx).is_err());
+    }
 }
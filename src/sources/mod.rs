@@ -1,2 +1,188 @@
+pub mod idle;
 pub mod jira;
 pub mod webhook;
+
+use axum::Router;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::config::SourceConfig;
+use crate::error::{AppError, Result};
+use crate::pipeline::PipelineSender;
+use crate::pipeline::event::Operation;
+use crate::pipeline::in_flight::InFlightLimit;
+use crate::server::routes::join_path;
+
+/// What a source gets from the server when registering its routes
+#[derive(Clone)]
+pub struct SourceContext {
+    pub pipeline_tx: PipelineSender,
+    /// Prefix for every webhook path, see `AppConfig::base_path`
+    pub base_path: Option<String>,
+    /// Shared cap on events in flight, see `AppConfig::max_in_flight`
+    pub in_flight: Option<InFlightLimit>,
+    /// Index of the integration whose source is registered, set on its events so they follow
+    /// the integration's routes
+    pub integration: usize,
+}
+
+impl SourceContext {
+    /// A webhook path under the base path, if any
+    pub fn path(&self, path: &str) -> String {
+        match &self.base_path {
+            Some(base_path) => join_path(base_path, path),
+            None => path.to_string(),
+        }
+    }
+}
+
+/// An event type a source accepts, as listed by `/-/routes`
+#[derive(Debug, Clone, Serialize)]
+pub struct EventRoute {
+    pub event_type: String,
+    /// Candidate primary key paths, in order
+    pub pk_paths: Vec<String>,
+    pub operation: Operation,
+}
+
+/// A kind of webhook source, selected by the `type` tag of an integration's source
+pub trait Source: Send + Sync {
+    /// Add the routes of one integration's source, sending its events to `context.pipeline_tx`
+    fn register_routes(&self, router: Router, config: &SourceConfig, context: &SourceContext) -> Result<Router>;
+
+    /// Full paths `register_routes` would add, checked for clashes with the other integrations'
+    /// and the `/-/*` routes first
+    fn webhook_paths(&self, config: &SourceConfig, context: &SourceContext) -> Vec<String>;
+
+    /// Event types accepted by the source, for `/-/routes`
+    fn events(&self, _config: &SourceConfig) -> Result<Vec<EventRoute>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Source implementations by `type` tag
+///
+/// The default registry knows the built-in sources; more can be added with `register` before
+/// building the router.
+#[derive(Clone)]
+pub struct SourceRegistry {
+    sources: HashMap<String, Arc<dyn Source>>,
+}
+
+impl SourceRegistry {
+    /// A registry without any source
+    pub fn empty() -> Self {
+        Self { sources: HashMap::new() }
+    }
+
+    /// Handle sources with this `type` tag, replacing any previous registration
+    pub fn register(&mut self, source_type: impl Into<String>, source: impl Source + 'static) -> &mut Self {
+        self.sources.insert(source_type.into(), Arc::new(source));
+        self
+    }
+
+    pub fn register_routes(&self, router: Router, config: &SourceConfig, context: &SourceContext) -> Result<Router> {
+        self.source(config)?.register_routes(router, config, context)
+    }
+
+    pub fn webhook_paths(&self, config: &SourceConfig, context: &SourceContext) -> Result<Vec<String>> {
+        Ok(self.source(config)?.webhook_paths(config, context))
+    }
+
+    pub fn events(&self, config: &SourceConfig) -> Result<Vec<EventRoute>> {
+        self.source(config)?.events(config)
+    }
+
+    fn source(&self, config: &SourceConfig) -> Result<&Arc<dyn Source>> {
+        self.sources
+            .get(config.source_type())
+            .ok_or_else(|| AppError::Config(format!("Unknown source type: {}", config.source_type())))
+    }
+}
+
+impl Default for SourceRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("jira", jira::JiraSource);
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::create_pipeline_channel;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::routing::post;
+    use serde::Deserialize;
+    use tower::ServiceExt;
+
+    /// Source answering `204` on its configured path
+    struct DummySource;
+
+    #[derive(Deserialize)]
+    struct DummySettings {
+        path: String,
+    }
+
+    impl Source for DummySource {
+        fn register_routes(&self, router: Router, config: &SourceConfig, context: &SourceContext) -> Result<Router> {
+            let SourceConfig::Custom { settings, .. } = config else {
+                return Err(AppError::Config("dummy source expects custom settings".to_string()));
+            };
+            let settings: DummySettings = serde_json::from_value(settings.clone().into())?;
+
+            Ok(router.route(&context.path(&settings.path), post(|| async { StatusCode::NO_CONTENT })))
+        }
+
+        fn webhook_paths(&self, config: &SourceConfig, context: &SourceContext) -> Vec<String> {
+            match config {
+                SourceConfig::Custom { settings, .. } => serde_json::from_value::<DummySettings>(settings.clone().into())
+                    .map(|settings| vec![context.path(&settings.path)])
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_source_type_registers_routes() {
+        let config: SourceConfig = serde_json::from_value(serde_json::json!({
+            "type": "dummy",
+            "path": "/dummy/webhook"
+        }))
+        .unwrap();
+        let (pipeline_tx, _rx) = create_pipeline_channel(1);
+        let context = SourceContext { pipeline_tx, base_path: Some("/webhooks".to_string()), in_flight: None, integration: 0 };
+
+        let mut registry = SourceRegistry::default();
+        registry.register("dummy", DummySource);
+        let router = registry.register_routes(Router::new(), &config, &context).unwrap();
+
+        let response = router
+            .oneshot(Request::builder().method("POST").uri("/webhooks/dummy/webhook").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    }
+
+    #[test]
+    fn test_unregistered_source_type_is_rejected() {
+        let config: SourceConfig = serde_json::from_value(serde_json::json!({ "type": "gitlab" })).unwrap();
+        let (pipeline_tx, _rx) = create_pipeline_channel(1);
+        let context = SourceContext { pipeline_tx, base_path: None, in_flight: None, integration: 0 };
+
+        let error = SourceRegistry::default().register_routes(Router::new(), &config, &context).unwrap_err();
+        assert!(error.to_string().contains("gitlab"));
+    }
+
+    #[test]
+    fn test_source_config_keeps_its_type_tag() {
+        let json = serde_json::json!({ "type": "dummy", "path": "/dummy/webhook" });
+        let config: SourceConfig = serde_json::from_value(json.clone()).unwrap();
+
+        assert_eq!(config.source_type(), "dummy");
+        assert_eq!(serde_json::to_value(config).unwrap(), json);
+    }
+}
@@ -0,0 +1,83 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+use crate::pipeline::sinks::SinkOutcome;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchItemStatus {
+    /// Sent to the pipeline
+    Accepted,
+    /// Valid but not configured for processing, same as a single unconfigured event
+    Ignored,
+    /// Failed validation or extraction
+    Rejected,
+    /// Failed on the server's side, e.g. the executor had stopped; to be sent again
+    Failed,
+    /// Not queued because the pipelines had no room; only these items need sending again
+    Busy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub status: BatchItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// What the required sink wrote, when the source waits for one
+    #[serde(flatten)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<SinkOutcome>,
+}
+
+/// Per-item outcome of a webhook whose body is a JSON array of events
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchReport {
+    pub items: Vec<BatchItemResult>,
+}
+
+impl BatchReport {
+    pub fn push(&mut self, status: BatchItemStatus, reason: Option<String>) {
+        let index = self.items.len();
+        self.items.push(BatchItemResult { index, status, reason, outcome: None });
+    }
+
+    /// An accepted item a required sink has written
+    pub fn push_written(&mut self, outcome: SinkOutcome) {
+        self.push(BatchItemStatus::Accepted, None);
+        if let Some(item) = self.items.last_mut() {
+            item.outcome = Some(outcome);
+        }
+    }
+
+    /// `503` when an item was busy, `500` when one failed, else `200` when nothing was rejected,
+    /// `400` when everything was, `207` otherwise
+    pub fn status_code(&self) -> StatusCode {
+        if self.items.iter().any(|item| item.status == BatchItemStatus::Busy) {
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+        if self.items.iter().any(|item| item.status == BatchItemStatus::Failed) {
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+
+        let rejected = self.items
+            .iter()
+            .filter(|item| item.status == BatchItemStatus::Rejected)
+            .count();
+
+        if rejected == 0 {
+            StatusCode::OK
+        } else if rejected == self.items.len() {
+            StatusCode::BAD_REQUEST
+        } else {
+            StatusCode::MULTI_STATUS
+        }
+    }
+}
+
+impl IntoResponse for BatchReport {
+    fn into_response(self) -> Response {
+        (self.status_code(), Json(self)).into_response()
+    }
+}
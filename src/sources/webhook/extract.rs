@@ -0,0 +1,216 @@
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Body paths to keep when parsing a payload selectively
+///
+/// Paths use the same syntax as the rest of the configuration: dot-separated keys, or a JSON
+/// pointer when starting with `/`. Array elements are addressed by index.
+#[derive(Debug, Clone, Default)]
+pub struct PathSelection {
+    children: HashMap<String, PathSelection>,
+    /// The whole value at this node is kept
+    whole: bool,
+}
+
+impl PathSelection {
+    pub fn new<'a>(paths: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut selection = Self::default();
+        for path in paths {
+            selection.insert(path);
+        }
+        selection
+    }
+
+    fn insert(&mut self, path: &str) {
+        let segments: Vec<String> = match path.strip_prefix('/') {
+            Some(pointer) => pointer.split('/').map(|s| s.replace("~1", "/").replace("~0", "~")).collect(),
+            None => path.split('.').map(str::to_string).collect(),
+        };
+
+        let mut node = self;
+        for segment in segments {
+            if node.whole {
+                return;
+            }
+            node = node.children.entry(segment).or_default();
+        }
+        node.whole = true;
+        node.children.clear();
+    }
+}
+
+/// Parse `bytes`, materializing only the values at the selected paths
+///
+/// Everything else is skipped while reading, so it never takes memory. A top-level array is read
+/// as a batch, the selection applying to each of its elements. Skipped array elements of a
+/// selected array are kept as `null`, so indexes still line up.
+pub fn from_slice(bytes: &[u8], selection: &PathSelection) -> serde_json::Result<Value> {
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let value = Selected { selection, batch: true }.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+struct Selected<'a> {
+    selection: &'a PathSelection,
+    /// Arrays at this level are batches of bodies rather than indexed values
+    batch: bool,
+}
+
+impl<'de> DeserializeSeed<'de> for Selected<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        if self.selection.whole {
+            return serde::Deserialize::deserialize(deserializer);
+        }
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Selected<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut selected = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match self.selection.children.get(&key) {
+                Some(child) => {
+                    let value = map.next_value_seed(Selected { selection: child, batch: false })?;
+                    selected.insert(key, value);
+                }
+                None => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(Value::Object(selected))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        loop {
+            let element = if self.batch {
+                seq.next_element_seed(Selected { selection: self.selection, batch: false })?
+            } else {
+                match self.selection.children.get(&items.len().to_string()) {
+                    Some(child) => seq.next_element_seed(Selected { selection: child, batch: false })?,
+                    None => seq.next_element::<IgnoredAny>()?.map(|_| Value::Null),
+                }
+            };
+            match element {
+                Some(value) => items.push(value),
+                None => return Ok(Value::Array(items)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Number of values in `value`, counting every nested one
+    fn count_values(value: &Value) -> usize {
+        1 + match value {
+            Value::Object(map) => map.values().map(count_values).sum(),
+            Value::Array(items) => items.iter().map(count_values).sum(),
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_keeps_only_selected_paths() {
+        let body = json!({
+            "webhookEvent": "jira:issue_updated",
+            "issue": { "id": "10001", "key": "PROJ-1", "fields": { "summary": "s", "labels": ["a", "b"] } },
+            "changelog": { "items": [{ "field": "status" }, { "field": "summary" }] },
+            "user": { "name": "admin" }
+        });
+        let selection = PathSelection::new(["webhookEvent", "issue.id", "/issue/fields/labels", "changelog.items.1.field"]);
+
+        let selected = from_slice(&serde_json::to_vec(&body).unwrap(), &selection).unwrap();
+
+        assert_eq!(selected, json!({
+            "webhookEvent": "jira:issue_updated",
+            "issue": { "id": "10001", "fields": { "labels": ["a", "b"] } },
+            "changelog": { "items": [null, { "field": "summary" }] }
+        }));
+    }
+
+    #[test]
+    fn test_top_level_array_is_selected_per_body() {
+        let batch = br#"[{"webhookEvent":"a","issue":{"id":"1","key":"K-1"}},{"webhookEvent":"b","issue":{"id":"2"}}]"#;
+
+        let selected = from_slice(batch, &PathSelection::new(["webhookEvent", "issue.id"])).unwrap();
+
+        assert_eq!(selected, json!([
+            { "webhookEvent": "a", "issue": { "id": "1" } },
+            { "webhookEvent": "b", "issue": { "id": "2" } }
+        ]));
+        assert!(from_slice(br#"{"webhookEvent": "a"} trailing"#, &PathSelection::new(["webhookEvent"])).is_err());
+        assert!(from_slice(br#"{"webhookEvent": "a", "issue": {"#, &PathSelection::new(["webhookEvent"])).is_err());
+    }
+
+    #[test]
+    fn test_selective_parse_of_large_payload_materializes_a_fraction() {
+        let comments: Vec<Value> = (0..5_000)
+            .map(|i| json!({ "id": i.to_string(), "body": "x".repeat(200), "author": { "name": format!("user{}", i) } }))
+            .collect();
+        let payload = serde_json::to_vec(&json!({
+            "webhookEvent": "jira:issue_updated",
+            "issue": { "id": "10001", "fields": { "summary": "Large issue", "comment": { "comments": comments } } }
+        }))
+        .unwrap();
+        let selection = PathSelection::new(["webhookEvent", "issue.id", "issue.fields.summary"]);
+
+        let full: Value = serde_json::from_slice(&payload).unwrap();
+        let selected = from_slice(&payload, &selection).unwrap();
+
+        let selected_values = count_values(&selected);
+        let (full_bytes, selected_bytes) = (serde_json::to_vec(&full).unwrap().len(), serde_json::to_vec(&selected).unwrap().len());
+
+        assert_eq!(selected_values, 6);
+        assert!(selected_bytes * 1_000 < full_bytes);
+        assert_eq!(selected["issue"]["fields"]["summary"], full["issue"]["fields"]["summary"]);
+    }
+}
@@ -1,46 +1,169 @@
+use base64::Engine;
 use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use sha2::Sha256;
 use subtle::ConstantTimeEq;
 use crate::error::{AppError, Result};
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// How the signature is encoded in the header value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureFormat {
+    /// `sha256=<hex>`
+    #[default]
+    Prefixed,
+    /// Bare `<hex>` digest
+    Raw,
+}
+
+/// How the resolved secret string is turned into the HMAC key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecretEncoding {
+    /// The secret's UTF-8 bytes, used as is
+    #[default]
+    Utf8,
+    /// Standard base64, ignoring surrounding whitespace
+    Base64,
+    /// Hex digits, ignoring surrounding whitespace
+    Hex,
+}
+
+impl SecretEncoding {
+    pub fn decode(self, secret: &str) -> Result<Vec<u8>> {
+        match self {
+            SecretEncoding::Utf8 => Ok(secret.as_bytes().to_vec()),
+            SecretEncoding::Base64 => base64::engine::general_purpose::STANDARD
+                .decode(secret.trim())
+                .map_err(|e| AppError::Config(format!("HMAC secret is not valid base64: {}", e))),
+            SecretEncoding::Hex => hex::decode(secret.trim())
+                .map_err(|e| AppError::Config(format!("HMAC secret is not valid hex: {}", e))),
+        }
+    }
+}
+
 pub struct HmacValidator {
-    secret: String,
-    header_name: String,
+    key: Vec<u8>,
+    /// Headers the signature is read from, the first present one wins
+    header_names: Vec<String>,
+    format: SignatureFormat,
+    canonicalize_body: bool,
+}
+
+/// Lowercase hex HMAC-SHA256 of `data` under `key`
+pub fn hex_digest(key: &[u8], data: &[u8]) -> String {
+    // HMAC accepts keys of any length
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC key of any length");
+    mac.update(data);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Compact JSON with object keys sorted, as signed by providers that MAC a canonical form
+pub fn canonical_json(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_canonical(value, &mut out);
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            
+            out.push(b'{');
+            for (idx, key) in keys.into_iter().enumerate() {
+                if idx > 0 {
+                    out.push(b',');
+                }
+                out.extend_from_slice(Value::String(key.clone()).to_string().as_bytes());
+                out.push(b':');
+                write_canonical(&map[key], out);
+            }
+            out.push(b'}');
+        }
+        Value::Array(items) => {
+            out.push(b'[');
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    out.push(b',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(b']');
+        }
+        scalar => out.extend_from_slice(scalar.to_string().as_bytes()),
+    }
 }
 
 impl HmacValidator {
     pub fn new(secret: String, header_name: String) -> Self {
-        Self { secret, header_name }
+        Self::from_key(secret.into_bytes(), header_name)
+    }
+    
+    /// Validator keyed with raw bytes, e.g. a secret decoded with [`SecretEncoding::decode`]
+    pub fn from_key(key: Vec<u8>, header_name: String) -> Self {
+        Self {
+            key,
+            header_names: vec![header_name],
+            format: SignatureFormat::default(),
+            canonicalize_body: false,
+        }
+    }
+    
+    pub fn with_format(mut self, format: SignatureFormat) -> Self {
+        self.format = format;
+        self
+    }
+    
+    /// Compute the MAC over the canonical JSON form of the body instead of its raw bytes
+    ///
+    /// This parses the body before it is authenticated, so malformed or oversized JSON from
+    /// unauthenticated senders is processed before being rejected.
+    pub fn with_canonical_body(mut self, canonicalize_body: bool) -> Self {
+        self.canonicalize_body = canonicalize_body;
+        self
     }
     
-    pub fn header_name(&self) -> &str {
-        &self.header_name
+    /// Also look for the signature in these headers, in order, when the main one is missing
+    pub fn with_fallback_headers(mut self, header_names: Vec<String>) -> Self {
+        self.header_names.extend(header_names);
+        self
+    }
+    
+    pub fn header_names(&self) -> &[String] {
+        &self.header_names
     }
     
     pub fn validate(&self, body: &[u8], signature_header: &str) -> Result<()> {
         tracing::debug!("Validating HMAC signature. Header: {}", signature_header);
         
-        let signature = signature_header
-            .strip_prefix("sha256=")
-            .ok_or_else(|| {
-                tracing::error!("Invalid signature format. Expected 'sha256=<hex>', got: {}", signature_header);
-                AppError::InvalidSignatureFormat
-            })?;
+        let signature = match self.format {
+            SignatureFormat::Prefixed => signature_header
+                .strip_prefix("sha256=")
+                .ok_or_else(|| {
+                    tracing::error!("Invalid signature format. Expected 'sha256=<hex>', got: {}", signature_header);
+                    AppError::InvalidSignatureFormat
+                })?,
+            SignatureFormat::Raw => signature_header,
+        };
         
         tracing::debug!("Extracted signature: {}", signature);
+        
+        if self.canonicalize_body {
+            // A body that isn't JSON can't match a signature over canonical JSON
+            let value: Value = serde_json::from_slice(body).map_err(|_| AppError::HmacValidation)?;
+            return self.validate_body(&canonical_json(&value), signature);
+        }
+        
         self.validate_body(body, signature)
     }
     
     fn validate_body(&self, body: &[u8], expected_signature: &str) -> Result<()> {
-        let mut mac = HmacSha256::new_from_slice(self.secret.as_bytes())
-            .map_err(|_| AppError::Config("Invalid HMAC secret".to_string()))?;
-        
-        mac.update(body);
-        let result = mac.finalize();
-        let code_bytes = result.into_bytes();
-        let computed_signature = hex::encode(code_bytes);
+        let computed_signature = hex_digest(&self.key, body);
         
         tracing::debug!("Computed signature: {}", computed_signature);
         tracing::debug!("Expected signature: {}", expected_signature);
@@ -85,4 +208,66 @@ mod tests {
         let result = validator.validate(b"test body", "sha256=wrongsignature");
         assert!(result.is_err());
     }
+    
+    #[test]
+    fn test_hmac_validation_raw_signature() {
+        let secret = "test_secret";
+        let body = b"test body content";
+        
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        let expected = hex::encode(mac.finalize().into_bytes());
+        
+        let validator = HmacValidator::new(secret.to_string(), "X-Signature".to_string())
+            .with_format(SignatureFormat::Raw);
+        
+        assert!(validator.validate(body, &expected).is_ok());
+        assert!(validator.validate(body, &format!("sha256={}", expected)).is_err());
+    }
+    
+    #[test]
+    fn test_hmac_validation_over_canonical_body() {
+        let secret = "test_secret";
+        let body = br#"{ "b": 1, "a": { "d": [1, { "z": true, "y": null }], "c": "x\"y" } }"#;
+        let canonical = br#"{"a":{"c":"x\"y","d":[1,{"y":null,"z":true}]},"b":1}"#;
+        
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(canonical);
+        let signature_header = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+        
+        let raw = HmacValidator::new(secret.to_string(), "X-Hub-Signature".to_string());
+        assert!(raw.validate(body, &signature_header).is_err());
+        
+        let canonicalizing = HmacValidator::new(secret.to_string(), "X-Hub-Signature".to_string())
+            .with_canonical_body(true);
+        assert!(canonicalizing.validate(body, &signature_header).is_ok());
+        assert!(canonicalizing.validate(b"not json", &signature_header).is_err());
+    }
+    
+    #[test]
+    fn test_hmac_validation_with_base64_secret() {
+        let key = b"\x00\xffbinary key";
+        let encoded = base64::engine::general_purpose::STANDARD.encode(key);
+        let body = b"test body content";
+        
+        let mut mac = HmacSha256::new_from_slice(key).unwrap();
+        mac.update(body);
+        let signature_header = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+        
+        let decoded = SecretEncoding::Base64.decode(&format!("{}\n", encoded)).unwrap();
+        let validator = HmacValidator::from_key(decoded, "X-Hub-Signature".to_string());
+        assert!(validator.validate(body, &signature_header).is_ok());
+        
+        // Used as plain text, the encoded secret is a different key
+        let utf8 = HmacValidator::new(encoded, "X-Hub-Signature".to_string());
+        assert!(utf8.validate(body, &signature_header).is_err());
+    }
+    
+    #[test]
+    fn test_secret_encoding_decode() {
+        assert_eq!(SecretEncoding::Utf8.decode(" abc ").unwrap(), b" abc ");
+        assert_eq!(SecretEncoding::Hex.decode("00ff\n").unwrap(), vec![0x00, 0xff]);
+        assert!(SecretEncoding::Hex.decode("xyz").is_err());
+        assert!(SecretEncoding::Base64.decode("not base64!").is_err());
+    }
 }
@@ -1,2 +1,4 @@
+pub mod batch;
+pub mod extract;
 pub mod hmac;
 pub mod types;
@@ -1,8 +1,16 @@
+use axum::http::HeaderMap;
 use serde_json::Value;
 use crate::error::{AppError, Result};
 use crate::pipeline::event::{PkField, PkFields};
 
+/// Resolve a dotted path (`issue.id`) or, when it starts with `/`, an RFC 6901 JSON Pointer (`/issue/id`)
 pub fn extract_value_by_path<'a>(body: &'a Value, path: &str) -> Result<&'a Value> {
+    if path.starts_with('/') {
+        return body
+            .pointer(path)
+            .ok_or_else(|| AppError::PrimaryKeyPathNotFound(path.to_string()));
+    }
+    
     let mut current = body;
     
     for segment in path.split('.') {
@@ -14,25 +22,72 @@ pub fn extract_value_by_path<'a>(body: &'a Value, path: &str) -> Result<&'a Valu
     Ok(current)
 }
 
-pub fn get_primary_key_by_path(path: &'static str) -> impl Fn(&Value) -> Result<PkFields> {
-    move |body: &Value| -> Result<PkFields> {
-        let value = extract_value_by_path(body, path)?;
-        
-        // Convert value to string
-        let value_str = match value {
-            Value::String(s) => s.clone(),
-            Value::Number(n) => n.to_string(),
-            Value::Bool(b) => b.to_string(),
-            _ => value.to_string(),
+/// Whether objects and arrays in `value` nest deeper than `max_depth`, a scalar being depth 0
+///
+/// Walks the value with an explicit stack, so the check itself can't overflow on the payloads
+/// it is meant to catch.
+pub fn exceeds_depth(value: &Value, max_depth: usize) -> bool {
+    let mut stack = vec![(value, 0)];
+    
+    while let Some((value, depth)) = stack.pop() {
+        let children: Box<dyn Iterator<Item = &Value>> = match value {
+            Value::Object(map) => Box::new(map.values()),
+            Value::Array(items) => Box::new(items.iter()),
+            _ => continue,
         };
         
+        if depth + 1 > max_depth {
+            return true;
+        }
+        stack.extend(children.map(|child| (child, depth + 1)));
+    }
+    
+    false
+}
+
+/// Prefix of pk candidates read from a request header instead of the body, e.g. `header:X-Resource-Id`
+pub const HEADER_PK_PREFIX: &str = "header:";
+
+pub fn get_primary_key_by_path(path: &'static str) -> impl Fn(&Value, &HeaderMap) -> Result<PkFields> {
+    get_primary_key_by_paths(vec![path.to_string()])
+}
+
+/// Build a pk extractor that tries each candidate in order and uses the first that resolves
+///
+/// Candidates are body paths, or header names prefixed with `header:`.
+pub fn get_primary_key_by_paths(paths: Vec<String>) -> impl Fn(&Value, &HeaderMap) -> Result<PkFields> {
+    move |body: &Value, headers: &HeaderMap| -> Result<PkFields> {
+        let (path, value_str) = paths
+            .iter()
+            .find_map(|path| resolve_candidate(body, headers, path).map(|value| (path, value)))
+            .ok_or_else(|| AppError::PrimaryKeyPathNotFound(paths.join(" | ")))?;
+        
         Ok(vec![PkField {
-            key: path.to_string(),
+            key: path.clone(),
             value: value_str,
         }])
     }
 }
 
+fn resolve_candidate(body: &Value, headers: &HeaderMap, candidate: &str) -> Option<String> {
+    if let Some(header) = candidate.strip_prefix(HEADER_PK_PREFIX) {
+        return headers
+            .get(header)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty())
+            .map(str::to_string);
+    }
+    
+    // Convert value to string
+    let value = extract_value_by_path(body, candidate).ok()?;
+    Some(match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        _ => value.to_string(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,10 +115,73 @@ mod tests {
         });
         
         let extractor = get_primary_key_by_path("issue.id");
-        let pk_fields = extractor(&body).unwrap();
+        let pk_fields = extractor(&body, &HeaderMap::new()).unwrap();
         
         assert_eq!(pk_fields.len(), 1);
         assert_eq!(pk_fields[0].key, "issue.id");
         assert_eq!(pk_fields[0].value, "12345");
     }
+    
+    #[test]
+    fn test_get_primary_key_by_paths_uses_fallback() {
+        let body = json!({
+            "issue": {
+                "key": "TEST-123"
+            }
+        });
+        
+        let extractor = get_primary_key_by_paths(vec!["issue.id".to_string(), "issue.key".to_string()]);
+        let pk_fields = extractor(&body, &HeaderMap::new()).unwrap();
+        
+        assert_eq!(pk_fields.len(), 1);
+        assert_eq!(pk_fields[0].key, "issue.key");
+        assert_eq!(pk_fields[0].value, "TEST-123");
+        
+        let result = extractor(&json!({"issue": {}}), &HeaderMap::new());
+        assert!(matches!(result, Err(AppError::PrimaryKeyPathNotFound(_))));
+    }
+    
+    #[test]
+    fn test_get_primary_key_from_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Resource-Id", "res-42".parse().unwrap());
+        
+        let extractor = get_primary_key_by_paths(vec!["header:X-Resource-Id".to_string(), "issue.id".to_string()]);
+        
+        let pk_fields = extractor(&json!({"issue": {"id": "12345"}}), &headers).unwrap();
+        assert_eq!(pk_fields[0].key, "header:X-Resource-Id");
+        assert_eq!(pk_fields[0].value, "res-42");
+        
+        // Falls back to the body when the header is missing
+        let pk_fields = extractor(&json!({"issue": {"id": "12345"}}), &HeaderMap::new()).unwrap();
+        assert_eq!(pk_fields[0].key, "issue.id");
+    }
+    
+    #[test]
+    fn test_exceeds_depth() {
+        let nested = json!({"a": [{"b": 1}]});
+        
+        assert!(!exceeds_depth(&json!("scalar"), 0));
+        assert!(!exceeds_depth(&nested, 3));
+        assert!(exceeds_depth(&nested, 2));
+    }
+    
+    #[test]
+    fn test_extract_value_by_json_pointer() {
+        let body = json!({
+            "issue": {
+                "fields": {
+                    "status": { "name": "Open" },
+                    "team.name": "Platform",
+                    "a/b": "slash"
+                }
+            }
+        });
+        
+        assert_eq!(extract_value_by_path(&body, "/issue/fields/status/name").unwrap(), "Open");
+        assert_eq!(extract_value_by_path(&body, "/issue/fields/team.name").unwrap(), "Platform");
+        assert_eq!(extract_value_by_path(&body, "/issue/fields/a~1b").unwrap(), "slash");
+        assert!(extract_value_by_path(&body, "issue.fields.team.name").is_err());
+        assert!(extract_value_by_path(&body, "/issue/missing").is_err());
+    }
 }
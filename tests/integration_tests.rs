@@ -2,14 +2,22 @@ use connectcare::{
     config::{AppConfig, Integration, SourceConfig},
     sources::jira::{JiraSourceConfig, config::{JiraAuthentication}},
     config::secret::SecretSource,
-    pipeline::create_pipeline_channel,
+    config::Pipeline,
+    pipeline::{create_pipeline_channel, handoff::accept_handoffs, run_pipeline, event::{Operation, PipelineEvent}, executor::PipelineExecutor},
+    metrics::{self, MetricsConfig},
+    debug::FailureBuffer,
+    pipeline::sinks::ConnectRetry,
+    pipeline::status::{HealthConfig, PipelineStatus},
     server::routes::create_router,
+    sources::webhook::hmac::{SecretEncoding, SignatureFormat},
 };
 use axum::http::{Request, StatusCode};
 use axum::body::Body;
 use tower::ServiceExt;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -19,24 +27,67 @@ fn generate_signature(secret: &str, body: &[u8]) -> String {
     hex::encode(mac.finalize().into_bytes())
 }
 
-#[tokio::test]
-async fn test_end_to_end_jira_webhook() {
-    let config = AppConfig {
+fn test_app_config() -> AppConfig {
+    AppConfig {
         integrations: vec![Integration {
-            source: SourceConfig::Jira(JiraSourceConfig {
+            source: SourceConfig::Jira(Box::new(JiraSourceConfig {
                 webhook_path: Some("/jira/webhook".to_string()),
                 authentication: JiraAuthentication {
                     secret: SecretSource::Plain("integration_test_secret".to_string()),
                     header_name: "X-Hub-Signature".to_string(),
+                    fallback_header_names: vec![],
+                    query_param: None,
+                    signature_format: SignatureFormat::Prefixed,
+                    canonicalize_body: false,
+                    secret_encoding: SecretEncoding::Utf8,
+                    auth_bypass_secret: None,
+                    auth_failure_min_ms: None,
                 },
-            }),
+                primary_key_paths: HashMap::new(),
+                event_overrides: HashMap::new(),
+                only_changed_fields: false,
+                require_sink_ack: false,
+                enqueue_deadline_ms: None,
+                delivery_id_header: "X-Atlassian-Webhook-Identifier".to_string(),
+                allowed_events: vec![],
+                max_body_depth: 100,
+                body_transform: None,
+                fixed_event_type: None,
+                event_type_paths: vec!["webhookEvent".to_string()],
+                event_type_parts: vec![],
+                event_type_separator: ".".to_string(),
+                form_payload_field: None,
+                extract_paths: vec![],
+                required_headers: HashMap::new(),
+                idle_timeout_secs: None,
+            })),
+            default_processors: vec![],
             pipelines: vec![],
+            routes: HashMap::new(),
+            default_route: None,
         }],
-    };
+        base_path: None,
+        debug: None,
+        sink_connect_retry: ConnectRetry::default(),
+        tls: None,
+        dead_letter: None,
+        retry_queue: None,
+        health: HealthConfig::default(),
+        max_in_flight: None,
+        metrics: MetricsConfig::default(),
+        outbound_limit: None,
+    }
+}
+
+#[tokio::test]
+async fn test_end_to_end_jira_webhook() {
+    let config = test_app_config();
     
-    let (pipeline_tx, mut pipeline_rx) = create_pipeline_channel(100);
+    let (pipeline_tx, pipeline_rx) = create_pipeline_channel(100);
+    // Stands in for the executor
+    let mut pipeline_rx = accept_handoffs(pipeline_rx, 100);
     
-    let app = create_router(config, pipeline_tx).unwrap();
+    let app = create_router(config, pipeline_tx, Arc::default()).unwrap();
     
     // Test issue created event
     let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"99291","key":"PROJ-123","fields":{"summary":"Test Issue"}}}"#;
@@ -75,3 +126,407 @@ async fn test_end_to_end_jira_webhook() {
     
     assert_eq!(response.status(), StatusCode::OK);
 }
+
+#[tokio::test]
+async fn test_run_pipeline_filter_and_mapper() {
+    let pipeline: Pipeline = serde_json::from_value(serde_json::json!({
+        "processors": [
+            { "type": "filter", "celExpression": "eventType == 'jira:issue_created'" },
+            { "type": "mapper", "outputEvent": { "key": "{{ issue.key }}", "summary": "{{ issue.fields.summary }}" } }
+        ],
+        "sinks": []
+    })).unwrap();
+    
+    let body = serde_json::json!({"issue": {"key": "TEST-1", "fields": {"summary": "Hello"}}});
+    
+    let created = PipelineEvent::new(body.clone(), "jira:issue_created".to_string(), vec![], Operation::Write);
+    let output = run_pipeline(&pipeline, created).await.unwrap();
+    assert_eq!(output.len(), 1);
+    assert_eq!(*output[0].body, serde_json::json!({"key": "TEST-1", "summary": "Hello"}));
+    
+    let deleted = PipelineEvent::new(body, "jira:issue_deleted".to_string(), vec![], Operation::Delete);
+    assert!(run_pipeline(&pipeline, deleted).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_status_reports_backlog() {
+    // Nothing drains the receiver, so sent events stay queued and their webhooks keep waiting
+    let (pipeline_tx, _pipeline_rx) = create_pipeline_channel(10);
+    let app = create_router(test_app_config(), pipeline_tx, Arc::default()).unwrap();
+    
+    for id in ["1", "2", "3"] {
+        let body = format!(r#"{{"webhookEvent":"jira:issue_created","issue":{{"id":"{}","key":"PROJ-{}"}}}}"#, id, id);
+        let signature = generate_signature("integration_test_secret", body.as_bytes());
+        
+        tokio::spawn(app.clone().oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/jira/webhook")
+                .header("X-Hub-Signature", format!("sha256={}", signature))
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap(),
+        ));
+    }
+    
+    let status = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        loop {
+            let response = app.clone()
+                .oneshot(Request::builder().method("GET").uri("/-/status").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let status: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            if status["backlog"]["depth"] == 3 {
+                return status;
+            }
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .expect("events were not queued");
+    
+    assert_eq!(status["backlog"], serde_json::json!({"depth": 3, "capacity": 10}));
+    assert_eq!(status["processed"], 0);
+    assert_eq!(status["dropped"], 0);
+}
+
+#[tokio::test]
+async fn test_closed_pipeline_counts_send_failure_and_fails_readiness() {
+    // Dropping the receiver stands in for an executor task that has panicked
+    let (pipeline_tx, pipeline_rx) = create_pipeline_channel(10);
+    drop(pipeline_rx);
+    let app = create_router(test_app_config(), pipeline_tx, Arc::default()).unwrap();
+    
+    let failures = || metrics::global().counter("connectcare_pipeline_send_failures_total", &[("source", "jira")]);
+    let before = failures();
+    
+    let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"PROJ-1"}}"#;
+    let signature = generate_signature("integration_test_secret", body.as_bytes());
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/jira/webhook")
+                .header("X-Hub-Signature", format!("sha256={}", signature))
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(failures(), before + 1);
+    
+    let response = app
+        .oneshot(Request::builder().uri("/-/ready").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn test_aborted_executor_fails_health_check() {
+    let config = test_app_config();
+    let (pipeline_tx, pipeline_rx) = create_pipeline_channel(10);
+    let executor = PipelineExecutor::new(&config).await.unwrap();
+    let status = executor.status();
+    let handle = tokio::spawn(executor.run(pipeline_rx));
+    let app = create_router(config, pipeline_tx, status.clone()).unwrap();
+    
+    let healthz = || async {
+        app.clone()
+            .oneshot(Request::builder().uri("/-/healthz").body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    };
+    
+    // The first heartbeat is sent as soon as the executor starts
+    while status.heartbeat_age().is_none() {
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    }
+    assert_eq!(healthz().await, StatusCode::OK);
+    
+    handle.abort();
+    assert!(handle.await.unwrap_err().is_cancelled());
+    assert_eq!(healthz().await, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn test_stale_executor_heartbeat_fails_health_check() {
+    let mut config = test_app_config();
+    config.health.heartbeat_timeout_ms = 10;
+    let (pipeline_tx, _pipeline_rx) = create_pipeline_channel(10);
+    let status = Arc::new(PipelineStatus::default());
+    let app = create_router(config, pipeline_tx, status.clone()).unwrap();
+    
+    status.record_heartbeat();
+    tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+    
+    let response = app
+        .oneshot(Request::builder().uri("/-/healthz").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn test_base_path_prefixes_webhook_routes() {
+    let mut config = test_app_config();
+    config.base_path = Some("/webhooks/".to_string());
+    
+    let (pipeline_tx, pipeline_rx) = create_pipeline_channel(10);
+    // Stands in for the executor
+    let mut pipeline_rx = accept_handoffs(pipeline_rx, 10);
+    let app = create_router(config, pipeline_tx, Arc::default()).unwrap();
+    
+    let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"PROJ-1"}}"#;
+    let signature = generate_signature("integration_test_secret", body.as_bytes());
+    
+    for (uri, expected) in [("/webhooks/jira/webhook", StatusCode::OK), ("/jira/webhook", StatusCode::NOT_FOUND)] {
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header("X-Hub-Signature", format!("sha256={}", signature))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected, "POST {}", uri);
+    }
+    
+    assert_eq!(pipeline_rx.recv().await.unwrap().pk_fields[0].value, "1");
+    
+    let response = app
+        .oneshot(Request::builder().method("GET").uri("/-/healthz").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_duplicate_webhook_paths_are_rejected() {
+    let mut config = test_app_config();
+    let mut second = config.integrations[0].clone();
+    if let SourceConfig::Jira(jira) = &mut second.source {
+        // Same as the first integration's default path once the base path is applied
+        jira.webhook_path = Some("jira/webhook".to_string());
+    }
+    config.integrations.push(second);
+    config.base_path = Some("/webhooks".to_string());
+    
+    let (pipeline_tx, _pipeline_rx) = create_pipeline_channel(10);
+    let error = create_router(config, pipeline_tx, Arc::default()).err().unwrap();
+    
+    assert_eq!(error.to_string(), "Configuration error: duplicate webhook path: /webhooks/jira/webhook");
+}
+
+#[tokio::test]
+async fn test_webhook_path_clashing_with_an_ops_route_is_rejected() {
+    let mut config = test_app_config();
+    if let SourceConfig::Jira(jira) = &mut config.integrations[0].source {
+        jira.webhook_path = Some("/-/healthz".to_string());
+    }
+    
+    let (pipeline_tx, _pipeline_rx) = create_pipeline_channel(10);
+    let error = create_router(config, pipeline_tx, Arc::default()).err().unwrap();
+    
+    assert_eq!(error.to_string(), "Configuration error: duplicate webhook path: /-/healthz");
+}
+
+#[tokio::test]
+async fn test_processing_failure_is_captured_for_debugging() {
+    let mut config = test_app_config();
+    config.debug = serde_json::from_value(serde_json::json!({
+        "token": "debug_token",
+        "redact_paths": ["issue.fields.reporter.emailAddress"]
+    })).unwrap();
+    config.integrations[0].pipelines.push(serde_json::from_value(serde_json::json!({
+        "processors": [
+            { "type": "mapper", "outputEvent": { "key": { "value": "{{ issue.key }}", "castTo": "integer" } } }
+        ],
+        "sinks": []
+    })).unwrap());
+    
+    let _failures = FailureBuffer::scoped();
+    
+    let (pipeline_tx, pipeline_rx) = create_pipeline_channel(10);
+    let executor = PipelineExecutor::new(&config).await.unwrap();
+    let app = create_router(config, pipeline_tx, executor.status()).unwrap();
+    tokio::spawn(executor.run(pipeline_rx));
+    
+    let body = r#"{"webhookEvent":"jira:issue_created","issue":{"id":"1","key":"PROJ-1","fields":{"reporter":{"emailAddress":"jane@example.com"}}}}"#;
+    let signature = generate_signature("integration_test_secret", body.as_bytes());
+    
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/jira/webhook")
+                .header("X-Hub-Signature", format!("sha256={}", signature))
+                .header("content-type", "application/json")
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    
+    let unauthorized = app.clone()
+        .oneshot(Request::builder().uri("/-/debug/failures").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(unauthorized.status(), StatusCode::UNAUTHORIZED);
+    
+    let failure = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        loop {
+            let response = app.clone()
+                .oneshot(
+                    Request::builder()
+                        .uri("/-/debug/failures")
+                        .header("X-Debug-Token", "debug_token")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let records: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+            
+            if let Some(record) = records.into_iter().next() {
+                return record;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("failure was not captured");
+    
+    assert_eq!(failure["stage"], "pipeline 0");
+    assert_eq!(failure["event_type"], "jira:issue_created");
+    assert!(failure["error"].as_str().unwrap().contains("integer"));
+    assert_eq!(failure["body"]["issue"]["fields"]["reporter"]["emailAddress"], "[REDACTED]");
+}
+
+#[tokio::test]
+async fn test_event_stream_tails_processed_events() {
+    use futures_util::StreamExt;
+    
+    let mut config = test_app_config();
+    config.debug = serde_json::from_value(serde_json::json!({ "token": "debug_token" })).unwrap();
+    config.integrations[0].pipelines.push(serde_json::from_value(serde_json::json!({
+        "processors": [
+            { "type": "mapper", "outputEvent": { "key": "{{ issue.key }}" } }
+        ],
+        "sinks": []
+    })).unwrap());
+    
+    let (pipeline_tx, pipeline_rx) = create_pipeline_channel(10);
+    let executor = PipelineExecutor::new(&config).await.unwrap();
+    let app = create_router(config, pipeline_tx, executor.status()).unwrap();
+    tokio::spawn(executor.run(pipeline_rx));
+    
+    let stream_request = |token: &str| {
+        Request::builder()
+            .uri("/-/events/stream?event_type=jira:issue_updated")
+            .header("X-Debug-Token", token)
+            .body(Body::empty())
+            .unwrap()
+    };
+    let unauthorized = app.clone().oneshot(stream_request("wrong")).await.unwrap();
+    assert_eq!(unauthorized.status(), StatusCode::UNAUTHORIZED);
+    
+    let response = app.clone().oneshot(stream_request("debug_token")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut stream = response.into_body().into_data_stream();
+    
+    for (event_type, key) in [("jira:issue_created", "PROJ-192"), ("jira:issue_updated", "PROJ-193")] {
+        let body = format!(r#"{{"webhookEvent":"{}","issue":{{"id":"193","key":"{}"}}}}"#, event_type, key);
+        let signature = generate_signature("integration_test_secret", body.as_bytes());
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/jira/webhook")
+                    .header("X-Hub-Signature", format!("sha256={}", signature))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    
+    // Other tests may run pipelines at the same time, so skip events that aren't ours
+    let streamed = tokio::time::timeout(std::time::Duration::from_secs(5), async {
+        loop {
+            let chunk = stream.next().await.unwrap().unwrap();
+            let text = String::from_utf8(chunk.to_vec()).unwrap();
+            let Some(data) = text.lines().find_map(|line| line.strip_prefix("data: ")) else {
+                continue;
+            };
+            let event: serde_json::Value = serde_json::from_str(data).unwrap();
+            if event["body"]["key"].as_str().is_some_and(|key| key.starts_with("PROJ-19")) {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("event was not streamed");
+    
+    // The created event was filtered out, and the body is the mapped one
+    assert_eq!(streamed["event_type"], "jira:issue_updated");
+    assert_eq!(streamed["operation"], "write");
+    assert_eq!(streamed["body"], serde_json::json!({ "key": "PROJ-193" }));
+}
+
+#[tokio::test]
+async fn test_routes_endpoint_lists_webhooks_and_events() {
+    let mut config = test_app_config();
+    config.debug = serde_json::from_value(serde_json::json!({ "token": "debug_token" })).unwrap();
+    if let SourceConfig::Jira(jira) = &mut config.integrations[0].source {
+        jira.primary_key_paths.insert("jira:issue_deleted".to_string(), vec!["issue.key".to_string()]);
+    }
+    
+    let (pipeline_tx, _pipeline_rx) = create_pipeline_channel(10);
+    let app = create_router(config, pipeline_tx, Arc::default()).unwrap();
+    
+    let response = app.clone()
+        .oneshot(Request::builder().uri("/-/routes").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    
+    let response = app
+        .oneshot(Request::builder().uri("/-/routes").header("X-Debug-Token", "debug_token").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let routes: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    
+    assert_eq!(routes[0]["source_type"], "jira");
+    assert_eq!(routes[0]["paths"], serde_json::json!(["/jira/webhook"]));
+    
+    let events = routes[0]["events"].as_array().unwrap();
+    let event = |event_type: &str| events.iter().find(|event| event["event_type"] == event_type).cloned();
+    assert_eq!(
+        event("jira:issue_created"),
+        Some(serde_json::json!({"event_type": "jira:issue_created", "pk_paths": ["issue.id"], "operation": "write"}))
+    );
+    assert_eq!(
+        event("jira:issue_deleted"),
+        Some(serde_json::json!({"event_type": "jira:issue_deleted", "pk_paths": ["issue.key"], "operation": "delete"}))
+    );
+}